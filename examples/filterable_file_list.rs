@@ -52,13 +52,12 @@ impl App {
         let words: Vec<&str> = self.input.split_whitespace().collect();
         
         for word in words {
-            if let Some(search_str) = word.strip_prefix('@') {
-                if !search_str.is_empty() {
-                    // Find the position of this word in the original string
-                    if let Some(pos) = self.input.find(word) {
-                        return Some((search_str.to_string(), pos, pos + word.len()));
-                    }
-                }
+            if let Some(search_str) = word.strip_prefix('@')
+                && !search_str.is_empty()
+                // Find the position of this word in the original string
+                && let Some(pos) = self.input.find(word)
+            {
+                return Some((search_str.to_string(), pos, pos + word.len()));
             }
         }
         None
@@ -244,15 +243,14 @@ fn run_app<B: ratatui::backend::Backend>(
                 KeyCode::Up => app.move_selection_up(),
                 KeyCode::Down => app.move_selection_down(),
                 KeyCode::Enter => {
-                    if let Some(file) = app.filtered_files.get(app.selected_index) {
-                        if let Some((_, start, end)) = app.get_current_search() {
-                            if let Some(filename) = file.file_name() {
-                                let filename_str = format!("@{}", filename.to_string_lossy());
-                                app.input.replace_range(start..end, &filename_str);
-                                app.cursor_position = start + filename_str.len();
-                                app.update_filter();
-                            }
-                        }
+                    if let Some(file) = app.filtered_files.get(app.selected_index)
+                        && let Some((_, start, end)) = app.get_current_search()
+                        && let Some(filename) = file.file_name()
+                    {
+                        let filename_str = format!("@{}", filename.to_string_lossy());
+                        app.input.replace_range(start..end, &filename_str);
+                        app.cursor_position = start + filename_str.len();
+                        app.update_filter();
                     }
                 }
                 _ => {}
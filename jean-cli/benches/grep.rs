@@ -0,0 +1,127 @@
+//! Compares the parallel `grep-searcher` engine against the previous
+//! sequential implementation (tokio line reader + sliding window) on a
+//! generated source tree.
+//!
+//! Run with `cargo bench -p jean-cli --bench grep`. The tree size can be
+//! tuned with `JEAN_BENCH_FILES` and `JEAN_BENCH_LINES`.
+
+use jean_cli::tools::grep::{self, GrepArgs};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 5;
+
+fn main() {
+    let files = env_usize("JEAN_BENCH_FILES", 4_000);
+    let lines = env_usize("JEAN_BENCH_LINES", 400);
+
+    let dir = tempfile::tempdir().expect("create temp dir");
+    generate_tree(dir.path(), files, lines);
+    println!(
+        "generated {} files x {} lines under {}",
+        files,
+        lines,
+        dir.path().display()
+    );
+
+    let runtime = tokio::runtime::Runtime::new().expect("build tokio runtime");
+
+    for (search_term, filter) in [("fn handle_\\w+", "**/*.rs"), ("TODO", "*")] {
+        let args = GrepArgs {
+            search_term: search_term.to_string(),
+            filter: filter.to_string(),
+            context_lines: 2,
+        };
+
+        let (naive, naive_count) = time(|| runtime.block_on(naive_grep(dir.path(), &args)));
+        let (parallel, parallel_count) = time(|| {
+            let files = grep::search(dir.path(), &args).expect("search");
+            files.iter().map(|f| f.match_count).sum()
+        });
+
+        assert_eq!(naive_count, parallel_count, "engines disagree on match count");
+        println!(
+            "'{}' in '{}': {} matches | sequential {:>8.2?} | parallel {:>8.2?} | {:.1}x",
+            search_term,
+            filter,
+            parallel_count,
+            naive,
+            parallel,
+            naive.as_secs_f64() / parallel.as_secs_f64()
+        );
+    }
+}
+
+fn env_usize(name: &str, default: usize) -> usize {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+/// Average wall time over `ITERATIONS` runs, plus the last result
+fn time(mut f: impl FnMut() -> usize) -> (Duration, usize) {
+    let mut result = 0;
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        result = f();
+    }
+    (start.elapsed() / ITERATIONS, result)
+}
+
+fn generate_tree(root: &Path, files: usize, lines: usize) {
+    for i in 0..files {
+        let dir = root.join(format!("crate_{}/src/module_{}", i % 40, i % 7));
+        fs::create_dir_all(&dir).expect("create dir");
+        let ext = if i % 3 == 0 { "txt" } else { "rs" };
+        let mut file = fs::File::create(dir.join(format!("file_{}.{}", i, ext))).expect("create file");
+        for line in 0..lines {
+            match line % 97 {
+                0 => writeln!(file, "pub fn handle_request_{}(input: &str) -> String {{", line),
+                50 if i % 5 == 0 => writeln!(file, "    // TODO: revisit allocation in step {}", line),
+                _ => writeln!(file, "    let value_{} = compute(\"{}\", {});", line, i, line * 31),
+            }
+            .expect("write line");
+        }
+    }
+}
+
+/// A condensed copy of the `execute_grep` used before the grep-searcher rewrite,
+/// kept here as the baseline. Returns the number of matching lines.
+async fn naive_grep(root: &Path, args: &GrepArgs) -> usize {
+    use ignore::WalkBuilder;
+    use tokio::io::{AsyncBufReadExt, BufReader};
+
+    let regex = regex::Regex::new(&args.search_term).expect("valid regex");
+    let glob = globset::Glob::new(&args.filter)
+        .expect("valid glob")
+        .compile_matcher();
+
+    let mut matches = 0;
+    for entry in WalkBuilder::new(root).build().flatten() {
+        let path = entry.path();
+        if !path.is_file() || !glob.is_match(path.strip_prefix(root).unwrap_or(path)) {
+            continue;
+        }
+
+        let Ok(file) = tokio::fs::File::open(path).await else {
+            continue;
+        };
+        let mut lines_reader = BufReader::new(file).lines();
+        let mut lines_buffer: Vec<String> = Vec::new();
+
+        while let Ok(Some(line)) = lines_reader.next_line().await {
+            lines_buffer.push(line.clone());
+            if lines_buffer.len() > args.context_lines + 1 {
+                lines_buffer.remove(0);
+            }
+            if regex.is_match(&line) {
+                matches += 1;
+                let _context: Vec<String> = lines_buffer.to_vec();
+            }
+        }
+    }
+    matches
+}
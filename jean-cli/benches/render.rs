@@ -0,0 +1,124 @@
+//! Per-frame cost of drawing the chat while a response streams in, for
+//! growing transcripts. The cached `ChatView` should stay flat as history
+//! grows; the baseline, which rebuilds and wraps every line each frame the
+//! way the chat was drawn before, grows with it.
+//!
+//! Run with `cargo bench -p jean-cli --bench render`. The frames measured
+//! per size can be tuned with `JEAN_BENCH_FRAMES`.
+
+use jean_cli::chat_view::ChatView;
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Paragraph, Wrap};
+use std::time::{Duration, Instant};
+
+const HISTORY_SIZES: [usize; 4] = [100, 1_000, 10_000, 50_000];
+/// The baseline gets slow; it is skipped above this many messages
+const MAX_BASELINE_MESSAGES: usize = 10_000;
+const WIDTH: u16 = 120;
+const HEIGHT: u16 = 40;
+
+fn main() {
+    let frames = std::env::var("JEAN_BENCH_FRAMES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(200);
+
+    for messages in HISTORY_SIZES {
+        let transcript: Vec<String> = (0..messages).map(message).collect();
+
+        let (first, cached) = bench_cached(&transcript, frames);
+        let baseline = if messages <= MAX_BASELINE_MESSAGES {
+            format!("{:>10.2?}/frame", bench_baseline(&transcript, frames.min(20)))
+        } else {
+            format!("{:>10}", "skipped")
+        };
+        println!(
+            "{:>6} messages | first frame {:>10.2?} | cached {:>10.2?}/frame | baseline {}",
+            messages, first, cached, baseline
+        );
+    }
+}
+
+/// Time of the first frame, which lays out the whole transcript, and the
+/// average of the frames after it
+fn bench_cached(transcript: &[String], frames: u32) -> (Duration, Duration) {
+    let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).expect("test terminal");
+    let mut view = ChatView::default();
+    let mut streaming = String::new();
+    let mut draw = |view: &mut ChatView, streaming: &str| {
+        terminal
+            .draw(|f| {
+                view.render(
+                    f,
+                    f.area(),
+                    transcript.len(),
+                    |index| message_lines(&transcript[index]),
+                    |_, _, _| {},
+                    message_lines(streaming),
+                )
+            })
+            .expect("draw");
+    };
+
+    let start = Instant::now();
+    draw(&mut view, &streaming);
+    let first = start.elapsed();
+
+    let start = Instant::now();
+    for frame in 0..frames {
+        streaming.push_str(&format!("token{} ", frame));
+        draw(&mut view, &streaming);
+    }
+    (first, start.elapsed() / frames)
+}
+
+/// The chat as drawn before the render cache: every line rebuilt and
+/// cloned each frame, wrapped as one paragraph
+fn bench_baseline(transcript: &[String], frames: u32) -> Duration {
+    let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).expect("test terminal");
+    let mut streaming = String::new();
+
+    let start = Instant::now();
+    for frame in 0..frames {
+        streaming.push_str(&format!("token{} ", frame));
+        terminal
+            .draw(|f| {
+                let mut lines = Vec::new();
+                for message in transcript {
+                    lines.extend(message_lines(message));
+                }
+                lines.extend(message_lines(&streaming));
+                let paragraph = Paragraph::new(lines.clone()).wrap(Wrap { trim: false });
+                let rows = paragraph.line_count(f.area().width);
+                let top = rows.saturating_sub(f.area().height as usize);
+                f.render_widget(paragraph.scroll((top.min(u16::MAX as usize) as u16, 0)), f.area());
+            })
+            .expect("draw");
+    }
+    start.elapsed() / frames
+}
+
+fn message_lines(content: &str) -> Vec<Line<'static>> {
+    let style = Style::default().fg(Color::Green);
+    let mut lines = vec![Line::from(Span::styled("Assistant:", style))];
+    lines.extend(content.lines().map(|line| Line::from(Span::styled(line.to_string(), style))));
+    lines.push(Line::from(""));
+    lines
+}
+
+/// A message of a few lines, some long enough to wrap
+fn message(i: usize) -> String {
+    let mut content = format!("Message {} looks at `src/module_{}.rs`.\n", i, i % 17);
+    for line in 0..(i % 5) + 1 {
+        content.push_str(&format!(
+            "Line {} of message {} says something {}\n",
+            line,
+            i,
+            "long enough to wrap at the bench width ".repeat(line % 4)
+        ));
+    }
+    content
+}
@@ -0,0 +1,1380 @@
+use crate::chat_view::ChatView;
+use crate::checkpoints::CheckpointStore;
+use crate::client::{BackendClient, ConnectionStatus};
+use crate::clipboard;
+use crate::commands::{self, Command, CommandRegistry};
+use crate::config::Config;
+use crate::conversation_logger::ConversationLogger;
+use crate::diff::{DiffViewer, FileChange, ViewerOutcome};
+use crate::history::{HistorySearch, PromptHistory};
+use crate::input::InputEditor;
+use crate::keymap::{Action, Keymap};
+use crate::markdown;
+use crate::mentions::{self, MentionPicker};
+use crate::search::TranscriptSearch;
+use crate::sessions::{self, SessionStore};
+use crate::status::WorkspaceInfo;
+use crate::theme;
+use crate::tools::{self, execute_tool, session_context};
+use crate::transcript::{Thinking, ToolRun, TranscriptItem};
+use crate::turn::{ChunkAction, Turn, TurnState};
+use crate::ui::{item_lines, thinking_lines, tool_run_lines};
+use crate::usage::UsageTracker;
+use crate::vim::{Vim, VimMode, VimOutcome};
+use crossterm::event::{
+    DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers,
+    MouseEventKind,
+};
+use crossterm::execute;
+use jean_shared::{ChatMessage, ClientChatRequest, MessageRole, ReasoningEffort, StreamChunk};
+use std::collections::VecDeque;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info};
+
+/// What copy mode (Ctrl-Y) has selected: a transcript item, or one of the
+/// code blocks in an assistant message
+pub struct CopySelection {
+    pub item: usize,
+    pub block: Option<usize>,
+    /// Lines of the item to highlight, all of them when `None`
+    pub lines: Option<Range<usize>>,
+}
+
+/// How long a first Ctrl-C waits for the second one that quits
+pub const QUIT_CONFIRM_WINDOW: Duration = Duration::from_secs(2);
+
+/// Where checkpoint stores are kept, in the logger's directory
+const CHECKPOINT_DIR: &str = "checkpoints";
+
+/// System messages starting with one of these are shown in the TUI only and
+/// never sent to the model
+const UI_ONLY_PREFIXES: [&str; 1] = ["[Command]"];
+
+fn is_ui_only(msg: &ChatMessage) -> bool {
+    msg.role == MessageRole::System && UI_ONLY_PREFIXES.iter().any(|p| msg.content.starts_with(p))
+}
+
+/// The first `max_bytes` of `s`, cut back to a character boundary
+fn preview(s: &str, max_bytes: usize) -> &str {
+    &s[..s.floor_char_boundary(max_bytes)]
+}
+
+pub struct App {
+    pub transcript: Vec<TranscriptItem>,
+    /// Index in `transcript` of the tool run or thinking section that
+    /// Ctrl-O expands
+    pub selected_tool: Option<usize>,
+    pub input: InputEditor,
+    pub chat_view: ChatView,
+    pub connection_status: ConnectionStatus,
+    pub turn: Turn,
+    /// Text of the response being streamed, moved to the transcript when
+    /// the response ends or asks for tools
+    pub streaming_text: String,
+    /// Reasoning streamed ahead of the response, moved to the transcript
+    /// with it
+    pub streaming_reasoning: String,
+    pub logger: ConversationLogger,
+    pub checkpoints: CheckpointStore,
+    pub commands: CommandRegistry,
+    pub sessions: SessionStore,
+    pub usage: UsageTracker,
+    /// Model picked with /model; the server's default when `None`
+    pub model: Option<String>,
+    /// Reasoning effort picked with /effort or in the config; the model's
+    /// default when `None`
+    pub reasoning_effort: Option<ReasoningEffort>,
+    pub history: PromptHistory,
+    /// Active Ctrl-R search, which takes over key handling until closed
+    pub history_search: Option<HistorySearch>,
+    pub search: Option<TranscriptSearch>,
+    pub copy_selection: Option<CopySelection>,
+    pub mouse_captured: bool,
+    pub workspace: WorkspaceInfo,
+    /// When the turn in progress started
+    pub turn_started: Option<Instant>,
+    pub keymap: Keymap,
+    /// Modal editing of the prompt, when enabled in the config
+    pub vim: Option<Vim>,
+    /// When Ctrl-C was pressed with nothing to cancel; another press within
+    /// `QUIT_CONFIRM_WINDOW` quits
+    pub quit_armed_at: Option<Instant>,
+    /// File picker for the `@mention` under the cursor
+    pub mention_picker: Option<MentionPicker>,
+    /// Position of a mention whose picker was closed with Esc, so it stays
+    /// closed while that mention is edited
+    pub dismissed_mention: Option<usize>,
+    /// Edits wait for approval in the diff viewer before they run
+    pub approve_edits: bool,
+    /// Open over the chat, for `/diff` or an edit awaiting approval
+    pub diff_viewer: Option<DiffViewer>,
+    /// Tool runs received but not started yet, as transcript indices
+    pub tool_queue: VecDeque<usize>,
+    /// The tool run shown in the diff viewer for approval
+    pub awaiting_approval: Option<usize>,
+    /// Prompts submitted during a turn, sent once it's over
+    pub queued: VecDeque<String>,
+    /// Send queued prompts with the next tool result instead of waiting
+    /// for the turn to end
+    pub steer: bool,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl App {
+    /// The app as `jean` runs it, with settings from the config file
+    pub fn new() -> Self {
+        let logger = ConversationLogger::new().unwrap_or_else(|e| {
+            error!("Failed to create conversation logger: {}", e);
+            ConversationLogger::default()
+        });
+        let (config, config_error) = Config::load();
+        let mut app = Self::with_logger(logger, &config);
+        if let Some(e) = config_error {
+            app.add_command_output(e);
+        }
+        app
+    }
+
+    /// The app keeping its logs, checkpoints, sessions and prompt history
+    /// in the logger's directory
+    pub fn with_logger(logger: ConversationLogger, config: &Config) -> Self {
+        if let Some(path) = logger.get_current_log_path() {
+            info!("Logging conversation to: {:?}", path);
+        }
+
+        // Carry on with the last conversation's checkpoints, so a restart
+        // doesn't lose its undo; /clear and /load start new ones
+        let conversation = logger
+            .get_current_log_path()
+            .and_then(Path::file_stem)
+            .map_or_else(|| "conversation".to_string(), |stem| stem.to_string_lossy().into_owned());
+        let checkpoints = CheckpointStore::open_latest(&logger.log_dir().join(CHECKPOINT_DIR), &conversation)
+            .unwrap_or_else(|e| {
+                error!("Failed to open checkpoint store: {}", e);
+                CheckpointStore::default()
+            });
+        let commands = CommandRegistry::load(Path::new(commands::TEMPLATE_DIR));
+        let sessions = SessionStore::new(logger.log_dir().join("sessions"));
+        let history = PromptHistory::load(logger.log_dir().join("prompt_history.jsonl"));
+
+        let (keymap, key_warnings) = Keymap::new(&config.keys);
+        let theme_warnings = theme::init(config.theme.as_deref(), &config.themes);
+        let mut chat_view = ChatView::default();
+        chat_view.notice_style = theme::current().notice;
+
+        let mut app = Self {
+            transcript: vec![],
+            selected_tool: None,
+            input: InputEditor::new(),
+            chat_view,
+            connection_status: ConnectionStatus::Disconnected,
+            turn: Turn::default(),
+            streaming_text: String::new(),
+            streaming_reasoning: String::new(),
+            logger,
+            checkpoints,
+            commands,
+            sessions,
+            usage: UsageTracker::default(),
+            model: None,
+            reasoning_effort: config.reasoning_effort,
+            history,
+            history_search: None,
+            search: None,
+            copy_selection: None,
+            mouse_captured: true,
+            workspace: WorkspaceInfo::new(),
+            turn_started: None,
+            keymap,
+            vim: config.vim.then(Vim::new),
+            quit_armed_at: None,
+            mention_picker: None,
+            dismissed_mention: None,
+            approve_edits: config.approve_edits,
+            diff_viewer: None,
+            tool_queue: VecDeque::new(),
+            awaiting_approval: None,
+            queued: VecDeque::new(),
+            steer: config.steer,
+        };
+
+        tools::refresh_git_status();
+        if !key_warnings.is_empty() {
+            app.add_command_output(format!("Ignored key bindings:\n{}", key_warnings.join("\n")));
+        }
+        if !theme_warnings.is_empty() {
+            app.add_command_output(format!("Theme problems:\n{}", theme_warnings.join("\n")));
+        }
+        app
+    }
+
+    fn add_user_message(&mut self, content: String) {
+        let message = ChatMessage {
+            role: MessageRole::User,
+            content,
+            tool_call_id: None,
+            tool_calls: None,
+        };
+
+        // Log the message
+        if let Err(e) = self.logger.log_message(&message) {
+            error!("Failed to log user message: {}", e);
+        }
+
+        self.push_message(message);
+    }
+
+    fn add_command_output(&mut self, content: String) {
+        let message = ChatMessage {
+            role: MessageRole::System,
+            content: format!("[Command] {}", content),
+            tool_call_id: None,
+            tool_calls: None,
+        };
+
+        if let Err(e) = self.logger.log_message(&message) {
+            error!("Failed to log command output: {}", e);
+        }
+
+        self.push_message(message);
+    }
+
+    fn push_message(&mut self, message: ChatMessage) {
+        self.transcript.push(TranscriptItem::Message(message));
+        self.chat_view.note_output();
+    }
+
+    /// Messages for the model: the transcript without tool runs, thinking
+    /// and UI-only output
+    fn messages_to_send(&self) -> Vec<ChatMessage> {
+        self.transcript
+            .iter()
+            .filter_map(|item| match item {
+                TranscriptItem::Message(msg) if !is_ui_only(msg) => Some(msg.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn tool_run_mut(&mut self, index: usize) -> Option<&mut ToolRun> {
+        self.chat_view.invalidate(index);
+        match self.transcript.get_mut(index) {
+            Some(TranscriptItem::Tool(run)) => Some(run),
+            _ => None,
+        }
+    }
+
+    /// Move the tool selection to the previous (older) or next tool run or
+    /// thinking section
+    fn select_tool(&mut self, older: bool) {
+        let mut tools = self
+            .transcript
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| matches!(item, TranscriptItem::Tool(_) | TranscriptItem::Thinking(_)))
+            .map(|(i, _)| i);
+        let selected = match (self.selected_tool, older) {
+            (None, _) => tools.next_back(),
+            (Some(current), true) => tools.rfind(|&i| i < current).or(Some(current)),
+            (Some(current), false) => tools.find(|&i| i > current).or(Some(current)),
+        };
+        // The selected run is drawn highlighted with a hint
+        for index in [self.selected_tool, selected].into_iter().flatten() {
+            self.chat_view.invalidate(index);
+        }
+        self.selected_tool = selected;
+    }
+
+    /// Expand or collapse the selected tool run or thinking section, or
+    /// the latest one
+    fn toggle_tool_output(&mut self) {
+        if self.selected_tool.is_none() {
+            self.select_tool(true);
+        }
+        let Some(index) = self.selected_tool else {
+            return;
+        };
+        self.chat_view.invalidate(index);
+        match self.transcript.get_mut(index) {
+            Some(TranscriptItem::Tool(run)) => run.expanded = !run.expanded,
+            Some(TranscriptItem::Thinking(thinking)) => thinking.expanded = !thinking.expanded,
+            _ => {}
+        }
+    }
+
+    /// Run a local `/command`. Returns the prompt to send to the model:
+    /// the input itself when it isn't a command, the expanded template for
+    /// user-defined commands and `None` for everything else.
+    fn handle_command(&mut self, input: &str) -> Option<String> {
+        let command = match self.commands.parse(input) {
+            None => return Some(input.to_string()),
+            Some(Ok(Command::Prompt(prompt))) => return Some(prompt),
+            Some(Ok(command)) => command,
+            Some(Err(e)) => {
+                self.add_command_output(e);
+                return None;
+            }
+        };
+
+        let output = match command {
+            Command::Clear | Command::Load(Some(_)) | Command::Undo | Command::RestoreCheckpoint(_)
+                if self.turn.in_progress() =>
+            {
+                "Wait for the current response to finish first".to_string()
+            }
+            Command::Clear => {
+                self.new_checkpoints();
+                self.search = None;
+                self.copy_selection = None;
+                self.transcript.clear();
+                self.selected_tool = None;
+                self.chat_view.reset();
+                "Started a new conversation".to_string()
+            }
+            Command::Model(None) => match (&self.model, &self.usage.last_model) {
+                (Some(model), _) => format!("Using model {}", model),
+                (None, Some(model)) => format!("Using the server's default model ({})", model),
+                (None, None) => "Using the server's default model".to_string(),
+            },
+            Command::Model(Some(model)) => {
+                let output = format!("Switched to model {} for new requests", model);
+                self.model = Some(model);
+                output
+            }
+            Command::Effort(None) => match &self.reasoning_effort {
+                Some(effort) => format!("Using {} reasoning effort", effort),
+                None => "Using the model's default reasoning effort".to_string(),
+            },
+            Command::Effort(Some(effort)) => {
+                let output = format!("Using {} reasoning effort for new requests", effort);
+                self.reasoning_effort = Some(effort);
+                output
+            }
+            Command::DefaultEffort => {
+                self.reasoning_effort = None;
+                "Using the model's default reasoning effort for new requests".to_string()
+            }
+            Command::Cost => self.usage.summary(),
+            Command::Save(name) => match self.sessions.save(name.as_deref(), &self.transcript) {
+                Ok(name) => format!("Saved conversation as '{}' (load it with /load {})", name, name),
+                Err(e) => format!("Save failed: {:#}", e),
+            },
+            Command::Load(None) => {
+                let saved = self.sessions.list();
+                if saved.is_empty() {
+                    "No saved conversations".to_string()
+                } else {
+                    format!("Saved conversations (load with /load <name>):\n  {}", saved.join("\n  "))
+                }
+            }
+            Command::Load(Some(name)) => match self.sessions.load(&name) {
+                Ok(transcript) => {
+                    self.new_checkpoints();
+                    self.search = None;
+                    self.copy_selection = None;
+                    self.transcript = transcript;
+                    self.selected_tool = None;
+                    self.chat_view.reset();
+                    format!("Loaded conversation '{}'", name)
+                }
+                Err(e) => format!("Load failed: {:#}", e),
+            },
+            Command::Export(path) => match sessions::export_markdown(path.as_deref(), &self.transcript) {
+                Ok(path) => format!("Exported conversation to {}", path.display()),
+                Err(e) => format!("Export failed: {:#}", e),
+            },
+            Command::Undo => match self.checkpoints.undo() {
+                Ok(Some(checkpoint)) => format!(
+                    "Reverted {} file(s) changed by turn {}: {}",
+                    checkpoint.files.len(),
+                    checkpoint.id,
+                    checkpoint.prompt
+                ),
+                Ok(None) => "Nothing to undo".to_string(),
+                Err(e) => format!("Undo failed: {}", e),
+            },
+            Command::Diff => self.show_session_diff(),
+            Command::Checkpoints => self.list_checkpoints(),
+            Command::RestoreCheckpoint(id) => match self.checkpoints.restore(id) {
+                Ok(reverted) if reverted.is_empty() => {
+                    format!("Checkpoint {} is already restored", id)
+                }
+                Ok(reverted) => format!(
+                    "Restored workspace to before turn {} (reverted {} turn(s))",
+                    id,
+                    reverted.len()
+                ),
+                Err(e) => format!("Restore failed: {}", e),
+            },
+            Command::Help => format!("{}\n\n{}", self.commands.help(), self.keymap.help()),
+            Command::Prompt(_) => unreachable!("handled above"),
+        };
+
+        self.add_command_output(output);
+        None
+    }
+
+    /// A new conversation doesn't undo the edits of the one before it
+    fn new_checkpoints(&mut self) {
+        match CheckpointStore::open_new(&self.logger.log_dir().join(CHECKPOINT_DIR)) {
+            Ok(checkpoints) => self.checkpoints = checkpoints,
+            Err(e) => error!("Failed to open checkpoint store: {}", e),
+        }
+    }
+
+    /// Tab on a partly typed `/command` completes its name
+    fn complete_command(&mut self) {
+        if let Some(completed) = self.commands.complete(self.input.text()) {
+            self.input.set_text(&completed);
+        }
+    }
+
+    /// Open the diff viewer on every file changed in this conversation
+    fn show_session_diff(&mut self) -> String {
+        let originals = match self.checkpoints.session_originals() {
+            Ok(originals) => originals,
+            Err(e) => return format!("Diff failed: {:#}", e),
+        };
+        let cwd = std::env::current_dir().and_then(|cwd| cwd.canonicalize()).unwrap_or_default();
+        let mut changes = Vec::new();
+        // Changed, but with no lines to diff
+        let mut binary = Vec::new();
+        for (path, before) in originals {
+            let after = std::fs::read(&path).ok();
+            if before == after {
+                continue;
+            }
+            let shown = path.strip_prefix(&cwd).unwrap_or(&path).display().to_string();
+            let before = before.map(String::from_utf8).transpose();
+            let after = after.map(String::from_utf8).transpose();
+            match (before, after) {
+                (Ok(before), Ok(after)) => changes.push(FileChange { path: shown, before, after }),
+                _ => binary.push(shown),
+            }
+        }
+        let binary_note = binary
+            .iter()
+            .map(|path| format!("\n  {}: binary file changed", path))
+            .collect::<String>();
+        if changes.is_empty() {
+            if binary.is_empty() {
+                return "No files changed this session".to_string();
+            }
+            return format!("{} binary file(s) changed this session:{}", binary.len(), binary_note);
+        }
+
+        let (added, removed) = changes.iter().fold((0, 0), |(a, r), change| {
+            let (added, removed) = change.stats();
+            (a + added, r + removed)
+        });
+        let summary = format!(
+            "{} file(s) changed this session, +{} -{}",
+            changes.len(),
+            added,
+            removed
+        );
+        self.diff_viewer = Some(DiffViewer::new(summary.clone(), changes, false));
+        summary + &binary_note
+    }
+
+    fn list_checkpoints(&self) -> String {
+        let checkpoints = self.checkpoints.checkpoints();
+        if checkpoints.is_empty() {
+            return "No checkpoints yet".to_string();
+        }
+
+        let mut lines = vec!["Checkpoints (restore with /checkpoints restore <id>):".to_string()];
+        for checkpoint in checkpoints.iter().rev() {
+            let prompt: String = checkpoint.prompt.chars().take(60).collect();
+            lines.push(format!(
+                "  {:>3}  {}  {} file(s){}  {}",
+                checkpoint.id,
+                checkpoint.created.format("%Y-%m-%d %H:%M"),
+                checkpoint.files.len(),
+                if checkpoint.restored { " [restored]" } else { "" },
+                prompt
+            ));
+        }
+        lines.join("\n")
+    }
+
+    /// Replace the input with an older prompt when Up is pressed on its
+    /// first row
+    fn recall_previous(&mut self) {
+        // The last queued prompt comes back first, to edit or delete
+        if self.input.is_empty()
+            && let Some(prompt) = self.queued.pop_back()
+        {
+            self.input.set_text(&prompt);
+            return;
+        }
+        if let Some(prompt) = self.history.older(self.input.text()) {
+            let prompt = prompt.to_string();
+            self.input.set_text(&prompt);
+        }
+    }
+
+    fn recall_next(&mut self) {
+        if let Some(prompt) = self.history.newer() {
+            let prompt = prompt.to_string();
+            self.input.set_text(&prompt);
+        }
+    }
+
+    fn start_search(&mut self) {
+        match self.search.as_mut() {
+            Some(search) => search.editing = true,
+            None => self.search = Some(TranscriptSearch::new()),
+        }
+    }
+
+    /// Run the search again after the query changed and jump to the newest
+    /// match. Tool runs and thinking are searched as if expanded, so text
+    /// hidden while collapsed is found too.
+    fn refresh_search(&mut self) {
+        let Some(search) = self.search.as_mut() else {
+            return;
+        };
+        search.update(self.transcript.iter().map(|item| match item {
+            TranscriptItem::Tool(run) if !run.expanded => {
+                let expanded = ToolRun {
+                    expanded: true,
+                    ..run.clone()
+                };
+                tool_run_lines(&expanded, false)
+            }
+            TranscriptItem::Thinking(thinking) if !thinking.expanded => {
+                let expanded = Thinking {
+                    expanded: true,
+                    ..thinking.clone()
+                };
+                thinking_lines(&expanded, false)
+            }
+            item => item_lines(item, false),
+        }));
+        self.show_current_match();
+    }
+
+    /// Scroll to the current match, expanding the tool run or thinking
+    /// it's in
+    fn show_current_match(&mut self) {
+        let Some(found) = self.search.as_ref().and_then(TranscriptSearch::current_match) else {
+            return;
+        };
+        match self.transcript.get_mut(found.item) {
+            Some(TranscriptItem::Tool(run)) if !run.expanded => run.expanded = true,
+            Some(TranscriptItem::Thinking(thinking)) if !thinking.expanded => thinking.expanded = true,
+            _ => {}
+        }
+        self.chat_view.invalidate(found.item);
+        self.chat_view.reveal(found.item, found.line);
+    }
+
+    /// Keys while the transcript search is open. While typing the query,
+    /// Up/Down move between matches, Ctrl-R toggles regex mode and Enter
+    /// accepts it. After that n/N step to older/newer matches and any other
+    /// key closes the search and is handled as usual. Returns whether the
+    /// key was used.
+    fn handle_search_key(&mut self, key: KeyEvent) -> bool {
+        let Some(search) = self.search.as_mut() else {
+            return false;
+        };
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        if key.code == KeyCode::Esc || (ctrl && matches!(key.code, KeyCode::Char('c' | 'g'))) {
+            self.search = None;
+            return true;
+        }
+
+        if search.editing {
+            match key.code {
+                KeyCode::Enter if search.query.is_empty() => self.search = None,
+                KeyCode::Enter => search.editing = false,
+                KeyCode::Up => {
+                    search.older();
+                    self.show_current_match();
+                }
+                KeyCode::Down => {
+                    search.newer();
+                    self.show_current_match();
+                }
+                KeyCode::Char('r') if ctrl => {
+                    search.regex = !search.regex;
+                    self.refresh_search();
+                }
+                KeyCode::Char(c) if !ctrl => {
+                    search.query.push(c);
+                    self.refresh_search();
+                }
+                KeyCode::Backspace => {
+                    search.query.pop();
+                    self.refresh_search();
+                }
+                _ => {}
+            }
+            return true;
+        }
+
+        match key.code {
+            KeyCode::Char('n') if !ctrl => search.older(),
+            KeyCode::Char('N') if !ctrl => search.newer(),
+            KeyCode::Char('f') if ctrl => search.editing = true,
+            _ => {
+                self.search = None;
+                return false;
+            }
+        }
+        self.show_current_match();
+        true
+    }
+
+    /// Enter copy mode with the latest transcript item selected
+    fn start_copy_mode(&mut self) {
+        if let Some(item) = self.copy_target(self.transcript.len(), true) {
+            self.select_copy_target(item, None);
+        }
+    }
+
+    /// The nearest item before (`older`) or after `from` that copy mode
+    /// can select. Thinking is skipped; it isn't part of the answer.
+    fn copy_target(&self, from: usize, older: bool) -> Option<usize> {
+        let copyable = |&i: &usize| !matches!(self.transcript[i], TranscriptItem::Thinking(_));
+        if older {
+            (0..from.min(self.transcript.len())).rev().find(copyable)
+        } else {
+            (from + 1..self.transcript.len()).find(copyable)
+        }
+    }
+
+    fn select_copy_target(&mut self, item: usize, block: Option<usize>) {
+        let lines = block.and_then(|block| {
+            let code_blocks = match self.transcript.get(item)? {
+                TranscriptItem::Message(msg) if msg.role == MessageRole::Assistant => {
+                    markdown::code_blocks(&msg.content)
+                }
+                _ => return None,
+            };
+            // The rendered message starts with the role line
+            let lines = &code_blocks.get(block)?.lines;
+            Some(lines.start + 1..lines.end + 1)
+        });
+        self.chat_view.reveal(item, lines.as_ref().map_or(0, |lines| lines.start));
+        self.copy_selection = Some(CopySelection { item, block, lines });
+    }
+
+    pub fn code_block_count(&self, item: usize) -> usize {
+        match self.transcript.get(item) {
+            Some(TranscriptItem::Message(msg)) if msg.role == MessageRole::Assistant => {
+                markdown::code_blocks(&msg.content).len()
+            }
+            _ => 0,
+        }
+    }
+
+    /// The text copy mode copies: a message as it was written, a tool run's
+    /// output, or the code in a code block
+    fn copy_text(&self, selection: &CopySelection) -> Option<String> {
+        let text = match self.transcript.get(selection.item)? {
+            TranscriptItem::Message(msg) => match selection.block {
+                Some(block) => markdown::code_blocks(&msg.content).get(block)?.code.clone(),
+                None if msg.role == MessageRole::User => mentions::split_attachments(&msg.content).0.to_string(),
+                None => msg.content.strip_prefix("[Command] ").unwrap_or(&msg.content).to_string(),
+            },
+            TranscriptItem::Tool(run) => run.result.clone().unwrap_or_else(|| run.pretty_arguments()),
+            TranscriptItem::Thinking(_) => return None,
+        };
+        Some(text)
+    }
+
+    /// Keys in copy mode: Up/Down (k/j) select an older or newer item,
+    /// Tab/Shift-Tab (Right/Left) step through its code blocks, Enter (y)
+    /// copies and Esc (q) leaves. Other keys are ignored.
+    fn handle_copy_key(&mut self, key: KeyEvent) -> bool {
+        let Some(selection) = &self.copy_selection else {
+            return false;
+        };
+        let (item, block) = (selection.item, selection.block);
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => self.copy_selection = None,
+            KeyCode::Char('c' | 'g') if ctrl => self.copy_selection = None,
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.select_copy_target(self.copy_target(item, true).unwrap_or(item), None)
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.select_copy_target(self.copy_target(item, false).unwrap_or(item), None)
+            }
+            KeyCode::Tab | KeyCode::Right => {
+                let count = self.code_block_count(item);
+                let next = match block {
+                    None if count > 0 => Some(0),
+                    Some(block) if block + 1 < count => Some(block + 1),
+                    _ => None,
+                };
+                self.select_copy_target(item, next);
+            }
+            KeyCode::BackTab | KeyCode::Left => {
+                let count = self.code_block_count(item);
+                let previous = match block {
+                    None => count.checked_sub(1),
+                    Some(block) => block.checked_sub(1),
+                };
+                self.select_copy_target(item, previous);
+            }
+            KeyCode::Enter | KeyCode::Char('y') => {
+                if let Some(selection) = self.copy_selection.take() {
+                    self.copy_to_clipboard(&selection);
+                }
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn copy_to_clipboard(&mut self, selection: &CopySelection) {
+        let Some(text) = self.copy_text(selection) else {
+            return;
+        };
+        let what = if selection.block.is_some() { "code block" } else { "message" };
+        let output = match clipboard::copy(&text) {
+            Ok(method) => format!(
+                "Copied {} ({} line(s)) to the clipboard via {}",
+                what,
+                text.lines().count(),
+                method
+            ),
+            Err(e) => format!("Copy failed: {:#}", e),
+        };
+        self.add_command_output(output);
+    }
+
+    /// Release the mouse so the terminal's own text selection works, or
+    /// take it back for scrolling
+    fn toggle_mouse_capture(&mut self) {
+        let result = if self.mouse_captured {
+            execute!(io::stdout(), DisableMouseCapture)
+        } else {
+            execute!(io::stdout(), EnableMouseCapture)
+        };
+        let output = match result {
+            Ok(()) => {
+                self.mouse_captured = !self.mouse_captured;
+                if self.mouse_captured {
+                    "Mouse capture on: the wheel scrolls the chat".to_string()
+                } else {
+                    "Mouse capture off: select text with the mouse, Ctrl-T to turn it back on".to_string()
+                }
+            }
+            Err(e) => format!("Failed to toggle mouse capture: {}", e),
+        };
+        self.add_command_output(output);
+    }
+
+    fn start_history_search(&mut self) {
+        self.history.reset();
+        self.history_search = Some(HistorySearch::new(self.input.text().to_string()));
+    }
+
+    /// Keys while Ctrl-R search is open: typing refines the query, Ctrl-R
+    /// steps to older matches, Enter or any motion key accepts the match
+    /// into the input and Esc puts back what was there before.
+    fn handle_history_search_key(&mut self, key: KeyEvent) {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let cancel = key.code == KeyCode::Esc || (ctrl && matches!(key.code, KeyCode::Char('c' | 'g')));
+        if cancel {
+            if let Some(search) = self.history_search.take() {
+                self.input.set_text(&search.original);
+            }
+            return;
+        }
+
+        let Some(search) = self.history_search.as_mut() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Char('r') if ctrl => search.older(&self.history),
+            KeyCode::Char(c) if !ctrl => {
+                search.query.push(c);
+                search.update(&self.history);
+            }
+            KeyCode::Backspace => {
+                search.query.pop();
+                search.update(&self.history);
+            }
+            KeyCode::Enter
+            | KeyCode::Left
+            | KeyCode::Right
+            | KeyCode::Up
+            | KeyCode::Down
+            | KeyCode::Home
+            | KeyCode::End => {
+                let accepted = match search.matched.and_then(|i| self.history.get(i)) {
+                    Some(prompt) => prompt.to_string(),
+                    None => std::mem::take(&mut search.original),
+                };
+                self.input.set_text(&accepted);
+                self.history_search = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Open, refresh or close the file picker to follow the `@mention`
+    /// being typed at the cursor
+    pub fn update_mention_picker(&mut self) {
+        let Some((start, query)) = mentions::active_mention(self.input.text(), self.input.cursor())
+        else {
+            self.mention_picker = None;
+            self.dismissed_mention = None;
+            return;
+        };
+
+        if self.dismissed_mention == Some(start) {
+            return;
+        }
+        match &mut self.mention_picker {
+            Some(picker) if picker.start == start => picker.set_query(query),
+            _ => {
+                let mut picker = MentionPicker::open(start);
+                picker.set_query(query);
+                self.mention_picker = Some(picker);
+            }
+        }
+    }
+
+    /// Keys for the open file picker. Returns false for keys it doesn't
+    /// use, which then edit the input as usual.
+    fn handle_mention_key(&mut self, key: KeyEvent) -> bool {
+        let Some(picker) = self.mention_picker.as_mut().filter(|p| !p.matches.is_empty()) else {
+            return false;
+        };
+        if !key.modifiers.difference(KeyModifiers::SHIFT).is_empty() {
+            return false;
+        }
+
+        match key.code {
+            KeyCode::Up => picker.select_previous(),
+            KeyCode::Down => picker.select_next(),
+            KeyCode::Tab | KeyCode::Enter if key.modifiers.is_empty() => {
+                let Some(path) = picker.selection() else {
+                    return false;
+                };
+                let mention = format!("@{} ", path);
+                let start = picker.start;
+                self.input.replace_range(start, self.input.cursor(), &mention);
+                self.mention_picker = None;
+            }
+            KeyCode::Esc => {
+                self.dismissed_mention = Some(picker.start);
+                self.mention_picker = None;
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn turn_in_progress(&self) -> bool {
+        self.turn.in_progress()
+    }
+
+    /// Map a key to an action, handling vim normal mode and plain typing
+    /// along the way
+    fn key_action(&mut self, key: KeyEvent) -> Option<Action> {
+        if let Some(vim) = &mut self.vim {
+            match vim.mode {
+                VimMode::Insert if key.code == KeyCode::Esc && key.modifiers.is_empty() => {
+                    vim.enter_normal(&mut self.input);
+                    return None;
+                }
+                VimMode::Normal => match vim.normal_key(key, &mut self.input) {
+                    VimOutcome::Handled => return None,
+                    VimOutcome::Submit => return Some(Action::Submit),
+                    VimOutcome::HistoryPrevious => {
+                        self.recall_previous();
+                        return None;
+                    }
+                    VimOutcome::HistoryNext => {
+                        self.recall_next();
+                        return None;
+                    }
+                    VimOutcome::Search => {
+                        self.start_search();
+                        return None;
+                    }
+                    VimOutcome::Unhandled => {}
+                },
+                VimMode::Insert => {}
+            }
+        }
+
+        if let Some(action) = self.keymap.action(key) {
+            return Some(action);
+        }
+        match key.code {
+            // While reading back through the transcript, / searches it
+            KeyCode::Char('/') if self.input.is_empty() && !self.chat_view.is_following() => {
+                self.start_search();
+            }
+            KeyCode::Char(c) if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                self.input.insert_char(c);
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Carry out a bound action; returns true to quit
+    async fn perform(&mut self, action: Action, client: &BackendClient) -> bool {
+        match action {
+            Action::Quit if !self.turn_in_progress() => return true,
+            Action::Quit => return self.confirm_quit(),
+            Action::Cancel if self.turn.cancellable() => self.cancel_turn(client).await,
+            Action::Cancel => return self.confirm_quit(),
+            Action::Submit => self.submit(client).await,
+            Action::Newline => self.input.newline(),
+            Action::Complete => self.complete_command(),
+            Action::HistoryPrevious => {
+                // Move within a multi-row prompt, recall history from its first row
+                if !self.input.move_up() {
+                    self.recall_previous();
+                }
+            }
+            Action::HistoryNext => {
+                if !self.input.move_down() {
+                    self.recall_next();
+                }
+            }
+            Action::HistorySearch => self.start_history_search(),
+            Action::Search => self.start_search(),
+            Action::CopyMode => self.start_copy_mode(),
+            Action::ToggleMouse => self.toggle_mouse_capture(),
+            Action::ToggleToolOutput => self.toggle_tool_output(),
+            Action::SelectPreviousTool => self.select_tool(true),
+            Action::SelectNextTool => self.select_tool(false),
+            Action::ScrollUp => self.chat_view.scroll_up(1),
+            Action::ScrollDown => self.chat_view.scroll_down(1),
+            Action::PageUp => self.chat_view.page_up(),
+            Action::PageDown => self.chat_view.page_down(),
+            Action::PreviousMessage => self.chat_view.previous_item(),
+            Action::NextMessage => self.chat_view.next_item(),
+            Action::ScrollToBottom => self.chat_view.scroll_to_bottom(),
+            Action::CursorLeft => self.input.move_left(),
+            Action::CursorRight => self.input.move_right(),
+            Action::WordLeft => self.input.move_word_left(),
+            Action::WordRight => self.input.move_word_right(),
+            Action::LineStart => self.input.move_line_start(),
+            Action::LineEnd => self.input.move_line_end(),
+            Action::DeleteBefore => self.input.delete_before(),
+            Action::DeleteAfter => self.input.delete_after(),
+            Action::DeleteWordBefore => self.input.delete_word_before(),
+            Action::DeleteWordAfter => self.input.delete_word_after(),
+            Action::DeleteToLineStart => self.input.delete_to_line_start(),
+        }
+        false
+    }
+
+    /// Quit if this is the second press in a row, otherwise ask for one
+    fn confirm_quit(&mut self) -> bool {
+        if self.quit_armed_at.is_some() {
+            return true;
+        }
+        self.quit_armed_at = Some(Instant::now());
+        false
+    }
+
+    /// Send the prompt, or run it as a command. Prompts submitted during a
+    /// turn wait for it to end.
+    async fn submit(&mut self, client: &BackendClient) {
+        if self.input.is_empty() {
+            return;
+        }
+        let input = self.input.take();
+        self.history.record(&input);
+        self.chat_view.scroll_to_bottom();
+
+        let Some(content) = self.handle_command(&input) else {
+            return;
+        };
+        if self.turn_in_progress() {
+            self.queued.push_back(content);
+            return;
+        }
+        self.send_prompt(content, client).await;
+    }
+
+    /// Start the next queued prompt's turn, if any
+    async fn send_queued(&mut self, client: &BackendClient) {
+        if !self.turn_in_progress()
+            && let Some(content) = self.queued.pop_front()
+        {
+            self.send_prompt(content, client).await;
+        }
+    }
+
+    async fn send_prompt(&mut self, content: String, client: &BackendClient) {
+        // Files mentioned with @path go along as context
+        self.add_user_message(mentions::attach_files(&content));
+        self.checkpoints.begin_turn(&content);
+        self.selected_tool = None;
+
+        let request = ClientChatRequest {
+            messages: self.messages_to_send(),
+            context: Some(session_context()),
+            model: self.model.clone(),
+            reasoning_effort: self.reasoning_effort,
+        };
+        
+        if let Err(e) = client.send_message(request).await {
+            self.end_turn_with_error(format!("Failed to send message: {}", e));
+        } else {
+            self.turn.submitted();
+            self.turn_started = Some(Instant::now());
+        }
+    }
+
+    /// Stop the turn in progress. What was streamed so far is kept, and
+    /// the rest of the response is dropped as it arrives.
+    async fn cancel_turn(&mut self, client: &BackendClient) {
+        if let Err(e) = client.send_cancel().await {
+            error!("Failed to send cancel: {}", e);
+        }
+        self.turn.cancel();
+        self.abandon_tools("Cancelled before it ran");
+        if !self.streaming_text.is_empty() || !self.streaming_reasoning.is_empty() {
+            self.streaming_text.push_str("\n\n*(cancelled)*");
+        }
+        self.finish_streaming();
+        self.turn_started = None;
+        self.add_command_output("Cancelled the current turn".to_string());
+
+        // Queued prompts go back to the input rather than starting a turn
+        // right after the one just cancelled
+        if !self.queued.is_empty() {
+            let mut prompts: Vec<String> = self.queued.drain(..).collect();
+            if !self.input.is_empty() {
+                prompts.push(self.input.take());
+            }
+            self.input.set_text(&prompts.join("\n\n"));
+        }
+    }
+
+    /// The turn ended early: tool calls that haven't run won't now
+    fn abandon_tools(&mut self, reason: &str) {
+        if self.awaiting_approval.is_some() {
+            self.diff_viewer = None;
+        }
+        let pending: Vec<usize> = self.awaiting_approval.take().into_iter().chain(self.tool_queue.drain(..)).collect();
+        for index in pending {
+            if let Some(run) = self.tool_run_mut(index) {
+                run.finish(reason.to_string(), Duration::ZERO);
+            }
+        }
+    }
+
+    /// Keep what was streamed and show why the turn stopped
+    fn end_turn_with_error(&mut self, error: String) {
+        self.turn.send_failed(error.clone());
+        self.abandon_tools("Not run; the turn ended with an error");
+        self.finish_streaming();
+        self.turn_started = None;
+
+        let error_msg = ChatMessage {
+            role: MessageRole::System,
+            content: error,
+            tool_call_id: None,
+            tool_calls: None,
+        };
+        if let Err(log_err) = self.logger.log_message(&error_msg) {
+            error!("Failed to log error message: {}", log_err);
+        }
+        self.push_message(error_msg);
+    }
+
+    /// Handle a chunk of the server's response as the turn's state says
+    pub async fn handle_chunk(&mut self, chunk: StreamChunk, client: &BackendClient) {
+        // Log the stream chunk
+        if let Err(e) = self.logger.log_stream_chunk(&chunk) {
+            error!("Failed to log stream chunk: {}", e);
+        }
+
+        match (self.turn.chunk(&chunk), chunk) {
+            (ChunkAction::RecordUsage, StreamChunk::Usage { model, prompt_tokens, completion_tokens }) => {
+                self.usage.record(&model, prompt_tokens, completion_tokens);
+            }
+            (ChunkAction::AppendText, StreamChunk::Text { delta, .. }) => {
+                self.streaming_text.push_str(&delta);
+                self.chat_view.note_output();
+            }
+            (ChunkAction::AppendReasoning, StreamChunk::Reasoning { delta }) => {
+                self.streaming_reasoning.push_str(&delta);
+                self.chat_view.note_output();
+            }
+            (ChunkAction::RunTool, StreamChunk::ToolCall { id, name, arguments }) => {
+                info!("=== TOOL CALL RECEIVED FROM SERVER ===");
+                info!("Tool ID: {}", id);
+                info!("Tool Name: {}", name);
+                info!("Arguments: {}", arguments);
+
+                // Text the model wrote before calling the tool stays above it
+                self.finish_streaming();
+                // Shown as running until the result is in
+                self.transcript.push(TranscriptItem::Tool(ToolRun::new(id, name, arguments)));
+                // Run by `advance_tools` once the call has been drawn
+                self.tool_queue.push_back(self.transcript.len() - 1);
+                self.chat_view.note_output();
+            }
+            (ChunkAction::EndTurn(error), _) => {
+                if error.is_some() {
+                    self.abandon_tools("Not run; the turn ended with an error");
+                }
+                self.finish_streaming();
+                // Ready for the next prompt's context
+                tools::refresh_git_status();
+                self.turn_started = None;
+                if let Some(error) = error {
+                    let error_msg = ChatMessage {
+                        role: MessageRole::System,
+                        content: error,
+                        tool_call_id: None,
+                        tool_calls: None,
+                    };
+                    if let Err(e) = self.logger.log_message(&error_msg) {
+                        error!("Failed to log error message: {}", e);
+                    }
+                    self.push_message(error_msg);
+                }
+                self.chat_view.note_output();
+                self.send_queued(client).await;
+            }
+            (_, StreamChunk::ToolResult { id, content }) => {
+                // This shouldn't be received by the client from server
+                debug!("Unexpected tool result from server: {} - {}", id, content);
+            }
+            (_, chunk) => debug!("Ignored {:?} in state {:?}", chunk, self.turn.state()),
+        }
+    }
+
+    /// Apply a terminal event; returns true to quit
+    pub async fn handle_event(&mut self, event: Event, client: &BackendClient) -> bool {
+        match event {
+            Event::Key(key) if key.kind == KeyEventKind::Press && self.history_search.is_some() => {
+                self.handle_history_search_key(key);
+            }
+            Event::Key(key) if key.kind == KeyEventKind::Press => {
+                // The diff viewer takes every key but those that cancel or quit
+                if let Some(viewer) = &mut self.diff_viewer
+                    && !matches!(self.keymap.action(key), Some(Action::Cancel | Action::Quit))
+                {
+                    match viewer.handle_key(key) {
+                        ViewerOutcome::Handled => {}
+                        ViewerOutcome::Close => self.diff_viewer = None,
+                        ViewerOutcome::Approve => self.resolve_approval(true, client).await,
+                        ViewerOutcome::Reject => self.resolve_approval(false, client).await,
+                    }
+                    return false;
+                }
+                if self.handle_copy_key(key) || self.handle_search_key(key) || self.handle_mention_key(key) {
+                    return false;
+                }
+                let Some(action) = self.key_action(key) else {
+                    return false;
+                };
+                if action != Action::Quit && action != Action::Cancel {
+                    self.quit_armed_at = None;
+                }
+                return self.perform(action, client).await;
+            }
+            Event::Paste(text) => {
+                // Bracketed paste: newlines in pasted text never submit
+                self.input.insert_str(&text);
+            }
+            Event::Mouse(mouse) => {
+                match mouse.kind {
+                    MouseEventKind::ScrollUp => {
+                        self.chat_view.scroll_up(3);
+                    }
+                    MouseEventKind::ScrollDown => {
+                        self.chat_view.scroll_down(3);
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+        false
+    }
+
+    pub fn handle_status(&mut self, status: ConnectionStatus) {
+        // Log connection status change
+        let status_str = match &status {
+            ConnectionStatus::Connected => "Connected",
+            ConnectionStatus::Connecting => "Connecting",
+            ConnectionStatus::Disconnected => "Disconnected",
+            ConnectionStatus::Error(e) => &format!("Error: {}", e),
+        };
+        if let Err(e) = self.logger.log_connection_status(status_str) {
+            error!("Failed to log connection status: {}", e);
+        }
+        if !matches!(status, ConnectionStatus::Connected) {
+            self.connection_lost();
+        }
+        self.connection_status = status;
+    }
+
+    /// Tool calls are waiting to run and none needs approval
+    pub fn tools_ready(&self) -> bool {
+        !self.tool_queue.is_empty() && self.awaiting_approval.is_none()
+    }
+
+    /// The connection dropped, taking any turn in progress with it
+    fn connection_lost(&mut self) {
+        let was_in_progress = self.turn.in_progress() && self.turn.state() != &TurnState::Cancelling;
+        self.turn.disconnected();
+        if was_in_progress {
+            self.abandon_tools("Not run; the connection was lost");
+            self.finish_streaming();
+            self.turn_started = None;
+            self.add_command_output("Connection lost; the turn in progress was abandoned".to_string());
+        }
+    }
+
+    /// Run queued tool calls in order, stopping at an edit that needs
+    /// approval first
+    pub async fn advance_tools(&mut self, client: &BackendClient) {
+        while self.awaiting_approval.is_none()
+            && self.turn.in_progress()
+            && let Some(index) = self.tool_queue.pop_front()
+        {
+            let Some(run) = self.tool_run_mut(index) else {
+                continue;
+            };
+            let (name, arguments) = (run.name.clone(), run.arguments.clone());
+            if self.approve_edits
+                && let Some(change) = tools::proposed_change(&name, &arguments).await
+            {
+                let (added, removed) = change.stats();
+                let title = format!("{} {} +{} -{}", name, change.path, added, removed);
+                self.diff_viewer = Some(DiffViewer::new(title, vec![change], true));
+                self.awaiting_approval = Some(index);
+                self.turn.approval_requested();
+                return;
+            }
+            self.run_tool(index, client).await;
+        }
+    }
+
+    /// Answer the diff viewer's approval prompt and carry on with the queue
+    async fn resolve_approval(&mut self, approved: bool, client: &BackendClient) {
+        self.diff_viewer = None;
+        let Some(index) = self.awaiting_approval.take() else {
+            return;
+        };
+        self.turn.approval_resolved();
+        if approved {
+            self.run_tool(index, client).await;
+        } else {
+            if let Some(run) = self.tool_run_mut(index) {
+                run.finish("Rejected by the user; the file was not changed".to_string(), Duration::ZERO);
+            }
+            self.send_tool_result(index, client).await;
+        }
+        self.advance_tools(client).await;
+    }
+
+    async fn run_tool(&mut self, index: usize, client: &BackendClient) {
+        let Some(run) = self.tool_run_mut(index) else {
+            return;
+        };
+        let (id, name, arguments) = (run.id.clone(), run.name.clone(), run.arguments.clone());
+
+        // Worked out before the tool changes the file
+        let change = tools::proposed_change(&name, &arguments).await;
+        let started = Instant::now();
+        let result = execute_tool(&name, &arguments, &mut self.checkpoints).await;
+        info!("Tool execution completed");
+        info!("Result length: {} chars", result.len());
+        info!("Result preview (first 200 bytes): {}", preview(&result, 200));
+
+        // Log tool execution result
+        if let Err(e) = self.logger.log_tool_execution(&id, &name, &result) {
+            error!("Failed to log tool execution: {}", e);
+        }
+
+        if let Some(run) = self.tool_run_mut(index) {
+            run.finish(result, started.elapsed());
+            if !run.failed() {
+                run.change = change;
+            }
+        }
+        self.chat_view.note_output();
+        self.send_tool_result(index, client).await;
+    }
+
+    async fn send_tool_result(&mut self, index: usize, client: &BackendClient) {
+        let Some(run) = self.tool_run_mut(index) else {
+            return;
+        };
+        let id = run.id.clone();
+        let result = run.result.clone().unwrap_or_default();
+
+        // Queued prompts can steer the model once the last tool of the
+        // batch is done
+        let last_of_batch = self.tool_queue.is_empty() && self.awaiting_approval.is_none();
+        let steer = if self.steer && last_of_batch && !self.queued.is_empty() {
+            let prompts: Vec<String> = self.queued.drain(..).collect();
+            let content = mentions::attach_files(&prompts.join("\n\n"));
+            self.add_user_message(content.clone());
+            Some(content)
+        } else {
+            None
+        };
+
+        // Send tool result back to server
+        info!("Sending tool result back to server...");
+        if let Err(e) = client.send_tool_result(id, result, steer).await {
+            info!("ERROR: Failed to send tool result: {}", e);
+            self.end_turn_with_error(format!("Failed to send tool result: {}", e));
+        } else {
+            info!("Tool result successfully sent to server");
+            self.turn.tool_answered();
+        }
+    }
+
+    /// Move the streamed reasoning and text into the transcript
+    fn finish_streaming(&mut self) {
+        let reasoning = std::mem::take(&mut self.streaming_reasoning);
+        if !reasoning.trim().is_empty() {
+            self.transcript.push(TranscriptItem::Thinking(Thinking {
+                text: reasoning.trim().to_string(),
+                expanded: false,
+            }));
+            self.chat_view.note_output();
+        }
+
+        let content = std::mem::take(&mut self.streaming_text);
+        if !content.is_empty() {
+            let message = ChatMessage {
+                role: MessageRole::Assistant,
+                content,
+                tool_call_id: None,
+                tool_calls: None,
+            };
+
+            // Log the complete assistant message
+            if let Err(e) = self.logger.log_message(&message) {
+                error!("Failed to log assistant message: {}", e);
+            }
+
+            self.push_message(message);
+        }
+    }
+
+}
+
@@ -0,0 +1,460 @@
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::Style,
+    text::Line,
+    widgets::{Clear, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap},
+};
+
+const UNSEEN_OUTPUT_NOTICE: &str = " ↓ New output below (Ctrl-End to jump to bottom) ";
+
+/// Lines laid out for a given width, with the row each line starts at
+#[derive(Debug, Default)]
+struct Layout {
+    lines: Vec<Line<'static>>,
+    line_starts: Vec<usize>,
+    height: usize,
+}
+
+impl Layout {
+    fn new(lines: Vec<Line<'static>>, width: u16) -> Self {
+        let mut line_starts = Vec::with_capacity(lines.len());
+        let mut height = 0;
+        for line in &lines {
+            line_starts.push(height);
+            height += Paragraph::new(line.clone())
+                .wrap(Wrap { trim: false })
+                .line_count(width);
+        }
+        Self {
+            lines,
+            line_starts,
+            height,
+        }
+    }
+
+    /// Lines overlapping rows `top..bottom`, where this layout begins at row
+    /// `start`, with the row and index of the first of them
+    fn visible(&self, start: usize, top: usize, bottom: usize) -> Option<(usize, usize, &[Line<'static>])> {
+        if self.height == 0 || start + self.height <= top || start >= bottom {
+            return None;
+        }
+        let first = self
+            .line_starts
+            .partition_point(|&row| start + row <= top)
+            .saturating_sub(1);
+        let end = self.line_starts.partition_point(|&row| start + row < bottom);
+        Some((start + self.line_starts[first], first, &self.lines[first..end]))
+    }
+}
+
+/// Scroll state and render cache of the chat transcript. Each transcript
+/// item is laid out once per width and kept until it changes, so a frame
+/// only lays out the streaming response and draws the rows in view. Rows
+/// are terminal rows after wrapping, so scrolling moves evenly however
+/// long the lines are.
+#[derive(Debug, Default)]
+pub struct ChatView {
+    /// First visible row, or `None` to follow the newest output
+    top: Option<usize>,
+    /// Line of an item to scroll into view at the next render
+    reveal: Option<(usize, usize)>,
+    width: u16,
+    /// Laid out transcript items, `None` until rendered at `width`
+    items: Vec<Option<Layout>>,
+    /// Row each item starts at; valid for the items before `dirty_from`
+    starts: Vec<usize>,
+    dirty_from: usize,
+    total_rows: usize,
+    viewport_rows: usize,
+    /// Output arrived below the view while scrolled up
+    pub unseen_output: bool,
+    /// Style of the notice shown for `unseen_output`
+    pub notice_style: Style,
+}
+
+impl ChatView {
+    /// Draw the transcript into `area`: the `item_count` transcript items
+    /// (laid out with `render_item` when not cached) and `tail` below them,
+    /// which is laid out afresh every frame.
+    /// `mark` may restyle the visible lines of items, given the item and
+    /// line index, without changing their text.
+    pub fn render(
+        &mut self,
+        f: &mut Frame,
+        area: Rect,
+        item_count: usize,
+        mut render_item: impl FnMut(usize) -> Vec<Line<'static>>,
+        mark: impl Fn(usize, usize, &mut Line<'static>),
+        tail: Vec<Line<'static>>,
+    ) {
+        // The rightmost column is kept for the scrollbar
+        let text_area = Rect {
+            width: area.width.saturating_sub(1),
+            ..area
+        };
+        self.set_width(text_area.width);
+        let items_rows = self.layout_items(item_count, &mut render_item);
+
+        let tail = Layout::new(tail, self.width);
+        let tail_start = items_rows;
+        self.total_rows = tail_start + tail.height;
+        self.viewport_rows = area.height as usize;
+        if let Some((item, line)) = self.reveal.take() {
+            self.scroll_to_line(item, line);
+        }
+        let top = self.top();
+        let bottom = top + self.viewport_rows;
+
+        // Only the lines overlapping the view are drawn, with the paragraph
+        // scrolled past the rows of the first line that are above the view
+        let mut visible: Vec<Line> = Vec::new();
+        let mut first_row = None;
+        let mut take = |row: usize, lines: &[Line<'static>]| {
+            first_row.get_or_insert(row);
+            visible.extend_from_slice(lines);
+        };
+        let first_item = self
+            .starts
+            .partition_point(|&start| start <= top)
+            .saturating_sub(1);
+        for (index, (&start, item)) in self.starts.iter().zip(&self.items).enumerate().skip(first_item) {
+            if start >= bottom {
+                break;
+            }
+            if let Some((row, first_line, lines)) = item.as_ref().and_then(|item| item.visible(start, top, bottom)) {
+                let mut lines = lines.to_vec();
+                for (line, rendered) in lines.iter_mut().enumerate() {
+                    mark(index, first_line + line, rendered);
+                }
+                take(row, &lines);
+            }
+        }
+        if let Some((row, _, lines)) = tail.visible(tail_start, top, bottom) {
+            take(row, lines);
+        }
+        let skip = top - first_row.unwrap_or(top);
+
+        let chat = Paragraph::new(visible)
+            .wrap(Wrap { trim: false })
+            .scroll((skip.min(u16::MAX as usize) as u16, 0));
+        f.render_widget(chat, text_area);
+
+        if self.total_rows > self.viewport_rows {
+            let mut state = ScrollbarState::new(self.max_top()).position(top);
+            f.render_stateful_widget(
+                Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                    .begin_symbol(None)
+                    .end_symbol(None),
+                area,
+                &mut state,
+            );
+        }
+
+        if self.unseen_output && self.top.is_some() && area.height > 1 {
+            let width = (UNSEEN_OUTPUT_NOTICE.chars().count() as u16).min(text_area.width);
+            let notice_area = Rect {
+                x: text_area.x + (text_area.width - width) / 2,
+                y: area.y + area.height - 1,
+                width,
+                height: 1,
+            };
+            f.render_widget(Clear, notice_area);
+            f.render_widget(
+                Paragraph::new(UNSEEN_OUTPUT_NOTICE).style(self.notice_style),
+                notice_area,
+            );
+        }
+    }
+
+    /// Item `index` changed how it renders
+    pub fn invalidate(&mut self, index: usize) {
+        if let Some(item) = self.items.get_mut(index) {
+            *item = None;
+            self.dirty_from = self.dirty_from.min(index);
+        }
+    }
+
+    /// The transcript was replaced; start over at the bottom
+    pub fn reset(&mut self) {
+        *self = Self {
+            width: self.width,
+            ..Self::default()
+        };
+    }
+
+    pub fn is_following(&self) -> bool {
+        self.top.is_none()
+    }
+
+    /// New output was added at the bottom. The view follows it unless the
+    /// user scrolled up, in which case the indicator is shown instead.
+    pub fn note_output(&mut self) {
+        if self.top.is_some() {
+            self.unseen_output = true;
+        }
+    }
+
+    pub fn scroll_up(&mut self, rows: usize) {
+        let top = self.current_top();
+        self.top = Some(top.saturating_sub(rows));
+    }
+
+    pub fn scroll_down(&mut self, rows: usize) {
+        self.scroll_to(self.current_top().saturating_add(rows));
+    }
+
+    pub fn page_up(&mut self) {
+        self.scroll_up(self.page_rows());
+    }
+
+    pub fn page_down(&mut self) {
+        self.scroll_down(self.page_rows());
+    }
+
+    pub fn scroll_to_bottom(&mut self) {
+        self.top = None;
+        self.unseen_output = false;
+    }
+
+    /// Scroll line `line` of item `item` into view once it's laid out
+    pub fn reveal(&mut self, item: usize, line: usize) {
+        self.reveal = Some((item, line));
+    }
+
+    /// Scroll so the item starting above the top of the view is at the top
+    pub fn previous_item(&mut self) {
+        let top = self.current_top();
+        if let Some(&start) = self.starts.iter().rev().find(|&&start| start < top) {
+            self.top = Some(start);
+        }
+    }
+
+    /// Scroll so the next item below the top of the view is at the top
+    pub fn next_item(&mut self) {
+        let top = self.current_top();
+        match self.starts.iter().find(|&&start| start > top) {
+            Some(&start) => self.scroll_to(start),
+            None => self.scroll_to_bottom(),
+        }
+    }
+
+    /// Layout is per width; a resize drops every cached item
+    fn set_width(&mut self, width: u16) {
+        if width != self.width {
+            self.width = width;
+            self.items.clear();
+            self.dirty_from = 0;
+        }
+    }
+
+    /// Lay out new and changed items and update where each one starts.
+    /// Returns the rows taken up by all of them.
+    fn layout_items(&mut self, count: usize, render_item: &mut impl FnMut(usize) -> Vec<Line<'static>>) -> usize {
+        self.items.resize_with(count, || None);
+        self.dirty_from = self.dirty_from.min(self.starts.len()).min(count);
+        self.starts.truncate(self.dirty_from);
+
+        let mut row = self.items_end(self.dirty_from);
+        for index in self.dirty_from..count {
+            self.starts.push(row);
+            let width = self.width;
+            let item = self.items[index].get_or_insert_with(|| Layout::new(render_item(index), width));
+            row += item.height;
+        }
+        self.dirty_from = count;
+        row
+    }
+
+    /// The row the item at `index` starts at, from the items before it
+    fn items_end(&self, index: usize) -> usize {
+        match index.checked_sub(1) {
+            Some(last) => self.starts[last] + self.items[last].as_ref().map_or(0, |item| item.height),
+            None => 0,
+        }
+    }
+
+    /// The first row to show, going back to following the output once
+    /// scrolled down to the bottom
+    fn top(&mut self) -> usize {
+        match self.top {
+            Some(top) if top < self.max_top() => top,
+            _ => {
+                self.scroll_to_bottom();
+                self.max_top()
+            }
+        }
+    }
+
+    /// Put the line a third of the way down the view, unless it's in view
+    fn scroll_to_line(&mut self, item: usize, line: usize) {
+        let (Some(start), Some(Some(layout))) = (self.starts.get(item), self.items.get(item)) else {
+            return;
+        };
+        let Some(line_start) = layout.line_starts.get(line) else {
+            return;
+        };
+        let row = start + line_start;
+        let top = self.current_top();
+        if row < top || row >= top + self.viewport_rows {
+            self.scroll_to(row.saturating_sub(self.viewport_rows / 3));
+        }
+    }
+
+    fn scroll_to(&mut self, top: usize) {
+        if top >= self.max_top() {
+            self.scroll_to_bottom();
+        } else {
+            self.top = Some(top);
+        }
+    }
+
+    fn current_top(&self) -> usize {
+        self.top.unwrap_or(self.max_top()).min(self.max_top())
+    }
+
+    fn max_top(&self) -> usize {
+        self.total_rows.saturating_sub(self.viewport_rows)
+    }
+
+    /// A page keeps a couple of rows of overlap for context
+    fn page_rows(&self) -> usize {
+        self.viewport_rows.saturating_sub(2).max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{Terminal, backend::TestBackend};
+
+    /// Draw `items` in a `width` x `height` area, counting how many items
+    /// had to be laid out. Returns the rows as text.
+    fn draw(view: &mut ChatView, items: &[&str], width: u16, height: u16, laid_out: &mut usize) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal
+            .draw(|f| {
+                let render_item = |index: usize| {
+                    *laid_out += 1;
+                    items[index].lines().map(|line| Line::from(line.to_string())).collect()
+                };
+                view.render(f, f.area(), items.len(), render_item, |_, _, _| {}, Vec::new());
+            })
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..height)
+            .map(|y| (0..width).map(|x| buffer[(x, y)].symbol()).collect::<String>().trim_end().to_string())
+            .collect()
+    }
+
+    fn rows(view: &mut ChatView, items: &[&str], width: u16, height: u16) -> Vec<String> {
+        draw(view, items, width, height, &mut 0)
+    }
+
+    const LONG: &str = "one two three four five six seven eight nine ten eleven twelve thirteen fourteen fifteen end";
+
+    #[test]
+    fn lines_take_as_many_rows_as_they_wrap_to() {
+        let layout = Layout::new(vec![Line::from("short"), Line::from(LONG), Line::default()], 20);
+        assert_eq!(layout.line_starts, [0, 1, 6]);
+        assert_eq!(layout.height, 7);
+    }
+
+    #[test]
+    fn the_bottom_of_a_wrapped_message_stays_in_view_at_any_width() {
+        let mut view = ChatView::default();
+        let items = ["first", LONG];
+
+        for width in [41, 21] {
+            let screen = rows(&mut view, &items, width, 4);
+            assert!(view.is_following());
+            // The rightmost column holds the scrollbar
+            assert!(screen[3].trim_end_matches(['█', '║', '│']).trim_end().ends_with("end"), "{:?}", screen);
+            assert!(view.current_top() <= view.max_top());
+        }
+
+        // Scrolled up at the narrow width, then widened: the top is
+        // clamped to the shorter transcript
+        view.scroll_up(1000);
+        rows(&mut view, &items, 21, 4);
+        assert_eq!(view.top, Some(0));
+        view.scroll_down(3);
+        rows(&mut view, &items, 81, 4);
+        assert!(view.current_top() <= view.max_top());
+        assert!(view.is_following(), "past the bottom goes back to following");
+    }
+
+    #[test]
+    fn a_new_width_lays_every_item_out_again() {
+        let mut view = ChatView::default();
+        let items = ["first", LONG];
+        let mut laid_out = 0;
+        draw(&mut view, &items, 41, 4, &mut laid_out);
+        assert_eq!(laid_out, 2);
+
+        draw(&mut view, &items, 21, 4, &mut laid_out);
+        assert_eq!(laid_out, 4);
+        assert_eq!(view.total_rows, 1 + 5);
+    }
+
+    #[test]
+    fn only_new_and_invalidated_items_are_laid_out() {
+        let mut view = ChatView::default();
+        let mut laid_out = 0;
+        draw(&mut view, &["a", "b"], 20, 4, &mut laid_out);
+        assert_eq!(laid_out, 2);
+
+        // Finished items are kept from frame to frame
+        draw(&mut view, &["a", "b"], 20, 4, &mut laid_out);
+        assert_eq!(laid_out, 2);
+        draw(&mut view, &["a", "b", "c"], 20, 4, &mut laid_out);
+        assert_eq!(laid_out, 3);
+
+        // An item that renders differently now, e.g. expanded, moves the
+        // ones after it without laying them out again
+        view.invalidate(0);
+        let screen = draw(&mut view, &["a\nexpanded", "b", "c"], 20, 4, &mut laid_out);
+        assert_eq!(laid_out, 4);
+        assert_eq!(view.starts, [0, 2, 3]);
+        assert_eq!(screen[..4], ["a", "expanded", "b", "c"]);
+    }
+
+    #[test]
+    fn unseen_output_is_announced_only_while_scrolled_up() {
+        let mut view = ChatView::default();
+        let items: Vec<String> = (0..10).map(|i| format!("line {}", i)).collect();
+        let items: Vec<&str> = items.iter().map(String::as_str).collect();
+        rows(&mut view, &items, 60, 4);
+
+        view.note_output();
+        assert!(!view.unseen_output);
+
+        view.scroll_up(2);
+        view.note_output();
+        let screen = rows(&mut view, &items, 60, 4);
+        assert!(screen[3].contains("New output below"), "{:?}", screen);
+
+        view.scroll_to_bottom();
+        let screen = rows(&mut view, &items, 60, 4);
+        assert!(!view.unseen_output);
+        assert!(screen[3].starts_with("line 9"), "{:?}", screen);
+    }
+
+    #[test]
+    fn message_navigation_moves_between_item_starts() {
+        let mut view = ChatView::default();
+        let items = ["a\na\na", "b\nb\nb", "c\nc\nc", "d\nd\nd"];
+        rows(&mut view, &items, 20, 4);
+        assert_eq!(view.current_top(), 8);
+
+        view.previous_item();
+        assert_eq!(view.top, Some(6));
+        view.previous_item();
+        assert_eq!(view.top, Some(3));
+        view.next_item();
+        assert_eq!(view.top, Some(6));
+        // The last item can't be scrolled to the top; that's the bottom
+        view.next_item();
+        assert!(view.is_following());
+    }
+}
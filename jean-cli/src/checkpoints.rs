@@ -0,0 +1,404 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use tracing::{debug, error};
+
+const MANIFEST_FILE: &str = "manifest.json";
+const BLOB_DIR: &str = "blobs";
+
+/// State of one file before a turn first modified it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileSnapshot {
+    /// Canonical and absolute, so restoring doesn't depend on the cwd
+    pub path: PathBuf,
+    /// Blob holding the original contents, or `None` if the file didn't exist
+    pub blob: Option<String>,
+}
+
+/// All files touched by mutating tools during a single user turn
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub id: u64,
+    pub created: DateTime<Local>,
+    /// The user prompt that started the turn, for listing
+    pub prompt: String,
+    pub files: Vec<FileSnapshot>,
+    /// Set once the files have been put back to their snapshotted state
+    pub restored: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    checkpoints: Vec<Checkpoint>,
+}
+
+/// Snapshots files before the assistant changes them so edits can be undone
+/// without relying on git. Each conversation keeps its snapshots in its own
+/// directory next to the conversation logs, named so the most recent sorts
+/// last.
+pub struct CheckpointStore {
+    dir: PathBuf,
+    manifest: Manifest,
+    /// Prompt of the turn in progress; its checkpoint is only created once
+    /// something is actually modified
+    pending_prompt: Option<String>,
+    /// Id of the checkpoint belonging to the turn in progress
+    current: Option<u64>,
+}
+
+impl CheckpointStore {
+    pub fn open(dir: PathBuf) -> Result<Self> {
+        fs::create_dir_all(dir.join(BLOB_DIR))?;
+
+        let manifest_path = dir.join(MANIFEST_FILE);
+        let manifest = if manifest_path.exists() {
+            let json = fs::read_to_string(&manifest_path)?;
+            serde_json::from_str(&json)
+                .with_context(|| format!("corrupt checkpoint manifest {:?}", manifest_path))?
+        } else {
+            Manifest::default()
+        };
+
+        debug!(
+            "Loaded {} checkpoints from {:?}",
+            manifest.checkpoints.len(),
+            dir
+        );
+
+        Ok(Self {
+            dir,
+            manifest,
+            pending_prompt: None,
+            current: None,
+        })
+    }
+
+    /// The most recent conversation's store under `root`, so its edits can
+    /// still be undone after a restart, or a new one named `name` if there
+    /// is none yet
+    pub fn open_latest(root: &Path, name: &str) -> Result<Self> {
+        let latest = fs::read_dir(root)
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().join(MANIFEST_FILE).is_file())
+            .map(|entry| entry.file_name())
+            .max();
+        match latest {
+            Some(latest) => Self::open(root.join(latest)),
+            None => Self::open(root.join(name)),
+        }
+    }
+
+    /// An empty store under `root` for a conversation starting now
+    pub fn open_new(root: &Path) -> Result<Self> {
+        let name = format!("conversation_{}", Local::now().format("%Y%m%d_%H%M%S"));
+        let mut dir = root.join(&name);
+        // Started twice within a second
+        for n in 2.. {
+            if !dir.exists() {
+                break;
+            }
+            dir = root.join(format!("{}-{}", name, n));
+        }
+        Self::open(dir)
+    }
+
+    /// Start grouping snapshots under a new user turn
+    pub fn begin_turn(&mut self, prompt: &str) {
+        self.pending_prompt = Some(prompt.to_string());
+        self.current = None;
+    }
+
+    /// Record the current contents of `path` before a tool modifies it. Only
+    /// the first modification per turn is recorded, so undo returns the file
+    /// to its state at the start of the turn.
+    pub fn snapshot(&mut self, path: &Path) -> Result<()> {
+        let path = &canonical_path(path).with_context(|| format!("failed to resolve {:?}", path))?;
+        let id = self.current_checkpoint_id();
+        let checkpoint = self
+            .manifest
+            .checkpoints
+            .iter_mut()
+            .find(|c| c.id == id)
+            .with_context(|| format!("checkpoint {} is missing from the manifest", id))?;
+
+        if checkpoint.files.iter().any(|f| f.path == *path) {
+            return Ok(());
+        }
+
+        let blob = if path.exists() {
+            let name = format!("{}-{}", id, checkpoint.files.len());
+            fs::copy(path, self.dir.join(BLOB_DIR).join(&name))
+                .with_context(|| format!("failed to snapshot {:?}", path))?;
+            Some(name)
+        } else {
+            None
+        };
+
+        checkpoint.files.push(FileSnapshot {
+            path: path.to_path_buf(),
+            blob,
+        });
+        self.save()
+    }
+
+    /// Revert the most recent turn that hasn't been reverted yet
+    pub fn undo(&mut self) -> Result<Option<Checkpoint>> {
+        let Some(id) = self
+            .manifest
+            .checkpoints
+            .iter()
+            .rev()
+            .find(|c| !c.restored)
+            .map(|c| c.id)
+        else {
+            return Ok(None);
+        };
+        self.restore(id).map(|mut restored| restored.pop())
+    }
+
+    /// Put the workspace back into the state it was in before turn `id`,
+    /// reverting that turn and every later one, newest first. Returns the
+    /// checkpoints that were reverted.
+    pub fn restore(&mut self, id: u64) -> Result<Vec<Checkpoint>> {
+        if !self.manifest.checkpoints.iter().any(|c| c.id == id) {
+            anyhow::bail!("no checkpoint with id {}", id);
+        }
+
+        let mut reverted = Vec::new();
+        for index in (0..self.manifest.checkpoints.len()).rev() {
+            let checkpoint = &self.manifest.checkpoints[index];
+            if checkpoint.id < id {
+                break;
+            }
+            if checkpoint.restored {
+                continue;
+            }
+
+            for file in checkpoint.files.iter().rev() {
+                self.restore_file(file)?;
+            }
+            self.manifest.checkpoints[index].restored = true;
+            reverted.push(self.manifest.checkpoints[index].clone());
+        }
+
+        // Later edits in this turn should start a fresh checkpoint
+        self.current = None;
+        self.save()?;
+        Ok(reverted)
+    }
+
+    pub fn checkpoints(&self) -> &[Checkpoint] {
+        &self.manifest.checkpoints
+    }
+
+    /// Every file modified in this conversation, with its contents from
+    /// before the first modification (`None` if it didn't exist yet). Read
+    /// as bytes, since the assistant may have overwritten a binary file.
+    pub fn session_originals(&self) -> Result<Vec<(PathBuf, Option<Vec<u8>>)>> {
+        let mut originals: Vec<(PathBuf, Option<Vec<u8>>)> = Vec::new();
+        for file in self.manifest.checkpoints.iter().flat_map(|c| &c.files) {
+            if originals.iter().any(|(path, _)| *path == file.path) {
+                continue;
+            }
+            let contents = match &file.blob {
+                Some(blob) => Some(
+                    fs::read(self.dir.join(BLOB_DIR).join(blob))
+                        .with_context(|| format!("failed to read snapshot of {:?}", file.path))?,
+                ),
+                None => None,
+            };
+            originals.push((file.path.clone(), contents));
+        }
+        Ok(originals)
+    }
+
+    fn restore_file(&self, file: &FileSnapshot) -> Result<()> {
+        match &file.blob {
+            Some(blob) => {
+                if let Some(parent) = file.path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::copy(self.dir.join(BLOB_DIR).join(blob), &file.path)
+                    .with_context(|| format!("failed to restore {:?}", file.path))?;
+            }
+            None => {
+                // The file was created during the turn
+                if file.path.exists() {
+                    fs::remove_file(&file.path)
+                        .with_context(|| format!("failed to remove {:?}", file.path))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn current_checkpoint_id(&mut self) -> u64 {
+        if let Some(id) = self.current {
+            return id;
+        }
+
+        let id = self
+            .manifest
+            .checkpoints
+            .last()
+            .map(|c| c.id + 1)
+            .unwrap_or(1);
+        self.manifest.checkpoints.push(Checkpoint {
+            id,
+            created: Local::now(),
+            prompt: self.pending_prompt.clone().unwrap_or_default(),
+            files: Vec::new(),
+            restored: false,
+        });
+        self.current = Some(id);
+        id
+    }
+
+    fn save(&self) -> Result<()> {
+        let json = serde_json::to_string_pretty(&self.manifest)?;
+        let tmp = self.dir.join(format!("{}.tmp", MANIFEST_FILE));
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, self.dir.join(MANIFEST_FILE))?;
+        Ok(())
+    }
+}
+
+impl Default for CheckpointStore {
+    /// Fallback used when the log directory can't be written: snapshots go to
+    /// a temp directory and won't be found again after a restart
+    fn default() -> Self {
+        let dir = std::env::temp_dir().join("jean-checkpoints");
+        if let Err(e) = fs::create_dir_all(dir.join(BLOB_DIR)) {
+            error!("Failed to create fallback checkpoint directory: {}", e);
+        }
+        Self {
+            dir,
+            manifest: Manifest::default(),
+            pending_prompt: None,
+            current: None,
+        }
+    }
+}
+
+/// `path` made absolute with symlinks and `..` resolved, also for a file
+/// that doesn't exist yet: its nearest existing ancestor is canonicalized
+/// and the rest appended, which may not climb back out with `..`.
+pub fn canonical_path(path: &Path) -> io::Result<PathBuf> {
+    let path = std::path::absolute(path)?;
+    let existing = path
+        .ancestors()
+        .find(|ancestor| ancestor.exists())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no existing ancestor"))?;
+    let rest = path.strip_prefix(existing).unwrap_or(Path::new(""));
+    if rest.components().any(|c| !matches!(c, Component::Normal(_))) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "'..' below a directory that doesn't exist",
+        ));
+    }
+    let existing = existing.canonicalize()?;
+    // Joining an empty path would add a trailing separator
+    Ok(if rest.as_os_str().is_empty() { existing } else { existing.join(rest) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(dir: &Path) -> CheckpointStore {
+        CheckpointStore::open(dir.join("checkpoints")).expect("open store")
+    }
+
+    #[test]
+    fn undo_puts_back_an_edited_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("main.rs");
+        fs::write(&file, "fn main() {}\n").unwrap();
+
+        let mut checkpoints = store(dir.path());
+        checkpoints.begin_turn("rename main");
+        checkpoints.snapshot(&file).unwrap();
+        fs::write(&file, "fn start() {}\n").unwrap();
+        // Only the state from before the turn's first edit is kept
+        checkpoints.snapshot(&file).unwrap();
+        fs::write(&file, "fn begin() {}\n").unwrap();
+
+        let undone = checkpoints.undo().unwrap().expect("a checkpoint to undo");
+        assert_eq!(undone.prompt, "rename main");
+        assert_eq!(fs::read_to_string(&file).unwrap(), "fn main() {}\n");
+        assert!(checkpoints.undo().unwrap().is_none());
+    }
+
+    #[test]
+    fn undo_deletes_a_file_the_turn_created() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("src/new.rs");
+
+        let mut checkpoints = store(dir.path());
+        checkpoints.begin_turn("add a module");
+        checkpoints.snapshot(&file).unwrap();
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(&file, "pub fn new() {}\n").unwrap();
+
+        checkpoints.undo().unwrap().expect("a checkpoint to undo");
+        assert!(!file.exists());
+    }
+
+    #[test]
+    fn a_restart_reopens_the_latest_conversation() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("checkpoints");
+        let file = dir.path().join("main.rs");
+        fs::write(&file, "fn main() {}\n").unwrap();
+
+        let mut first = CheckpointStore::open_latest(&root, "conversation_20260101_090000").unwrap();
+        first.begin_turn("first");
+        first.snapshot(&file).unwrap();
+        let mut second = CheckpointStore::open_new(&root).unwrap();
+        second.begin_turn("second");
+        second.snapshot(&file).unwrap();
+        // A conversation that never changed a file isn't reopened
+        CheckpointStore::open_new(&root).unwrap();
+
+        let mut reopened = CheckpointStore::open_latest(&root, "conversation_20990101_090000").unwrap();
+        let undone = reopened.undo().unwrap().expect("a checkpoint to undo");
+        assert_eq!(undone.prompt, "second");
+    }
+
+    #[test]
+    fn binary_originals_are_read_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let image = dir.path().join("logo.png");
+        let text = dir.path().join("README.md");
+        fs::write(&image, [0x89, b'P', b'N', b'G', 0xff, 0x00]).unwrap();
+        fs::write(&text, "# Logo\n").unwrap();
+
+        let mut checkpoints = store(dir.path());
+        checkpoints.begin_turn("replace the logo");
+        checkpoints.snapshot(&image).unwrap();
+        checkpoints.snapshot(&text).unwrap();
+
+        let originals = checkpoints.session_originals().unwrap();
+        assert_eq!(originals[0].1.as_deref(), Some(&[0x89, b'P', b'N', b'G', 0xff, 0x00][..]));
+        assert_eq!(originals[1].1.as_deref(), Some(&b"# Logo\n"[..]));
+    }
+
+    #[test]
+    fn snapshots_are_stored_as_absolute_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("sub/../lib.rs");
+        fs::write(dir.path().join("lib.rs"), "").unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+
+        let mut checkpoints = store(dir.path());
+        checkpoints.begin_turn("edit lib");
+        checkpoints.snapshot(&file).unwrap();
+        let snapshot = &checkpoints.checkpoints()[0].files[0];
+        assert_eq!(snapshot.path, dir.path().canonicalize().unwrap().join("lib.rs"));
+    }
+}
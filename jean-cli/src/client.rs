@@ -54,10 +54,16 @@ impl BackendClient {
                                             info!("Message type: ChatRequest");
                                             info!("Number of messages: {}", req.messages.len());
                                         }
-                                        ClientMessage::ToolResult { id, content } => {
+                                        ClientMessage::ToolResult { id, content, steer } => {
                                             info!("Message type: ToolResult");
                                             info!("Tool ID: {}", id);
                                             info!("Content length: {} chars", content.len());
+                                            if steer.is_some() {
+                                                info!("Carries a steering message");
+                                            }
+                                        }
+                                        ClientMessage::Cancel => {
+                                            info!("Message type: Cancel");
                                         }
                                     }
 
@@ -81,7 +87,7 @@ impl BackendClient {
                                             match serde_json::from_str::<StreamChunk>(&text) {
                                                 Ok(chunk) => {
                                                     match &chunk {
-                                                        StreamChunk::Text { .. } => {}
+                                                        StreamChunk::Text { .. } | StreamChunk::Reasoning { .. } => {}
                                                         StreamChunk::ToolCall { id, name, .. } => {
                                                             info!("=== RECEIVED TOOL CALL FROM SERVER ===");
                                                             info!("Tool: {} (ID: {})", name, id);
@@ -89,6 +95,10 @@ impl BackendClient {
                                                         StreamChunk::ToolResult { id, .. } => {
                                                             info!("Received tool result from server (ID: {})", id);
                                                         }
+                                                        StreamChunk::Error { message } => {
+                                                            warn!("Server reported an error: {}", message);
+                                                        }
+                                                        StreamChunk::Usage { .. } | StreamChunk::Cancelled => {}
                                                     }
                                                     if chunk_tx.send(chunk).is_err() {
                                                         error!("Failed to send chunk to receiver - channel closed?");
@@ -134,13 +144,25 @@ impl BackendClient {
         (client, chunk_rx, status_rx)
     }
 
+    /// A client that isn't connected anywhere; what it sends comes out of
+    /// the returned receiver
+    pub fn detached() -> (Self, mpsc::UnboundedReceiver<ClientMessage>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (Self { tx }, rx)
+    }
+
     pub async fn send_message(&self, request: ClientChatRequest) -> Result<()> {
         self.tx.send(ClientMessage::ChatRequest(request))?;
         Ok(())
     }
 
-    pub async fn send_tool_result(&self, id: String, content: String) -> Result<()> {
-        self.tx.send(ClientMessage::ToolResult { id, content })?;
+    pub async fn send_tool_result(&self, id: String, content: String, steer: Option<String>) -> Result<()> {
+        self.tx.send(ClientMessage::ToolResult { id, content, steer })?;
         Ok(())
     }
-}
\ No newline at end of file
+
+    pub async fn send_cancel(&self) -> Result<()> {
+        self.tx.send(ClientMessage::Cancel)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+use anyhow::{Result, bail};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+/// Terminals cap the OSC 52 payload; xterm's default is about 100KB of
+/// base64, others are lower
+const MAX_OSC52_BYTES: usize = 74_994;
+
+/// Clipboard programs, tried in order, with the environment variable that
+/// must be set for them to apply
+const CLIPBOARD_TOOLS: &[(&str, &[&str], Option<&str>)] = &[
+    ("wl-copy", &[], Some("WAYLAND_DISPLAY")),
+    ("xclip", &["-selection", "clipboard"], Some("DISPLAY")),
+    ("xsel", &["--clipboard", "--input"], Some("DISPLAY")),
+    ("pbcopy", &[], None),
+    ("clip.exe", &[], None),
+];
+
+/// Copy `text` to the clipboard. An OSC 52 escape asks the terminal to do
+/// it, which also works over SSH and in tmux; on a local session a
+/// clipboard program is used as well, for terminals without OSC 52.
+/// Returns how it was copied, e.g. "OSC 52, wl-copy".
+pub fn copy(text: &str) -> Result<String> {
+    let mut methods = Vec::new();
+
+    let encoded = STANDARD.encode(text);
+    if encoded.len() <= MAX_OSC52_BYTES {
+        let sequence = osc52(&encoded, std::env::var_os("TMUX").is_some());
+        let mut stdout = io::stdout();
+        stdout.write_all(sequence.as_bytes())?;
+        stdout.flush()?;
+        methods.push("OSC 52");
+    }
+
+    let remote = std::env::var_os("SSH_CONNECTION").is_some() || std::env::var_os("SSH_TTY").is_some();
+    if !remote && let Some(tool) = copy_with_tool(text) {
+        methods.push(tool);
+    }
+
+    if methods.is_empty() {
+        bail!(
+            "{} bytes is too much for OSC 52 and no clipboard program is available",
+            text.len()
+        );
+    }
+    Ok(methods.join(", "))
+}
+
+/// The escape that sets the clipboard to the base64 `encoded` text
+fn osc52(encoded: &str, tmux: bool) -> String {
+    let sequence = format!("\x1b]52;c;{}\x07", encoded);
+    if tmux {
+        // tmux passes escapes through when wrapped in DCS, with inner ESCs doubled
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else {
+        sequence
+    }
+}
+
+/// The first clipboard program that takes the text, if any
+fn copy_with_tool(text: &str) -> Option<&'static str> {
+    CLIPBOARD_TOOLS
+        .iter()
+        .filter(|(_, _, env)| env.is_none_or(|var| std::env::var_os(var).is_some()))
+        .find(|(program, args, _)| run_tool(program, args, text).is_ok())
+        .map(|(program, _, _)| *program)
+}
+
+fn run_tool(program: &str, args: &[&str], text: &str) -> Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        bail!("{} exited with {}", program, status);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn osc52_sets_the_clipboard_selection_to_base64() {
+        let encoded = STANDARD.encode("héllo\n");
+        assert_eq!(encoded, "aMOpbGxvCg==");
+        assert_eq!(osc52(&encoded, false), "\x1b]52;c;aMOpbGxvCg==\x07");
+    }
+
+    #[test]
+    fn osc52_inside_tmux_is_wrapped_with_escapes_doubled() {
+        assert_eq!(osc52("aGk=", true), "\x1bPtmux;\x1b\x1b]52;c;aGk=\x07\x1b\\");
+    }
+}
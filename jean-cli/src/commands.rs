@@ -0,0 +1,440 @@
+use jean_shared::ReasoningEffort;
+use std::fs;
+use std::path::Path;
+use tracing::{debug, error, warn};
+
+/// Project-specific prompt templates, one markdown file per command
+pub const TEMPLATE_DIR: &str = ".jean/commands";
+
+/// Template text is replaced with this placeholder's arguments
+const ARGUMENTS_PLACEHOLDER: &str = "$ARGUMENTS";
+
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub usage: &'static str,
+    pub description: &'static str,
+}
+
+const BUILTIN: &[CommandSpec] = &[
+    CommandSpec {
+        name: "clear",
+        usage: "",
+        description: "Start a new conversation",
+    },
+    CommandSpec {
+        name: "model",
+        usage: "[name]",
+        description: "Show or switch the model used for new requests",
+    },
+    CommandSpec {
+        name: "effort",
+        usage: "[minimal|low|medium|high|default]",
+        description: "Show or set how much the model reasons before answering",
+    },
+    CommandSpec {
+        name: "cost",
+        usage: "",
+        description: "Token usage and estimated cost of this session",
+    },
+    CommandSpec {
+        name: "save",
+        usage: "[name]",
+        description: "Save the conversation so it can be loaded later",
+    },
+    CommandSpec {
+        name: "load",
+        usage: "[name]",
+        description: "Load a saved conversation, or list them",
+    },
+    CommandSpec {
+        name: "export",
+        usage: "[path]",
+        description: "Write the conversation to a markdown file",
+    },
+    CommandSpec {
+        name: "undo",
+        usage: "",
+        description: "Revert the files changed during the last turn",
+    },
+    CommandSpec {
+        name: "diff",
+        usage: "",
+        description: "Review every file changed this session",
+    },
+    CommandSpec {
+        name: "checkpoints",
+        usage: "[restore <id>]",
+        description: "List checkpoints, or restore the workspace to one",
+    },
+    CommandSpec {
+        name: "help",
+        usage: "",
+        description: "List available commands",
+    },
+];
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Clear,
+    Model(Option<String>),
+    Effort(Option<ReasoningEffort>),
+    /// Go back to the model's own reasoning effort
+    DefaultEffort,
+    Cost,
+    Save(Option<String>),
+    Load(Option<String>),
+    Export(Option<String>),
+    Undo,
+    Diff,
+    Checkpoints,
+    RestoreCheckpoint(u64),
+    Help,
+    /// A user-defined template, expanded into the prompt to send
+    Prompt(String),
+}
+
+/// A user-defined command: `<TEMPLATE_DIR>/<name>.md` holds the prompt,
+/// with `$ARGUMENTS` standing for whatever follows the command name. An
+/// optional `---` front matter block can set `description:`.
+pub struct PromptTemplate {
+    pub name: String,
+    pub description: String,
+    body: String,
+}
+
+impl PromptTemplate {
+    fn parse(name: String, content: &str) -> Self {
+        let mut description = None;
+        let mut body = content;
+        if let Some(rest) = content.strip_prefix("---\n")
+            && let Some((front_matter, after)) = rest.split_once("\n---\n")
+        {
+            description = front_matter
+                .lines()
+                .find_map(|line| line.strip_prefix("description:"))
+                .map(|d| d.trim().to_string());
+            body = after;
+        }
+
+        // Without a description, use the first line of the prompt
+        let description = description.unwrap_or_else(|| {
+            let first = body.lines().find(|l| !l.trim().is_empty()).unwrap_or_default();
+            first.trim_start_matches('#').trim().chars().take(60).collect()
+        });
+
+        Self {
+            name,
+            description,
+            body: body.trim().to_string(),
+        }
+    }
+
+    fn expand(&self, arguments: &str) -> String {
+        if self.body.contains(ARGUMENTS_PLACEHOLDER) {
+            self.body.replace(ARGUMENTS_PLACEHOLDER, arguments)
+        } else if arguments.is_empty() {
+            self.body.clone()
+        } else {
+            format!("{}\n\n{}", self.body, arguments)
+        }
+    }
+}
+
+/// Built-in commands plus the project's prompt templates
+pub struct CommandRegistry {
+    templates: Vec<PromptTemplate>,
+}
+
+impl CommandRegistry {
+    pub fn load(dir: &Path) -> Self {
+        let mut templates = Vec::new();
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    error!("Failed to read command templates in {:?}: {}", dir, e);
+                }
+                return Self { templates };
+            }
+        };
+
+        for entry in entries.filter_map(Result::ok) {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "md") {
+                continue;
+            }
+            let Some(name) = path.file_stem().map(|s| s.to_string_lossy().into_owned()) else {
+                continue;
+            };
+            if BUILTIN.iter().any(|spec| spec.name == name) {
+                warn!("Command template {:?} shadows a built-in command, ignoring", path);
+                continue;
+            }
+            match fs::read_to_string(&path) {
+                Ok(content) => templates.push(PromptTemplate::parse(name, &content)),
+                Err(e) => error!("Failed to read command template {:?}: {}", path, e),
+            }
+        }
+
+        templates.sort_by(|a, b| a.name.cmp(&b.name));
+        debug!("Loaded {} command templates from {:?}", templates.len(), dir);
+        Self { templates }
+    }
+
+    /// Parse `/name args...`. Returns `None` when the input isn't a command
+    /// at all (including absolute paths like `/usr/bin`), so it can be sent
+    /// as a prompt, and an error message for unknown commands or bad
+    /// arguments.
+    pub fn parse(&self, input: &str) -> Option<Result<Command, String>> {
+        let input = input.trim();
+        let rest = input.strip_prefix('/')?;
+        let (name, arguments) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+            return None;
+        }
+        let arguments = arguments.trim();
+
+        if let Some(template) = self.templates.iter().find(|t| t.name == name) {
+            return Some(Ok(Command::Prompt(template.expand(arguments))));
+        }
+
+        let Some(spec) = BUILTIN.iter().find(|spec| spec.name == name) else {
+            return Some(Err(format!("Unknown command /{} (see /help)", name)));
+        };
+        let usage = || format!("Usage: /{} {}", spec.name, spec.usage).trim_end().to_string();
+
+        let args = match split_args(arguments) {
+            Ok(args) => args,
+            Err(e) => return Some(Err(e)),
+        };
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let optional = |args: &[&str]| args.first().map(|a| a.to_string());
+
+        let command = match (spec.name, args.as_slice()) {
+            ("clear", []) => Command::Clear,
+            ("model", [] | [_]) => Command::Model(optional(&args)),
+            ("effort", []) => Command::Effort(None),
+            ("effort", ["default"]) => Command::DefaultEffort,
+            ("effort", [effort]) => match effort.parse() {
+                Ok(effort) => Command::Effort(Some(effort)),
+                Err(e) => return Some(Err(format!("{}, or default", e))),
+            },
+            ("cost", []) => Command::Cost,
+            ("save", [] | [_]) => Command::Save(optional(&args)),
+            ("load", [] | [_]) => Command::Load(optional(&args)),
+            ("export", [] | [_]) => Command::Export(optional(&args)),
+            ("undo", []) => Command::Undo,
+            ("diff", []) => Command::Diff,
+            ("checkpoints", []) => Command::Checkpoints,
+            ("checkpoints", ["restore", id]) => match id.parse() {
+                Ok(id) => Command::RestoreCheckpoint(id),
+                Err(_) => return Some(Err(format!("Invalid checkpoint id '{}'", id))),
+            },
+            ("help", []) => Command::Help,
+            _ => return Some(Err(usage())),
+        };
+        Some(Ok(command))
+    }
+
+    /// Commands whose name starts with what's typed after the `/`, as
+    /// (`/name usage`, description) pairs for the suggestion list. Empty
+    /// once arguments are being typed.
+    pub fn completions(&self, input: &str) -> Vec<(String, String)> {
+        let Some(prefix) = input.strip_prefix('/') else {
+            return Vec::new();
+        };
+        if prefix.contains(char::is_whitespace) {
+            return Vec::new();
+        }
+
+        let builtins = BUILTIN
+            .iter()
+            .filter(|spec| spec.name.starts_with(prefix))
+            .map(|spec| {
+                let usage = format!("/{} {}", spec.name, spec.usage);
+                (usage.trim_end().to_string(), spec.description.to_string())
+            });
+        let templates = self
+            .templates
+            .iter()
+            .filter(|t| t.name.starts_with(prefix))
+            .map(|t| (format!("/{} [arguments]", t.name), t.description.clone()));
+        builtins.chain(templates).collect()
+    }
+
+    /// Tab completion of the command name: the unique match followed by a
+    /// space, or the longest prefix the matches share
+    pub fn complete(&self, input: &str) -> Option<String> {
+        let prefix = input.strip_prefix('/')?;
+        if prefix.contains(char::is_whitespace) {
+            return None;
+        }
+
+        let names: Vec<&str> = BUILTIN
+            .iter()
+            .map(|spec| spec.name)
+            .chain(self.templates.iter().map(|t| t.name.as_str()))
+            .filter(|name| name.starts_with(prefix))
+            .collect();
+        match names.as_slice() {
+            [] => None,
+            [name] => Some(format!("/{} ", name)),
+            [first, rest @ ..] => {
+                let common = rest.iter().fold(*first, |common, name| {
+                    let len = common
+                        .chars()
+                        .zip(name.chars())
+                        .take_while(|(a, b)| a == b)
+                        .map(|(a, _)| a.len_utf8())
+                        .sum();
+                    &common[..len]
+                });
+                Some(format!("/{}", common))
+            }
+        }
+    }
+
+    pub fn help(&self) -> String {
+        let mut lines = vec!["Commands:".to_string()];
+        for spec in BUILTIN {
+            let usage = format!("/{} {}", spec.name, spec.usage);
+            lines.push(format!("  {:<28} {}", usage.trim_end(), spec.description));
+        }
+        if !self.templates.is_empty() {
+            lines.push(format!("Project commands ({}):", TEMPLATE_DIR));
+            for template in &self.templates {
+                let usage = format!("/{} [arguments]", template.name);
+                lines.push(format!("  {:<28} {}", usage, template.description));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// Split arguments on whitespace, keeping "double" or 'single' quoted
+/// parts together so names and paths may contain spaces
+fn split_args(input: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote = None;
+
+    for c in input.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            None if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            None => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+
+    if quote.is_some() {
+        return Err("Unterminated quote in arguments".to_string());
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry(templates: &[(&str, &str)]) -> CommandRegistry {
+        CommandRegistry {
+            templates: templates
+                .iter()
+                .map(|(name, content)| PromptTemplate::parse(name.to_string(), content))
+                .collect(),
+        }
+    }
+
+    fn args(input: &str) -> Result<Vec<String>, String> {
+        split_args(input)
+    }
+
+    #[test]
+    fn splits_arguments_keeping_quoted_parts_together() {
+        assert_eq!(args("  one   two "), Ok(vec!["one".to_string(), "two".to_string()]));
+        assert_eq!(args(r#""my notes" 'it''s'"#), Ok(vec!["my notes".to_string(), "its".to_string()]));
+        // Quotes may start or end inside an argument
+        assert_eq!(args(r#"notes/"week 1".md"#), Ok(vec!["notes/week 1.md".to_string()]));
+        assert_eq!(args(r#"'say "hi"'"#), Ok(vec![r#"say "hi""#.to_string()]));
+        assert_eq!(args(r#""""#), Ok(vec![String::new()]));
+        assert_eq!(args(""), Ok(vec![]));
+        assert_eq!(args(r#""unfinished"#), Err("Unterminated quote in arguments".to_string()));
+    }
+
+    #[test]
+    fn parses_builtins_and_their_arguments() {
+        let commands = registry(&[]);
+        let parse = |input| commands.parse(input);
+
+        assert_eq!(parse("/clear"), Some(Ok(Command::Clear)));
+        assert_eq!(parse("  /save 'release notes'  "), Some(Ok(Command::Save(Some("release notes".to_string())))));
+        assert_eq!(parse("/effort high"), Some(Ok(Command::Effort(Some(ReasoningEffort::High)))));
+        assert_eq!(parse("/effort default"), Some(Ok(Command::DefaultEffort)));
+        assert_eq!(parse("/checkpoints restore 3"), Some(Ok(Command::RestoreCheckpoint(3))));
+        assert_eq!(parse("/model a b"), Some(Err("Usage: /model [name]".to_string())));
+        assert_eq!(parse("/undo now"), Some(Err("Usage: /undo".to_string())));
+        assert_eq!(parse("/checkpoints restore x"), Some(Err("Invalid checkpoint id 'x'".to_string())));
+        assert_eq!(parse("/nope"), Some(Err("Unknown command /nope (see /help)".to_string())));
+        // Not commands at all
+        assert_eq!(parse("/usr/bin/env is missing"), None);
+        assert_eq!(parse("hello /clear"), None);
+        assert_eq!(parse("/"), None);
+    }
+
+    #[test]
+    fn templates_take_front_matter_and_arguments() {
+        let template = PromptTemplate::parse(
+            "review".to_string(),
+            "---\ndescription: Review a file\n---\nReview $ARGUMENTS for bugs.\n",
+        );
+        assert_eq!(template.description, "Review a file");
+        assert_eq!(template.expand("src/main.rs"), "Review src/main.rs for bugs.");
+
+        // Without a placeholder, arguments go after the prompt
+        let template = PromptTemplate::parse("tests".to_string(), "\n# Write tests\n\nCover the edge cases.\n");
+        assert_eq!(template.description, "Write tests");
+        assert_eq!(template.expand(""), "# Write tests\n\nCover the edge cases.");
+        assert_eq!(template.expand("for the parser"), "# Write tests\n\nCover the edge cases.\n\nfor the parser");
+
+        let commands = registry(&[("review", "Review $ARGUMENTS.")]);
+        assert_eq!(
+            commands.parse("/review  the lexer "),
+            Some(Ok(Command::Prompt("Review the lexer.".to_string())))
+        );
+    }
+
+    #[test]
+    fn completes_to_the_unique_match_or_the_shared_prefix() {
+        let commands = registry(&[("café", "x"), ("cafés", "x"), ("review", "x")]);
+
+        assert_eq!(commands.complete("/cl"), Some("/clear ".to_string()));
+        assert_eq!(commands.complete("/re"), Some("/review ".to_string()));
+        // clear, cost, checkpoints, café and cafés
+        assert_eq!(commands.complete("/c"), Some("/c".to_string()));
+        assert_eq!(commands.complete("/caf"), Some("/café".to_string()));
+        assert_eq!(commands.complete("/x"), None);
+        assert_eq!(commands.complete("/clear "), None);
+        assert_eq!(commands.complete("clear"), None);
+
+        let listed: Vec<String> = commands.completions("/ch").into_iter().map(|(usage, _)| usage).collect();
+        assert_eq!(listed, ["/checkpoints [restore <id>]"]);
+        assert!(commands.completions("/model gpt").is_empty());
+    }
+}
@@ -0,0 +1,73 @@
+use crate::theme::ThemeConfig;
+use jean_shared::ReasoningEffort;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// User settings from `config.json` in the config directory, e.g.
+///
+/// ```json
+/// {
+///   "vim": true,
+///   "approve_edits": true,
+///   "reasoning_effort": "low",
+///   "keys": { "ctrl-k": "search", "ctrl-t": "none" },
+///   "theme": "solarized",
+///   "themes": { "solarized": { "base": "light", "user": "bold #268bd2" } }
+/// }
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Key bindings on top of the defaults: key name to action name, or
+    /// "none" to unbind the key
+    pub keys: HashMap<String, String>,
+    /// Edit the prompt with vim-style normal and insert modes
+    pub vim: bool,
+    /// Show file edits as a diff to approve before the tool runs
+    pub approve_edits: bool,
+    /// Send prompts typed during a turn along with the next tool result,
+    /// instead of after the turn
+    pub steer: bool,
+    /// Reasoning effort asked of models that support it: "minimal", "low",
+    /// "medium" or "high"; the model's default when unset
+    pub reasoning_effort: Option<ReasoningEffort>,
+    /// A built-in theme or one from `themes`; when unset, `NO_COLOR` and
+    /// the terminal background decide
+    pub theme: Option<String>,
+    /// User-defined themes by name
+    pub themes: HashMap<String, ThemeConfig>,
+}
+
+impl Config {
+    /// `$JEAN_CONFIG`, or `jean/config.json` under `$XDG_CONFIG_HOME` or
+    /// `~/.config`
+    pub fn path() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os("JEAN_CONFIG") {
+            return Some(PathBuf::from(path));
+        }
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config_dir.join("jean").join("config.json"))
+    }
+
+    /// Load the config file, falling back to the defaults when there is
+    /// none. A file that can't be read or parsed is reported as an error
+    /// and ignored.
+    pub fn load() -> (Self, Option<String>) {
+        let Some(path) = Self::path() else {
+            return (Self::default(), None);
+        };
+        let json = match fs::read_to_string(&path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return (Self::default(), None),
+            Err(e) => return (Self::default(), Some(format!("Failed to read {}: {}", path.display(), e))),
+        };
+        match serde_json::from_str(&json) {
+            Ok(config) => (config, None),
+            Err(e) => (Self::default(), Some(format!("Ignoring {}: {}", path.display(), e))),
+        }
+    }
+}
@@ -39,14 +39,19 @@ pub enum EntryType {
 }
 
 pub struct ConversationLogger {
-    _log_dir: PathBuf,
+    log_dir: PathBuf,
     current_log_file: Option<PathBuf>,
     _session_start: DateTime<Local>,
 }
 
 impl ConversationLogger {
     pub fn new() -> Result<Self> {
-        let log_dir = PathBuf::from("conversation_logs");
+        Self::in_dir(PathBuf::from("conversation_logs"))
+    }
+
+    /// Log to a new file in `log_dir`, which the app's other state is kept
+    /// next to
+    pub fn in_dir(log_dir: PathBuf) -> Result<Self> {
         if !log_dir.exists() {
             fs::create_dir_all(&log_dir)?;
         }
@@ -58,7 +63,7 @@ impl ConversationLogger {
         debug!("Starting conversation logger: {:?}", log_file);
 
         Ok(Self {
-            _log_dir: log_dir,
+            log_dir,
             current_log_file: Some(log_file),
             _session_start: session_start,
         })
@@ -118,7 +123,7 @@ impl ConversationLogger {
         // Only log tool calls and tool results from chunks
         // Text streaming chunks are ignored as we'll log the complete message later
         match chunk {
-            StreamChunk::Text { .. } => {
+            StreamChunk::Text { .. } | StreamChunk::Reasoning { .. } | StreamChunk::Usage { .. } | StreamChunk::Cancelled | StreamChunk::Error { .. } => {
                 // Don't log streaming text chunks
                 Ok(())
             }
@@ -153,6 +158,10 @@ impl ConversationLogger {
     pub fn get_current_log_path(&self) -> Option<&Path> {
         self.current_log_file.as_deref()
     }
+
+    pub fn log_dir(&self) -> &Path {
+        &self.log_dir
+    }
 }
 
 impl Default for ConversationLogger {
@@ -160,7 +169,7 @@ impl Default for ConversationLogger {
         Self::new().unwrap_or_else(|e| {
             error!("Failed to create conversation logger: {}", e);
             Self {
-                _log_dir: PathBuf::from("conversation_logs"),
+                log_dir: PathBuf::from("conversation_logs"),
                 current_log_file: None,
                 _session_start: Local::now(),
             }
@@ -0,0 +1,757 @@
+use crate::highlight;
+use crate::search::restyle;
+use crate::theme;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+use serde::{Deserialize, Serialize};
+use unicode_width::UnicodeWidthChar;
+
+/// Unchanged lines shown around each change
+const CONTEXT_LINES: usize = 3;
+/// Beyond this many differing lines the middle of the files is shown as
+/// replaced wholesale; finding the exact edits would cost too much
+const MAX_LINE_EDITS: usize = 2000;
+/// Lines differing in more words than this aren't compared word by word
+const MAX_WORD_EDITS: usize = 64;
+const TAB: &str = "    ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// A line of a diff with its index in the old and new text
+#[derive(Debug, Clone, Copy)]
+pub struct DiffLine {
+    pub op: Op,
+    pub old: Option<usize>,
+    pub new: Option<usize>,
+}
+
+/// A run of changes with the context around them
+#[derive(Debug)]
+pub struct Hunk {
+    /// 1-based, as in `@@ -old_start,old_len +new_start,new_len @@`
+    pub old_start: usize,
+    pub old_len: usize,
+    pub new_start: usize,
+    pub new_len: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffLayout {
+    Unified,
+    /// Old on the left, new on the right, in the given total width
+    SideBySide(usize),
+}
+
+/// A file before and after a change; `None` when it doesn't exist
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileChange {
+    pub path: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+impl FileChange {
+    /// Lines added and removed
+    pub fn stats(&self) -> (usize, usize) {
+        let (old, new) = self.texts();
+        let lines = diff_lines(&lines(&old), &lines(&new));
+        let added = lines.iter().filter(|l| l.op == Op::Insert).count();
+        let removed = lines.iter().filter(|l| l.op == Op::Delete).count();
+        (added, removed)
+    }
+
+    /// The diff as styled lines: a header naming the file, then each hunk
+    /// with syntax highlighting and the changed words within lines marked
+    pub fn render(&self, layout: DiffLayout) -> Vec<Line<'static>> {
+        let theme = theme::current();
+        let (old, new) = self.texts();
+        let (old_lines, new_lines) = (lines(&old), lines(&new));
+        let diff = diff_lines(&old_lines, &new_lines);
+        let added = diff.iter().filter(|l| l.op == Op::Insert).count();
+        let removed = diff.iter().filter(|l| l.op == Op::Delete).count();
+
+        let mut header = vec![Span::styled(self.path.clone(), theme.accent.add_modifier(Modifier::BOLD))];
+        match (&self.before, &self.after) {
+            (None, Some(_)) => header.push(Span::styled("  (new file)", theme.muted)),
+            (Some(_), None) => header.push(Span::styled("  (deleted)", theme.muted)),
+            _ => {}
+        }
+        header.push(Span::styled(format!("  +{}", added), theme.success));
+        header.push(Span::styled(format!(" -{}", removed), theme.error));
+        let mut rendered = vec![Line::from(header)];
+
+        let hunks = hunks(&diff, CONTEXT_LINES);
+        if hunks.is_empty() {
+            rendered.push(Line::styled("  (no changes)", theme.muted));
+            return rendered;
+        }
+
+        let sides = Sides {
+            old: Side::new(&self.path, &old, &old_lines),
+            new: Side::new(&self.path, &new, &new_lines),
+            number_width: old_lines.len().max(new_lines.len()).max(1).to_string().len(),
+        };
+        for hunk in &hunks {
+            rendered.push(Line::styled(
+                format!(
+                    "@@ -{},{} +{},{} @@",
+                    hunk.old_start, hunk.old_len, hunk.new_start, hunk.new_len
+                ),
+                theme.muted,
+            ));
+            let emphasis = word_changes(&hunk.lines, &old_lines, &new_lines);
+            match layout {
+                DiffLayout::Unified => sides.unified(hunk, &emphasis, &mut rendered),
+                DiffLayout::SideBySide(width) => sides.side_by_side(hunk, &emphasis, width, &mut rendered),
+            }
+        }
+        rendered
+    }
+
+    /// Both versions with tabs expanded, so columns line up
+    fn texts(&self) -> (String, String) {
+        let expand = |text: &Option<String>| text.as_deref().unwrap_or_default().replace('\t', TAB);
+        (expand(&self.before), expand(&self.after))
+    }
+}
+
+fn lines(text: &str) -> Vec<&str> {
+    text.lines().collect()
+}
+
+/// Line by line diff of `old` and `new`
+pub fn diff_lines(old: &[&str], new: &[&str]) -> Vec<DiffLine> {
+    let mut lines = Vec::with_capacity(old.len().max(new.len()));
+    let (mut o, mut n) = (0, 0);
+    for op in diff_ops(old, new, MAX_LINE_EDITS) {
+        lines.push(DiffLine {
+            op,
+            old: (op != Op::Insert).then_some(o),
+            new: (op != Op::Delete).then_some(n),
+        });
+        if op != Op::Insert {
+            o += 1;
+        }
+        if op != Op::Delete {
+            n += 1;
+        }
+    }
+    lines
+}
+
+/// Group the changed lines of a diff into hunks with `context` lines of
+/// unchanged text around them; changes closer than that share a hunk
+pub fn hunks(diff: &[DiffLine], context: usize) -> Vec<Hunk> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for (i, line) in diff.iter().enumerate() {
+        if line.op == Op::Equal {
+            continue;
+        }
+        let start = i.saturating_sub(context);
+        let end = (i + context + 1).min(diff.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            let before = &diff[..start];
+            let lines = diff[start..end].to_vec();
+            let old_len = lines.iter().filter(|l| l.op != Op::Insert).count();
+            let new_len = lines.iter().filter(|l| l.op != Op::Delete).count();
+            let old_before = before.iter().filter(|l| l.op != Op::Insert).count();
+            let new_before = before.iter().filter(|l| l.op != Op::Delete).count();
+            Hunk {
+                // An empty side points at the line before it, as diff does
+                old_start: old_before + (old_len > 0) as usize,
+                old_len,
+                new_start: new_before + (new_len > 0) as usize,
+                new_len,
+                lines,
+            }
+        })
+        .collect()
+}
+
+/// Edit script from `a` to `b`. The common prefix and suffix are split off
+/// first; the rest is diffed with Myers' algorithm, or shown as replaced
+/// outright when it takes more than `max_edits` edits.
+fn diff_ops<T: PartialEq>(a: &[T], b: &[T], max_edits: usize) -> Vec<Op> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let mut ops = vec![Op::Equal; prefix];
+    match myers(a_mid, b_mid, max_edits) {
+        Some(middle) => ops.extend(middle),
+        None => {
+            ops.extend(std::iter::repeat_n(Op::Delete, a_mid.len()));
+            ops.extend(std::iter::repeat_n(Op::Insert, b_mid.len()));
+        }
+    }
+    ops.extend(std::iter::repeat_n(Op::Equal, suffix));
+    ops
+}
+
+/// Shortest edit script by Myers' O(ND) algorithm. Keeps the frontier of
+/// each round to walk the path back, so memory grows with the square of
+/// the number of edits; gives up past `max_edits`.
+fn myers<T: PartialEq>(a: &[T], b: &[T], max_edits: usize) -> Option<Vec<Op>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (n + m).min(max_edits as isize);
+    let offset = max + 1;
+    let mut v = vec![0isize; 2 * offset as usize + 1];
+    // Round d's frontier, diagonals -(d+1)..=(d+1), as it was before the round
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    let at = |k: isize| (k + offset) as usize;
+
+    for d in 0..=max {
+        trace.push(v[at(-d - 1)..=at(d + 1)].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[at(k - 1)] < v[at(k + 1)]) {
+                v[at(k + 1)]
+            } else {
+                v[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[at(k)] = x;
+            if x >= n && y >= m {
+                return Some(backtrack(&trace, n, m));
+            }
+        }
+    }
+    None
+}
+
+fn backtrack(trace: &[Vec<isize>], n: isize, m: isize) -> Vec<Op> {
+    let mut ops = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let at = |k: isize| (k + d + 1) as usize;
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && v[at(k - 1)] < v[at(k + 1)]) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = v[at(prev_k)];
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            ops.push(Op::Equal);
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            ops.push(if x == prev_x { Op::Insert } else { Op::Delete });
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    ops.reverse();
+    ops
+}
+
+/// Byte ranges within a line
+type Ranges = Vec<(usize, usize)>;
+
+/// Byte ranges of the changed words in replaced lines, by old and new line
+/// index. Within a hunk, the removed lines of a block are paired up with
+/// the added lines that follow them.
+struct WordChanges {
+    old: Vec<(usize, Ranges)>,
+    new: Vec<(usize, Ranges)>,
+}
+
+impl WordChanges {
+    fn in_old(&self, line: usize) -> &[(usize, usize)] {
+        lookup(&self.old, line)
+    }
+
+    fn in_new(&self, line: usize) -> &[(usize, usize)] {
+        lookup(&self.new, line)
+    }
+}
+
+fn lookup(ranges: &[(usize, Ranges)], line: usize) -> &[(usize, usize)] {
+    ranges
+        .iter()
+        .find(|(l, _)| *l == line)
+        .map(|(_, ranges)| ranges.as_slice())
+        .unwrap_or_default()
+}
+
+fn word_changes(lines: &[DiffLine], old: &[&str], new: &[&str]) -> WordChanges {
+    let mut changes = WordChanges {
+        old: Vec::new(),
+        new: Vec::new(),
+    };
+    for (deleted, inserted) in change_blocks(lines) {
+        for (d, i) in deleted.iter().zip(&inserted) {
+            let (Some(o), Some(n)) = (d.old, i.new) else {
+                continue;
+            };
+            if let Some((old_ranges, new_ranges)) = changed_words(old[o], new[n]) {
+                changes.old.push((o, old_ranges));
+                changes.new.push((n, new_ranges));
+            }
+        }
+    }
+    changes
+}
+
+/// Each run of removed lines with the run of added lines right after it
+fn change_blocks(lines: &[DiffLine]) -> Vec<(Vec<DiffLine>, Vec<DiffLine>)> {
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        if lines[i].op == Op::Equal {
+            i += 1;
+            continue;
+        }
+        let deleted: Vec<DiffLine> = lines[i..].iter().take_while(|l| l.op == Op::Delete).copied().collect();
+        i += deleted.len();
+        let inserted: Vec<DiffLine> = lines[i..].iter().take_while(|l| l.op == Op::Insert).copied().collect();
+        i += inserted.len();
+        blocks.push((deleted, inserted));
+    }
+    blocks
+}
+
+/// The words that differ between two versions of a line, as byte ranges in
+/// each. `None` when the lines have too little in common for that to help.
+fn changed_words(old: &str, new: &str) -> Option<(Ranges, Ranges)> {
+    let old_words = words(old);
+    let new_words = words(new);
+    let old_tokens: Vec<&str> = old_words.iter().map(|&(s, e)| &old[s..e]).collect();
+    let new_tokens: Vec<&str> = new_words.iter().map(|&(s, e)| &new[s..e]).collect();
+    let ops = myers(&old_tokens, &new_tokens, MAX_WORD_EDITS)?;
+
+    let (mut old_ranges, mut new_ranges) = (Vec::new(), Vec::new());
+    let (mut o, mut n) = (0, 0);
+    let mut unchanged = 0;
+    for op in ops {
+        match op {
+            Op::Equal => {
+                unchanged += old_words[o].1 - old_words[o].0;
+                o += 1;
+                n += 1;
+            }
+            Op::Delete => {
+                push_range(&mut old_ranges, old_words[o]);
+                o += 1;
+            }
+            Op::Insert => {
+                push_range(&mut new_ranges, new_words[n]);
+                n += 1;
+            }
+        }
+    }
+    // Marking most of the line says no more than the line's own color
+    let significant = old.trim().len().max(new.trim().len());
+    (unchanged * 2 >= significant).then_some((old_ranges, new_ranges))
+}
+
+/// Add a range, merging it with the previous one when they touch
+fn push_range(ranges: &mut Vec<(usize, usize)>, (start, end): (usize, usize)) {
+    match ranges.last_mut() {
+        Some(last) if last.1 == start => last.1 = end,
+        _ => ranges.push((start, end)),
+    }
+}
+
+/// Words, runs of whitespace and single punctuation characters, as byte
+/// ranges
+fn words(line: &str) -> Vec<(usize, usize)> {
+    let class = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            0
+        } else if c.is_whitespace() {
+            1
+        } else {
+            2
+        }
+    };
+    let mut words: Vec<(usize, usize)> = Vec::new();
+    let mut previous = None;
+    for (i, c) in line.char_indices() {
+        let current = class(c);
+        match words.last_mut() {
+            Some(last) if previous == Some(current) && current != 2 => last.1 = i + c.len_utf8(),
+            _ => words.push((i, i + c.len_utf8())),
+        }
+        previous = Some(current);
+    }
+    words
+}
+
+/// One version of the file, with its highlighted lines when the file type
+/// is known
+struct Side<'a> {
+    lines: &'a [&'a str],
+    highlighted: Option<highlight::HighlightedLines>,
+}
+
+impl<'a> Side<'a> {
+    fn new(path: &str, text: &str, lines: &'a [&'a str]) -> Self {
+        let highlighted = highlight::highlight_file(text, path).filter(|h| h.len() == lines.len());
+        Self { lines, highlighted }
+    }
+
+    /// Line `index` styled for a diff: highlighted, with `base` patched on
+    /// for added and removed lines and `emphasis` on the changed words
+    fn content(&self, index: usize, base: Style, words: &[(usize, usize)], emphasis: Style) -> Line<'static> {
+        let mut line = match &self.highlighted {
+            Some(highlighted) => Line::from(highlighted[index].clone()),
+            None => Line::styled(self.lines[index].to_string(), theme::current().code),
+        };
+        for span in &mut line.spans {
+            span.style = span.style.patch(base);
+        }
+        let ranges: Vec<(usize, usize, Style)> = words.iter().map(|&(s, e)| (s, e, emphasis)).collect();
+        restyle(&mut line, &ranges);
+        line
+    }
+}
+
+struct Sides<'a> {
+    old: Side<'a>,
+    new: Side<'a>,
+    number_width: usize,
+}
+
+impl Sides<'_> {
+    fn line(&self, line: &DiffLine, emphasis: &WordChanges) -> (Option<Line<'static>>, Option<Line<'static>>) {
+        let theme = theme::current();
+        let old = line.old.map(|o| match line.op {
+            Op::Delete => self.old.content(o, theme.diff_removed, emphasis.in_old(o), theme.diff_removed_emphasis),
+            _ => self.old.content(o, Style::default(), &[], Style::default()),
+        });
+        let new = line.new.map(|n| match line.op {
+            Op::Insert => self.new.content(n, theme.diff_added, emphasis.in_new(n), theme.diff_added_emphasis),
+            _ => self.new.content(n, Style::default(), &[], Style::default()),
+        });
+        (old, new)
+    }
+
+    /// Line numbers and the change marker in front of a line
+    fn gutter(&self, number: Option<usize>, op: Op) -> Vec<Span<'static>> {
+        let theme = theme::current();
+        let number = match number {
+            Some(n) => format!("{:>width$} ", n + 1, width = self.number_width),
+            None => " ".repeat(self.number_width + 1),
+        };
+        let marker = match op {
+            Op::Equal => Span::raw("  "),
+            Op::Delete => Span::styled("- ", theme.error.patch(theme.diff_removed)),
+            Op::Insert => Span::styled("+ ", theme.success.patch(theme.diff_added)),
+        };
+        vec![Span::styled(number, theme.muted), marker]
+    }
+
+    fn unified(&self, hunk: &Hunk, emphasis: &WordChanges, rendered: &mut Vec<Line<'static>>) {
+        for line in &hunk.lines {
+            let (old, new) = self.line(line, emphasis);
+            let (number, content) = match line.op {
+                Op::Delete => (line.old, old),
+                _ => (line.new, new),
+            };
+            let mut spans = self.gutter(number, line.op);
+            spans.extend(content.map(|c| c.spans).unwrap_or_default());
+            rendered.push(Line::from(spans));
+        }
+    }
+
+    fn side_by_side(&self, hunk: &Hunk, emphasis: &WordChanges, width: usize, rendered: &mut Vec<Line<'static>>) {
+        let theme = theme::current();
+        let column = width.saturating_sub(3) / 2;
+        let cell = |line: Option<(&DiffLine, Line<'static>, Option<usize>)>| -> Vec<Span<'static>> {
+            match line {
+                Some((diff_line, content, number)) => {
+                    let mut spans = self.gutter(number, diff_line.op);
+                    spans.extend(content.spans);
+                    let pad = match diff_line.op {
+                        Op::Equal => Style::default(),
+                        Op::Delete => theme.diff_removed,
+                        Op::Insert => theme.diff_added,
+                    };
+                    fit(spans, column, pad)
+                }
+                None => vec![Span::raw(" ".repeat(column))],
+            }
+        };
+        let mut row = |left, right| {
+            let mut spans = cell(left);
+            spans.push(Span::styled(" │ ", theme.muted));
+            spans.extend(cell(right));
+            rendered.push(Line::from(spans));
+        };
+
+        let mut i = 0;
+        while i < hunk.lines.len() {
+            let line = &hunk.lines[i];
+            if line.op == Op::Equal {
+                let (old, new) = self.line(line, emphasis);
+                row(old.map(|o| (line, o, line.old)), new.map(|n| (line, n, line.new)));
+                i += 1;
+                continue;
+            }
+            // Removed lines on the left next to the lines that replace them
+            let deleted: Vec<&DiffLine> = hunk.lines[i..].iter().take_while(|l| l.op == Op::Delete).collect();
+            let inserted: Vec<&DiffLine> = hunk.lines[i + deleted.len()..]
+                .iter()
+                .take_while(|l| l.op == Op::Insert)
+                .collect();
+            for r in 0..deleted.len().max(inserted.len()) {
+                let left = deleted.get(r).map(|&l| (l, self.line(l, emphasis).0.unwrap_or_default(), l.old));
+                let right = inserted.get(r).map(|&l| (l, self.line(l, emphasis).1.unwrap_or_default(), l.new));
+                row(left, right);
+            }
+            i += deleted.len() + inserted.len();
+        }
+    }
+}
+
+/// Cut the spans to `width` columns, or pad them to it with `pad`
+fn fit(spans: Vec<Span<'static>>, width: usize, pad: Style) -> Vec<Span<'static>> {
+    let mut fitted = Vec::new();
+    let mut used = 0;
+    for span in spans {
+        let mut text = String::new();
+        for c in span.content.chars() {
+            let w = c.width().unwrap_or(0);
+            if used + w > width {
+                break;
+            }
+            used += w;
+            text.push(c);
+        }
+        let truncated = text.len() < span.content.len();
+        fitted.push(Span::styled(text, span.style));
+        if truncated {
+            break;
+        }
+    }
+    if used < width {
+        fitted.push(Span::styled(" ".repeat(width - used), pad));
+    }
+    fitted
+}
+
+/// What a key did in the diff viewer
+#[derive(Debug, PartialEq, Eq)]
+pub enum ViewerOutcome {
+    Handled,
+    Close,
+    Approve,
+    Reject,
+}
+
+/// Full-screen review of file changes, for `/diff` and for approving an
+/// edit before the tool runs
+pub struct DiffViewer {
+    pub title: String,
+    pub changes: Vec<FileChange>,
+    /// Asks for approval instead of just showing the changes
+    pub approval: bool,
+    pub side_by_side: bool,
+    scroll: usize,
+    /// Rendered lines, with the layout they were rendered for
+    rendered: Option<(DiffLayout, Vec<Line<'static>>)>,
+    page: usize,
+}
+
+impl DiffViewer {
+    pub fn new(title: String, changes: Vec<FileChange>, approval: bool) -> Self {
+        Self {
+            title,
+            changes,
+            approval,
+            side_by_side: false,
+            scroll: 0,
+            rendered: None,
+            page: 1,
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> ViewerOutcome {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Enter if self.approval => return ViewerOutcome::Approve,
+            KeyCode::Char('n') | KeyCode::Esc if self.approval => return ViewerOutcome::Reject,
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Enter => return ViewerOutcome::Close,
+            KeyCode::Char('s') => self.side_by_side = !self.side_by_side,
+            KeyCode::Up | KeyCode::Char('k') => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.scroll += 1,
+            KeyCode::PageUp | KeyCode::Char('b') => self.scroll = self.scroll.saturating_sub(self.page),
+            KeyCode::PageDown | KeyCode::Char(' ') => self.scroll += self.page,
+            KeyCode::Char('u') if ctrl => self.scroll = self.scroll.saturating_sub(self.page / 2),
+            KeyCode::Char('d') if ctrl => self.scroll += self.page / 2,
+            KeyCode::Home | KeyCode::Char('g') => self.scroll = 0,
+            KeyCode::End | KeyCode::Char('G') => self.scroll = usize::MAX,
+            _ => {}
+        }
+        ViewerOutcome::Handled
+    }
+
+    pub fn render(&mut self, f: &mut Frame, area: Rect) {
+        let theme = theme::current();
+        let keys = if self.approval {
+            "y approve, n reject, s side-by-side, ↑↓ PgUp PgDn scroll"
+        } else {
+            "s side-by-side, ↑↓ PgUp PgDn scroll, Esc close"
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!("{} ({})", self.title, keys))
+            .border_style(if self.approval { theme.attention } else { theme.accent });
+        let inner = block.inner(area);
+
+        let layout = if self.side_by_side {
+            DiffLayout::SideBySide(inner.width as usize)
+        } else {
+            DiffLayout::Unified
+        };
+        if self.rendered.as_ref().is_none_or(|(rendered_for, _)| *rendered_for != layout) {
+            let mut lines = Vec::new();
+            for change in &self.changes {
+                if !lines.is_empty() {
+                    lines.push(Line::default());
+                }
+                lines.extend(change.render(layout));
+            }
+            if lines.is_empty() {
+                lines.push(Line::styled("No files changed", theme.muted));
+            }
+            self.rendered = Some((layout, lines));
+        }
+        let lines = self.rendered.as_ref().map(|(_, lines)| lines.clone()).unwrap_or_default();
+
+        let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false });
+        let rows = paragraph.line_count(inner.width);
+        self.page = (inner.height as usize).max(1);
+        self.scroll = self.scroll.min(rows.saturating_sub(self.page));
+
+        f.render_widget(Clear, area);
+        f.render_widget(
+            paragraph.block(block).scroll((self.scroll.min(u16::MAX as usize) as u16, 0)),
+            area,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ops(old: &[&str], new: &[&str]) -> Vec<Op> {
+        diff_lines(old, new).iter().map(|line| line.op).collect()
+    }
+
+    /// Hunk headers as `diff -U<context>` prints them, always with lengths
+    fn headers(old: &str, new: &str, context: usize) -> Vec<String> {
+        hunks(&diff_lines(&lines(old), &lines(new)), context)
+            .iter()
+            .map(|h| format!("-{},{} +{},{}", h.old_start, h.old_len, h.new_start, h.new_len))
+            .collect()
+    }
+
+    fn numbers(from: usize, to: usize) -> String {
+        (from..=to).map(|n| format!("{}\n", n)).collect()
+    }
+
+    #[test]
+    fn whole_files_appear_and_disappear() {
+        use Op::*;
+        assert_eq!(ops(&[], &["a", "b"]), [Insert, Insert]);
+        assert_eq!(ops(&["a", "b"], &[]), [Delete, Delete]);
+        assert_eq!(ops(&[], &[]), []);
+
+        // An empty side points at the line before it: line 0
+        assert_eq!(headers("", "a\nb\n", 3), ["-0,0 +1,2"]);
+        assert_eq!(headers("a\nb\n", "", 3), ["-1,2 +0,0"]);
+    }
+
+    #[test]
+    fn inserts_deletes_and_replacements_keep_the_rest_equal() {
+        use Op::*;
+        assert_eq!(ops(&["a", "c"], &["a", "b", "c"]), [Equal, Insert, Equal]);
+        assert_eq!(ops(&["a", "b", "c"], &["a", "c"]), [Equal, Delete, Equal]);
+        assert_eq!(ops(&["a", "b", "c"], &["a", "x", "c"]), [Equal, Delete, Insert, Equal]);
+        assert_eq!(ops(&["a", "b", "c"], &["a", "b", "z"]), [Equal, Equal, Delete, Insert]);
+        assert_eq!(ops(&["b", "c"], &["a", "b", "c"]), [Insert, Equal, Equal]);
+
+        let diff = diff_lines(&["a", "b", "c"], &["a", "x", "c"]);
+        assert_eq!((diff[1].old, diff[1].new), (Some(1), None));
+        assert_eq!((diff[2].old, diff[2].new), (None, Some(1)));
+        assert_eq!((diff[3].old, diff[3].new), (Some(2), Some(2)));
+    }
+
+    #[test]
+    fn hunk_headers_match_diff_u() {
+        let old = numbers(1, 10);
+        let inserted = format!("{}x\n{}", numbers(1, 5), numbers(6, 10));
+        let deleted = format!("{}{}", numbers(1, 4), numbers(6, 10));
+        let last_changed = format!("{}ten\n", numbers(1, 9));
+
+        assert_eq!(headers(&old, &inserted, 3), ["-3,6 +3,7"]);
+        assert_eq!(headers(&old, &deleted, 3), ["-2,7 +2,6"]);
+        assert_eq!(headers(&old, &last_changed, 3), ["-7,4 +7,4"]);
+        // Without context the empty side of a hunk is the line before it
+        assert_eq!(headers(&old, &inserted, 0), ["-5,0 +6,1"]);
+        assert_eq!(headers(&old, &deleted, 0), ["-5,1 +4,0"]);
+
+        // Changes further apart than twice the context get hunks of their own
+        let old = numbers(1, 20);
+        let new = format!("0\n{}{}", numbers(1, 9), numbers(11, 20));
+        assert_eq!(headers(&old, &new, 3), ["-1,3 +1,4", "-7,7 +8,6"]);
+    }
+
+    #[test]
+    fn too_many_edits_replace_the_middle_wholesale() {
+        use Op::*;
+        let old = ["same", "a", "b", "c", "end"];
+        let new = ["same", "x", "b", "y", "end"];
+        assert_eq!(diff_ops(&old, &new, 10), [Equal, Delete, Insert, Equal, Delete, Insert, Equal]);
+        assert!(myers(&old[1..4], &new[1..4], 3).is_none());
+        assert_eq!(diff_ops(&old, &new, 3), [Equal, Delete, Delete, Delete, Insert, Insert, Insert, Equal]);
+    }
+
+    #[test]
+    fn words_are_byte_ranges_on_character_boundaries() {
+        let line = "let naïve = 価格;";
+        let words: Vec<&str> = words(line).iter().map(|&(s, e)| &line[s..e]).collect();
+        assert_eq!(words, ["let", " ", "naïve", " ", "=", " ", "価格", ";"]);
+
+        assert_eq!(
+            changed_words("let naïve = 価格 + 1;", "let naïve = 価格 + 2;"),
+            Some((vec![(22, 23)], vec![(22, 23)]))
+        );
+        // Little left in common: the line's own color says enough
+        assert_eq!(changed_words("naïve", "größe"), None);
+    }
+}
@@ -0,0 +1,116 @@
+use crate::theme;
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::Span,
+};
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::Path;
+use std::sync::{Arc, LazyLock, Mutex};
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{FontStyle, Theme, ThemeSet};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::util::LinesWithEndings;
+
+/// Highlighted blocks kept around; the cache is simply dropped when full
+const MAX_CACHED_BLOCKS: usize = 256;
+
+/// One styled span list per source line
+pub type HighlightedLines = Arc<Vec<Vec<Span<'static>>>>;
+
+static SYNTAXES: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+
+static THEMES: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
+
+/// The UI theme's choice; `None` turns highlighting off
+static THEME: LazyLock<Option<&'static Theme>> = LazyLock::new(|| {
+    let name = theme::current().syntax.as_deref()?;
+    THEMES.themes.get(name)
+});
+
+/// Keyed by a hash of the language and code. The chat is redrawn on every
+/// streamed chunk, and with this only the code block still being written
+/// is highlighted again; finished blocks come straight from the cache.
+static CACHE: LazyLock<Mutex<HashMap<u64, HighlightedLines>>> = LazyLock::new(Default::default);
+
+/// Highlight a fenced code block by its info string (`rust`, `py`, ...).
+/// Returns `None` when the language is unknown.
+pub fn highlight_code(code: &str, lang: &str) -> Option<HighlightedLines> {
+    if lang.is_empty() {
+        return None;
+    }
+    let theme = (*THEME)?;
+    let syntax = SYNTAXES.find_syntax_by_token(lang)?;
+    Some(cached(code, syntax, theme))
+}
+
+/// Highlight file contents using the syntax for the file's extension
+pub fn highlight_file(code: &str, path: &str) -> Option<HighlightedLines> {
+    let theme = (*THEME)?;
+    let path = Path::new(path);
+    let syntax = path
+        .extension()
+        .and_then(|ext| SYNTAXES.find_syntax_by_extension(&ext.to_string_lossy()))
+        .or_else(|| {
+            // Extensionless files such as Makefile or Dockerfile
+            let name = path.file_name()?.to_string_lossy();
+            SYNTAXES.find_syntax_by_extension(&name)
+        })?;
+    Some(cached(code, syntax, theme))
+}
+
+/// Whether `name` is one of the bundled syntect themes
+pub fn has_theme(name: &str) -> bool {
+    THEMES.themes.contains_key(name)
+}
+
+fn cached(code: &str, syntax: &SyntaxReference, theme: &Theme) -> HighlightedLines {
+    let mut hasher = DefaultHasher::new();
+    syntax.name.hash(&mut hasher);
+    code.hash(&mut hasher);
+    let key = hasher.finish();
+
+    if let Some(lines) = CACHE.lock().unwrap().get(&key) {
+        return lines.clone();
+    }
+
+    let lines = Arc::new(highlight(code, syntax, theme));
+    let mut cache = CACHE.lock().unwrap();
+    if cache.len() >= MAX_CACHED_BLOCKS {
+        cache.clear();
+    }
+    cache.insert(key, lines.clone());
+    lines
+}
+
+fn highlight(code: &str, syntax: &SyntaxReference, theme: &Theme) -> Vec<Vec<Span<'static>>> {
+    let mut highlighter = HighlightLines::new(syntax, theme);
+    LinesWithEndings::from(code)
+        .map(|line| match highlighter.highlight_line(line, &SYNTAXES) {
+            Ok(regions) => regions
+                .into_iter()
+                .map(|(style, text)| {
+                    Span::styled(text.trim_end_matches(['\n', '\r']).to_string(), convert(style))
+                })
+                .filter(|span| !span.content.is_empty())
+                .collect(),
+            Err(_) => vec![Span::raw(line.trim_end_matches(['\n', '\r']).to_string())],
+        })
+        .collect()
+}
+
+/// Foreground and font style only; the terminal's own background is kept
+fn convert(style: syntect::highlighting::Style) -> Style {
+    let fg = style.foreground;
+    let mut converted = Style::default().fg(Color::Rgb(fg.r, fg.g, fg.b));
+    if style.font_style.contains(FontStyle::BOLD) {
+        converted = converted.add_modifier(Modifier::BOLD);
+    }
+    if style.font_style.contains(FontStyle::ITALIC) {
+        converted = converted.add_modifier(Modifier::ITALIC);
+    }
+    if style.font_style.contains(FontStyle::UNDERLINE) {
+        converted = converted.add_modifier(Modifier::UNDERLINED);
+    }
+    converted
+}
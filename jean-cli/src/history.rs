@@ -0,0 +1,251 @@
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use tracing::{debug, error};
+
+/// Only the most recent prompts are kept in memory
+const MAX_HISTORY: usize = 1000;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct HistoryEntry {
+    timestamp: DateTime<Local>,
+    prompt: String,
+}
+
+/// Previously submitted prompts, persisted per project as JSON lines next to
+/// the conversation logs so they carry over between sessions.
+pub struct PromptHistory {
+    path: PathBuf,
+    entries: Vec<String>,
+    /// Index into `entries` while recalling with Up/Down
+    position: Option<usize>,
+    /// The unsent input that was there before recall started
+    draft: String,
+}
+
+/// State of an active Ctrl-R reverse incremental search
+pub struct HistorySearch {
+    pub query: String,
+    /// Index of the entry currently matching `query`
+    pub matched: Option<usize>,
+    /// Input to restore if the search is cancelled
+    pub original: String,
+}
+
+impl PromptHistory {
+    pub fn load(path: PathBuf) -> Self {
+        let mut entries: Vec<String> = match fs::read_to_string(&path) {
+            Ok(content) => content
+                .lines()
+                .filter_map(|line| serde_json::from_str::<HistoryEntry>(line).ok())
+                .map(|entry| entry.prompt)
+                .collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => {
+                error!("Failed to read prompt history {:?}: {}", path, e);
+                Vec::new()
+            }
+        };
+
+        if entries.len() > MAX_HISTORY {
+            entries.drain(..entries.len() - MAX_HISTORY);
+        }
+        debug!("Loaded {} history entries from {:?}", entries.len(), path);
+
+        Self {
+            path,
+            entries,
+            position: None,
+            draft: String::new(),
+        }
+    }
+
+    /// Remember a submitted prompt. Repeating the previous prompt doesn't
+    /// add a duplicate entry.
+    pub fn record(&mut self, prompt: &str) {
+        self.reset();
+        if prompt.trim().is_empty() || self.entries.last().is_some_and(|last| last == prompt) {
+            return;
+        }
+
+        self.entries.push(prompt.to_string());
+        if self.entries.len() > MAX_HISTORY {
+            self.entries.remove(0);
+        }
+
+        if let Err(e) = self.append(prompt) {
+            error!("Failed to save prompt history: {}", e);
+        }
+    }
+
+    /// Step back to an older prompt. `current` is saved as the draft when
+    /// recall starts so stepping forward past the newest entry restores it.
+    pub fn older(&mut self, current: &str) -> Option<&str> {
+        let index = match self.position {
+            None => {
+                self.draft = current.to_string();
+                self.entries.len().checked_sub(1)?
+            }
+            Some(0) => return None,
+            Some(i) => i - 1,
+        };
+        self.position = Some(index);
+        self.entries.get(index).map(String::as_str)
+    }
+
+    /// Step forward to a newer prompt, ending with the saved draft. Returns
+    /// `None` when not recalling.
+    pub fn newer(&mut self) -> Option<&str> {
+        let index = self.position? + 1;
+        if index < self.entries.len() {
+            self.position = Some(index);
+            self.entries.get(index).map(String::as_str)
+        } else {
+            self.position = None;
+            Some(&self.draft)
+        }
+    }
+
+    /// Stop recalling; the next Up starts again from the newest entry
+    pub fn reset(&mut self) {
+        self.position = None;
+    }
+
+    /// Newest entry older than `before` containing `query` (case-insensitive)
+    pub fn search(&self, query: &str, before: usize) -> Option<usize> {
+        let query = query.to_lowercase();
+        self.entries[..before.min(self.entries.len())]
+            .iter()
+            .rposition(|entry| entry.to_lowercase().contains(&query))
+    }
+
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.entries.get(index).map(String::as_str)
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn append(&self, prompt: &str) -> Result<()> {
+        let entry = HistoryEntry {
+            timestamp: Local::now(),
+            prompt: prompt.to_string(),
+        };
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+        Ok(())
+    }
+}
+
+impl HistorySearch {
+    pub fn new(original: String) -> Self {
+        Self {
+            query: String::new(),
+            matched: None,
+            original,
+        }
+    }
+
+    /// Re-run the search from the newest entry after the query changed
+    pub fn update(&mut self, history: &PromptHistory) {
+        self.matched = if self.query.is_empty() {
+            None
+        } else {
+            history.search(&self.query, history.len())
+        };
+    }
+
+    /// Move to the next older match, staying put if there is none
+    pub fn older(&mut self, history: &PromptHistory) {
+        if self.query.is_empty() {
+            return;
+        }
+        let before = self.matched.unwrap_or(history.len());
+        if let Some(index) = history.search(&self.query, before) {
+            self.matched = Some(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(prompts: &[&str]) -> (tempfile::TempDir, PromptHistory) {
+        let dir = tempfile::tempdir().unwrap();
+        let mut history = PromptHistory::load(dir.path().join("history.jsonl"));
+        for prompt in prompts {
+            history.record(prompt);
+        }
+        (dir, history)
+    }
+
+    #[test]
+    fn prompts_are_kept_between_sessions() {
+        let (dir, _) = history(&["fix the build", "now add a test\nfor it"]);
+        let path = dir.path().join("history.jsonl");
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        writeln!(file, "not json").unwrap();
+
+        // Lines that don't parse are skipped
+        let reloaded = PromptHistory::load(path);
+        assert_eq!(reloaded.entries, ["fix the build", "now add a test\nfor it"]);
+        assert!(PromptHistory::load(dir.path().join("missing.jsonl")).entries.is_empty());
+    }
+
+    #[test]
+    fn repeated_and_blank_prompts_are_not_recorded() {
+        let (_dir, history) = history(&["ls", "ls", "  \n", "pwd", "ls"]);
+        assert_eq!(history.entries, ["ls", "pwd", "ls"]);
+    }
+
+    #[test]
+    fn stepping_past_the_newest_entry_restores_the_draft() {
+        let (_dir, mut history) = history(&["first", "second"]);
+        assert_eq!(history.newer(), None);
+
+        assert_eq!(history.older("half typed"), Some("second"));
+        assert_eq!(history.older("second"), Some("first"));
+        assert_eq!(history.older("first"), None);
+        assert_eq!(history.newer(), Some("second"));
+        assert_eq!(history.newer(), Some("half typed"));
+        assert_eq!(history.newer(), None);
+
+        // Recording ends recall, so Up starts again from the newest entry
+        history.older("");
+        history.older("");
+        history.record("third");
+        assert_eq!(history.older(""), Some("third"));
+    }
+
+    #[test]
+    fn reverse_search_walks_back_through_older_matches() {
+        let (_dir, history) = history(&["cargo build", "git status", "Cargo test", "cargo clippy"]);
+        let mut search = HistorySearch::new("draft".to_string());
+        search.update(&history);
+        assert_eq!(search.matched, None);
+
+        search.query.push_str("CARGO");
+        search.update(&history);
+        assert_eq!(search.matched, Some(3));
+        search.older(&history);
+        assert_eq!(search.matched, Some(2));
+        search.older(&history);
+        assert_eq!(search.matched, Some(0));
+        // No older match: stay on the oldest one
+        search.older(&history);
+        assert_eq!(search.matched, Some(0));
+
+        search.query.push_str(" t");
+        search.update(&history);
+        assert_eq!(search.matched.and_then(|i| history.get(i)), Some("Cargo test"));
+        assert_eq!(search.original, "draft");
+    }
+}
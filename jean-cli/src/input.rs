@@ -0,0 +1,548 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Tabs are expanded on paste since their rendered width depends on the terminal
+const TAB_WIDTH: usize = 4;
+
+/// Multi-line prompt editor backing the input box.
+///
+/// The text is a single `String` with `\n` separating lines; `cursor` is a
+/// byte offset that is always kept on a grapheme cluster boundary, so an
+/// accented letter, CJK character or emoji sequence is moved over and
+/// deleted as a single unit. Columns are measured in terminal cells.
+#[derive(Debug, Default)]
+pub struct InputEditor {
+    text: String,
+    cursor: usize,
+    /// Cells per row the input box was last drawn with, which Up and Down
+    /// move between; `None` until the first draw
+    wrap_width: Option<usize>,
+}
+
+/// Input text wrapped to a given width, ready to render
+pub struct InputLayout {
+    pub rows: Vec<String>,
+    /// Byte offset in the text at which each row starts
+    pub offsets: Vec<usize>,
+    /// Row and cell column of the cursor within `rows`
+    pub cursor: (usize, usize),
+}
+
+impl InputLayout {
+    /// Offset of the grapheme at cell `column` on `row`, clamped to the end
+    /// of the row. Landing in the middle of a wide grapheme snaps to its
+    /// start, and a row that wraps stops before its last grapheme, since
+    /// the offset after it is drawn at the start of the next row.
+    fn offset_at(&self, row: usize, column: usize) -> usize {
+        let start = self.offsets[row];
+        let text = &self.rows[row];
+        let mut width = 0;
+        let mut last = 0;
+        for (i, grapheme) in text.grapheme_indices(true) {
+            width += grapheme.width();
+            if width > column {
+                return start + i;
+            }
+            last = i;
+        }
+        let wraps = self.offsets.get(row + 1).is_some_and(|&next| next == start + text.len());
+        if wraps { start + last } else { start + text.len() }
+    }
+}
+
+impl InputEditor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replace the contents, e.g. with a recalled prompt, cursor at the end
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.to_string();
+        self.cursor = self.text.len();
+    }
+
+    /// Byte offset of the cursor in `text()`
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Replace `start..end` with `with`, leaving the cursor after it
+    pub fn replace_range(&mut self, start: usize, end: usize, with: &str) {
+        self.text.replace_range(start..end, with);
+        self.cursor = start + with.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Clear the editor, returning what was typed
+    pub fn take(&mut self) -> String {
+        self.cursor = 0;
+        std::mem::take(&mut self.text)
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    /// Insert pasted text verbatim, normalizing line endings and tabs
+    pub fn insert_str(&mut self, s: &str) {
+        let s = s
+            .replace("\r\n", "\n")
+            .replace('\r', "\n")
+            .replace('\t', &" ".repeat(TAB_WIDTH));
+        self.text.insert_str(self.cursor, &s);
+        self.cursor += s.len();
+    }
+
+    pub fn newline(&mut self) {
+        self.insert_char('\n');
+    }
+
+    pub fn delete_before(&mut self) {
+        if let Some(start) = self.prev_boundary(self.cursor) {
+            self.text.replace_range(start..self.cursor, "");
+            self.cursor = start;
+        }
+    }
+
+    pub fn delete_after(&mut self) {
+        if let Some(end) = self.next_boundary(self.cursor) {
+            self.text.replace_range(self.cursor..end, "");
+        }
+    }
+
+    pub fn delete_word_before(&mut self) {
+        let start = self.word_start_before(self.cursor);
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    pub fn delete_word_after(&mut self) {
+        let end = self.word_end_after(self.cursor);
+        self.text.replace_range(self.cursor..end, "");
+    }
+
+    /// Delete up to the start of the next word, as vim's `dw`, but never
+    /// past the end of the line
+    pub fn delete_to_next_word_start(&mut self) {
+        let end = self.next_word_start(self.cursor).min(self.line_end(self.cursor));
+        self.text.replace_range(self.cursor..end, "");
+    }
+
+    /// Delete from the start of the current line to the cursor
+    pub fn delete_to_line_start(&mut self) {
+        let start = self.line_start(self.cursor);
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    /// Delete from the cursor to the end of the current line
+    pub fn delete_to_line_end(&mut self) {
+        let end = self.line_end(self.cursor);
+        self.text.replace_range(self.cursor..end, "");
+    }
+
+    /// Delete the current line, including its line break
+    pub fn delete_line(&mut self) {
+        let start = self.line_start(self.cursor);
+        let end = self.line_end(self.cursor);
+        let (start, end) = if end < self.text.len() {
+            (start, end + 1)
+        } else {
+            (start.saturating_sub(1), end)
+        };
+        self.text.replace_range(start..end, "");
+        self.cursor = self.line_start(start.min(self.text.len()));
+    }
+
+    pub fn move_left(&mut self) {
+        if let Some(prev) = self.prev_boundary(self.cursor) {
+            self.cursor = prev;
+        }
+    }
+
+    pub fn move_right(&mut self) {
+        if let Some(next) = self.next_boundary(self.cursor) {
+            self.cursor = next;
+        }
+    }
+
+    /// Move left without leaving the current line
+    pub fn move_left_in_line(&mut self) {
+        if self.cursor > self.line_start(self.cursor) {
+            self.move_left();
+        }
+    }
+
+    /// Move right without leaving the current line
+    pub fn move_right_in_line(&mut self) {
+        if self.cursor < self.line_end(self.cursor) {
+            self.move_right();
+        }
+    }
+
+    pub fn move_word_left(&mut self) {
+        self.cursor = self.word_start_before(self.cursor);
+    }
+
+    pub fn move_word_right(&mut self) {
+        self.cursor = self.word_end_after(self.cursor);
+    }
+
+    /// Move to the start of the next word, as vim's `w`
+    pub fn move_next_word_start(&mut self) {
+        self.cursor = self.next_word_start(self.cursor);
+    }
+
+    /// Move onto the last grapheme of the word, or of the next one when
+    /// already there, as vim's `e`
+    pub fn move_word_end(&mut self) {
+        let Some(next) = self.next_boundary(self.cursor) else {
+            return;
+        };
+        let end = self.word_end_after(next);
+        if let Some(last) = self.prev_boundary(end) {
+            self.cursor = last.max(self.cursor);
+        }
+    }
+
+    pub fn move_line_start(&mut self) {
+        self.cursor = self.line_start(self.cursor);
+    }
+
+    pub fn move_line_end(&mut self) {
+        self.cursor = self.line_end(self.cursor);
+    }
+
+    /// Move to the same display column on the row above, as wrapped when
+    /// the input box was last drawn. Returns false when the cursor is
+    /// already on the first row.
+    pub fn move_up(&mut self) -> bool {
+        let layout = self.layout(self.wrap_width.unwrap_or(usize::MAX));
+        let (row, column) = layout.cursor;
+        if row == 0 {
+            return false;
+        }
+        self.cursor = layout.offset_at(row - 1, column);
+        true
+    }
+
+    /// Move to the same display column on the row below. Returns false when
+    /// the cursor is already on the last row.
+    pub fn move_down(&mut self) -> bool {
+        let layout = self.layout(self.wrap_width.unwrap_or(usize::MAX));
+        let (row, column) = layout.cursor;
+        if row + 1 >= layout.rows.len() {
+            return false;
+        }
+        self.cursor = layout.offset_at(row + 1, column);
+        true
+    }
+
+    /// Set the row width Up and Down move by; called when the input box is
+    /// drawn
+    pub fn set_wrap_width(&mut self, width: usize) {
+        self.wrap_width = Some(width);
+    }
+
+    /// Hard-wrap the text to `width` cells and locate the cursor. Wrapping
+    /// is done here rather than by `Paragraph` so the cursor position is
+    /// always consistent with what is drawn. A wide grapheme that doesn't
+    /// fit at the end of a row moves to the next one.
+    pub fn layout(&self, width: usize) -> InputLayout {
+        let width = width.max(2);
+        let mut rows = Vec::new();
+        let mut offsets = Vec::new();
+        let mut cursor = (0, 0);
+        let mut offset = 0;
+
+        for line in self.text.split('\n') {
+            let mut row = String::new();
+            let mut column = 0;
+            offsets.push(offset);
+            for (i, grapheme) in line.grapheme_indices(true) {
+                let grapheme_width = grapheme.width();
+                if column + grapheme_width > width {
+                    rows.push(std::mem::take(&mut row));
+                    offsets.push(offset + i);
+                    column = 0;
+                }
+                if offset + i == self.cursor {
+                    cursor = (rows.len(), column);
+                }
+                row.push_str(grapheme);
+                column += grapheme_width;
+            }
+            if offset + line.len() == self.cursor {
+                if column >= width {
+                    // Cursor after a full row gets a continuation row of its own
+                    rows.push(std::mem::take(&mut row));
+                    offsets.push(self.cursor);
+                    column = 0;
+                }
+                cursor = (rows.len(), column);
+            }
+            rows.push(row);
+            offset += line.len() + 1;
+        }
+
+        InputLayout {
+            rows,
+            offsets,
+            cursor,
+        }
+    }
+
+    fn prev_boundary(&self, offset: usize) -> Option<usize> {
+        self.text[..offset]
+            .grapheme_indices(true)
+            .next_back()
+            .map(|(i, _)| i)
+    }
+
+    fn next_boundary(&self, offset: usize) -> Option<usize> {
+        self.text[offset..]
+            .graphemes(true)
+            .next()
+            .map(|g| offset + g.len())
+    }
+
+    fn line_start(&self, offset: usize) -> usize {
+        self.text[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0)
+    }
+
+    fn line_end(&self, offset: usize) -> usize {
+        self.text[offset..]
+            .find('\n')
+            .map(|i| offset + i)
+            .unwrap_or(self.text.len())
+    }
+
+    /// Skip whitespace backwards, then the word before it
+    fn word_start_before(&self, offset: usize) -> usize {
+        let mut graphemes = self.text[..offset].grapheme_indices(true).rev().peekable();
+        let mut start = offset;
+
+        while let Some((i, _)) = graphemes.next_if(|(_, g)| is_whitespace(g)) {
+            start = i;
+        }
+        match graphemes.next() {
+            Some((i, g)) if is_word(g) => {
+                start = i;
+                while let Some((i, _)) = graphemes.next_if(|(_, g)| is_word(g)) {
+                    start = i;
+                }
+            }
+            // Not preceded by a word: step over a single punctuation grapheme
+            Some((i, _)) => start = i,
+            None => {}
+        }
+        start
+    }
+
+    /// Skip the rest of the word or punctuation run at `offset`, then the
+    /// whitespace after it
+    fn next_word_start(&self, offset: usize) -> usize {
+        let mut graphemes = self.text[offset..].grapheme_indices(true).peekable();
+        if let Some(&(_, first)) = graphemes.peek()
+            && !is_whitespace(first)
+        {
+            let word = is_word(first);
+            while graphemes.next_if(|(_, g)| !is_whitespace(g) && is_word(g) == word).is_some() {}
+        }
+        while graphemes.next_if(|(_, g)| is_whitespace(g)).is_some() {}
+        graphemes.peek().map_or(self.text.len(), |(i, _)| offset + i)
+    }
+
+    /// Skip whitespace forwards, then the word after it
+    fn word_end_after(&self, offset: usize) -> usize {
+        let mut graphemes = self.text[offset..].grapheme_indices(true).peekable();
+        let mut end = offset;
+
+        while let Some((i, g)) = graphemes.next_if(|(_, g)| is_whitespace(g)) {
+            end = offset + i + g.len();
+        }
+        match graphemes.next() {
+            Some((i, g)) if is_word(g) => {
+                end = offset + i + g.len();
+                while let Some((i, g)) = graphemes.next_if(|(_, g)| is_word(g)) {
+                    end = offset + i + g.len();
+                }
+            }
+            Some((i, g)) => end = offset + i + g.len(),
+            None => {}
+        }
+        end
+    }
+}
+
+fn is_word(grapheme: &str) -> bool {
+    grapheme
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn is_whitespace(grapheme: &str) -> bool {
+    grapheme.chars().all(char::is_whitespace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor(text: &str) -> InputEditor {
+        let mut editor = InputEditor::new();
+        editor.insert_str(text);
+        editor
+    }
+
+    #[test]
+    fn typing_non_ascii_characters_keeps_cursor_on_boundaries() {
+        let mut editor = InputEditor::new();
+        for c in "héllo ü 日本 🦀".chars() {
+            editor.insert_char(c);
+        }
+        assert_eq!(editor.text, "héllo ü 日本 🦀");
+        assert_eq!(editor.cursor, editor.text.len());
+
+        editor.move_left();
+        editor.move_left();
+        editor.insert_char('語');
+        assert_eq!(editor.text, "héllo ü 日本語 🦀");
+    }
+
+    #[test]
+    fn backspace_removes_whole_grapheme_clusters() {
+        // "e" + combining acute, and a ZWJ family emoji
+        let mut editor = editor("cafe\u{301} 👨\u{200d}👩\u{200d}👧");
+        editor.delete_before();
+        assert_eq!(editor.text, "cafe\u{301} ");
+        editor.delete_before();
+        editor.delete_before();
+        assert_eq!(editor.text, "caf");
+    }
+
+    #[test]
+    fn cursor_moves_over_graphemes_not_chars() {
+        let mut editor = editor("a\u{301}b");
+        editor.move_line_start();
+        editor.move_right();
+        assert_eq!(editor.cursor, "a\u{301}".len());
+        editor.delete_after();
+        assert_eq!(editor.text, "a\u{301}");
+        editor.move_left();
+        assert_eq!(editor.cursor, 0);
+    }
+
+    #[test]
+    fn layout_measures_cursor_in_display_cells() {
+        let mut editor = editor("日本語");
+        assert_eq!(editor.layout(20).cursor, (0, 6));
+
+        editor.move_left();
+        assert_eq!(editor.layout(20).cursor, (0, 4));
+    }
+
+    #[test]
+    fn layout_wraps_wide_characters_that_do_not_fit() {
+        let editor = editor("ab日本");
+        let layout = editor.layout(5);
+        assert_eq!(layout.rows, vec!["ab日", "本"]);
+        assert_eq!(layout.offsets, vec![0, "ab日".len()]);
+        assert_eq!(layout.cursor, (1, 2));
+    }
+
+    #[test]
+    fn cursor_at_end_of_full_row_gets_a_continuation_row() {
+        let editor = editor("abcd");
+        let layout = editor.layout(4);
+        assert_eq!(layout.rows, vec!["abcd", ""]);
+        assert_eq!(layout.cursor, (1, 0));
+    }
+
+    #[test]
+    fn vertical_motion_preserves_display_column() {
+        let mut editor = editor("日本語\nabcdef");
+        editor.move_left();
+        editor.move_left();
+        // Column 4 on the second line lands after "日本" on the first
+        assert!(editor.move_up());
+        assert_eq!(editor.cursor, "日本".len());
+        assert!(!editor.move_up());
+        assert!(editor.move_down());
+        assert_eq!(editor.cursor, "日本語\nabcd".len());
+        assert!(!editor.move_down());
+    }
+
+    #[test]
+    fn vertical_motion_follows_wrapped_rows() {
+        // "abcdef" wraps to "abcd" and "ef" at width 4
+        let mut wrapped = editor("abcdef\nxy");
+        wrapped.set_wrap_width(4);
+        assert!(wrapped.move_up());
+        assert_eq!(wrapped.cursor, "abcdef".len());
+        assert!(wrapped.move_up());
+        assert_eq!(wrapped.cursor, "ab".len());
+        assert!(!wrapped.move_up());
+        assert!(wrapped.move_down());
+        assert!(wrapped.move_down());
+        assert_eq!(wrapped.cursor, "abcdef\nxy".len());
+        assert!(!wrapped.move_down());
+
+        // A column past the end of a wrapped row stays on that row
+        let mut wide = editor("ab日本\nxy");
+        wide.set_wrap_width(3);
+        wide.move_up();
+        wide.move_up();
+        assert_eq!(wide.cursor, "ab".len());
+        assert_eq!(wide.layout(3).cursor, (1, 0));
+    }
+
+    #[test]
+    fn word_deletion_handles_non_ascii_words() {
+        let mut editor = editor("grüße naïve\u{301} ");
+        editor.delete_word_before();
+        assert_eq!(editor.text, "grüße ");
+        editor.move_line_start();
+        editor.delete_word_after();
+        assert_eq!(editor.text, " ");
+    }
+
+    #[test]
+    fn pasted_text_normalizes_line_endings_and_tabs() {
+        let editor = editor("a\r\n\tb\rc");
+        assert_eq!(editor.text, "a\n    b\nc");
+    }
+
+    #[test]
+    fn line_bound_moves_stop_at_the_line_edges() {
+        let mut editor = editor("ab\ncd");
+        editor.move_line_start();
+        editor.move_left_in_line();
+        assert_eq!(editor.cursor(), 3);
+        editor.move_up();
+        editor.move_line_end();
+        editor.move_right_in_line();
+        assert_eq!(editor.cursor(), 2);
+    }
+
+    #[test]
+    fn next_word_start_treats_punctuation_runs_as_words() {
+        let mut editor = editor("foo::bar  baz");
+        editor.move_line_start();
+        let mut starts = Vec::new();
+        for _ in 0..4 {
+            editor.move_next_word_start();
+            starts.push(editor.cursor());
+        }
+        assert_eq!(starts, vec![3, 5, 10, 13]);
+    }
+}
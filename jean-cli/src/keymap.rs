@@ -0,0 +1,326 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::HashMap;
+
+/// Something a key can be bound to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    Cancel,
+    Submit,
+    Newline,
+    Complete,
+    HistoryPrevious,
+    HistoryNext,
+    HistorySearch,
+    Search,
+    CopyMode,
+    ToggleMouse,
+    ToggleToolOutput,
+    SelectPreviousTool,
+    SelectNextTool,
+    ScrollUp,
+    ScrollDown,
+    PageUp,
+    PageDown,
+    PreviousMessage,
+    NextMessage,
+    ScrollToBottom,
+    CursorLeft,
+    CursorRight,
+    WordLeft,
+    WordRight,
+    LineStart,
+    LineEnd,
+    DeleteBefore,
+    DeleteAfter,
+    DeleteWordBefore,
+    DeleteWordAfter,
+    DeleteToLineStart,
+}
+
+/// Action names as used in the config file
+const ACTIONS: &[(&str, Action)] = &[
+    ("quit", Action::Quit),
+    ("cancel", Action::Cancel),
+    ("submit", Action::Submit),
+    ("newline", Action::Newline),
+    ("complete", Action::Complete),
+    ("history_previous", Action::HistoryPrevious),
+    ("history_next", Action::HistoryNext),
+    ("history_search", Action::HistorySearch),
+    ("search", Action::Search),
+    ("copy_mode", Action::CopyMode),
+    ("toggle_mouse", Action::ToggleMouse),
+    ("toggle_tool_output", Action::ToggleToolOutput),
+    ("select_previous_tool", Action::SelectPreviousTool),
+    ("select_next_tool", Action::SelectNextTool),
+    ("scroll_up", Action::ScrollUp),
+    ("scroll_down", Action::ScrollDown),
+    ("page_up", Action::PageUp),
+    ("page_down", Action::PageDown),
+    ("previous_message", Action::PreviousMessage),
+    ("next_message", Action::NextMessage),
+    ("scroll_to_bottom", Action::ScrollToBottom),
+    ("cursor_left", Action::CursorLeft),
+    ("cursor_right", Action::CursorRight),
+    ("word_left", Action::WordLeft),
+    ("word_right", Action::WordRight),
+    ("line_start", Action::LineStart),
+    ("line_end", Action::LineEnd),
+    ("delete_before", Action::DeleteBefore),
+    ("delete_after", Action::DeleteAfter),
+    ("delete_word_before", Action::DeleteWordBefore),
+    ("delete_word_after", Action::DeleteWordAfter),
+    ("delete_to_line_start", Action::DeleteToLineStart),
+];
+
+const DEFAULT_BINDINGS: &[(&str, Action)] = &[
+    ("ctrl-q", Action::Quit),
+    ("ctrl-c", Action::Cancel),
+    ("enter", Action::Submit),
+    ("shift-enter", Action::Newline),
+    ("alt-enter", Action::Newline),
+    ("ctrl-j", Action::Newline),
+    ("tab", Action::Complete),
+    ("up", Action::HistoryPrevious),
+    ("down", Action::HistoryNext),
+    ("ctrl-r", Action::HistorySearch),
+    ("ctrl-f", Action::Search),
+    ("ctrl-y", Action::CopyMode),
+    ("ctrl-t", Action::ToggleMouse),
+    ("ctrl-o", Action::ToggleToolOutput),
+    ("alt-up", Action::SelectPreviousTool),
+    ("alt-down", Action::SelectNextTool),
+    ("shift-up", Action::ScrollUp),
+    ("shift-down", Action::ScrollDown),
+    ("pageup", Action::PageUp),
+    ("pagedown", Action::PageDown),
+    ("ctrl-up", Action::PreviousMessage),
+    ("ctrl-down", Action::NextMessage),
+    ("ctrl-end", Action::ScrollToBottom),
+    ("left", Action::CursorLeft),
+    ("right", Action::CursorRight),
+    ("ctrl-left", Action::WordLeft),
+    ("alt-left", Action::WordLeft),
+    ("alt-b", Action::WordLeft),
+    ("ctrl-right", Action::WordRight),
+    ("alt-right", Action::WordRight),
+    ("alt-f", Action::WordRight),
+    ("home", Action::LineStart),
+    ("ctrl-a", Action::LineStart),
+    ("end", Action::LineEnd),
+    ("ctrl-e", Action::LineEnd),
+    ("backspace", Action::DeleteBefore),
+    ("delete", Action::DeleteAfter),
+    ("ctrl-backspace", Action::DeleteWordBefore),
+    ("alt-backspace", Action::DeleteWordBefore),
+    ("ctrl-w", Action::DeleteWordBefore),
+    ("ctrl-delete", Action::DeleteWordAfter),
+    ("alt-delete", Action::DeleteWordAfter),
+    ("alt-d", Action::DeleteWordAfter),
+    ("ctrl-u", Action::DeleteToLineStart),
+];
+
+type Key = (KeyCode, KeyModifiers);
+
+/// Maps keys to actions: the defaults, with the user's config on top
+pub struct Keymap {
+    bindings: HashMap<Key, Action>,
+}
+
+impl Keymap {
+    /// Apply `overrides` (key name to action name, or "none" to unbind) to
+    /// the default bindings. Entries that don't parse are skipped and
+    /// described in the returned warnings.
+    pub fn new(overrides: &HashMap<String, String>) -> (Self, Vec<String>) {
+        let mut bindings: HashMap<Key, Action> = DEFAULT_BINDINGS
+            .iter()
+            .map(|(key, action)| (parse_key(key).expect("default binding parses"), *action))
+            .collect();
+
+        let mut warnings = Vec::new();
+        let mut overrides: Vec<_> = overrides.iter().collect();
+        overrides.sort();
+        for (key_name, action_name) in overrides {
+            let key = match parse_key(key_name) {
+                Ok(key) => key,
+                Err(e) => {
+                    warnings.push(e);
+                    continue;
+                }
+            };
+            if action_name == "none" {
+                bindings.remove(&key);
+                continue;
+            }
+            match ACTIONS.iter().find(|(name, _)| name == action_name) {
+                Some((_, action)) => {
+                    bindings.insert(key, *action);
+                }
+                None => warnings.push(format!("unknown action '{}' for key '{}'", action_name, key_name)),
+            }
+        }
+        (Self { bindings }, warnings)
+    }
+
+    pub fn action(&self, key: KeyEvent) -> Option<Action> {
+        self.bindings.get(&normalize(key.code, key.modifiers)).copied()
+    }
+
+    /// Bindings grouped by action, for /help
+    pub fn help(&self) -> String {
+        let mut lines = vec!["Keys:".to_string()];
+        for (name, action) in ACTIONS {
+            let mut keys: Vec<String> = self
+                .bindings
+                .iter()
+                .filter(|(_, bound)| *bound == action)
+                .map(|(key, _)| key_name(*key))
+                .collect();
+            if keys.is_empty() {
+                continue;
+            }
+            keys.sort();
+            lines.push(format!("  {:<28} {}", name, keys.join(", ")));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Parse a key like "ctrl-f", "alt-enter", "shift-up", "f2" or "x"
+pub fn parse_key(name: &str) -> Result<Key, String> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = name;
+    // A trailing "-" is the minus key itself, as in "ctrl--"
+    while let Some((modifier, after)) = rest.split_once('-').filter(|(_, after)| !after.is_empty()) {
+        modifiers |= match modifier.to_ascii_lowercase().as_str() {
+            "ctrl" | "c" => KeyModifiers::CONTROL,
+            "alt" | "meta" | "m" => KeyModifiers::ALT,
+            "shift" | "s" => KeyModifiers::SHIFT,
+            _ => return Err(format!("unknown modifier '{}' in key '{}'", modifier, name)),
+        };
+        rest = after;
+    }
+
+    let code = match rest.to_ascii_lowercase().as_str() {
+        "enter" | "return" => KeyCode::Enter,
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "backspace" => KeyCode::Backspace,
+        "delete" | "del" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "esc" | "escape" => KeyCode::Esc,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "space" => KeyCode::Char(' '),
+        lower => {
+            let mut chars = rest.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => KeyCode::Char(c),
+                _ => match lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => return Err(format!("unknown key '{}'", name)),
+                },
+            }
+        }
+    };
+    Ok(normalize(code, modifiers))
+}
+
+/// Shift is part of the character for character keys ("N", not "shift-n"),
+/// and terminals disagree on whether they report it, so it's dropped
+fn normalize(code: KeyCode, modifiers: KeyModifiers) -> Key {
+    let modifiers = modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+    match code {
+        KeyCode::Char(c) if modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+            (KeyCode::Char(c.to_ascii_lowercase()), modifiers - KeyModifiers::SHIFT)
+        }
+        KeyCode::Char(c) => (KeyCode::Char(c), modifiers - KeyModifiers::SHIFT),
+        code => (code, modifiers),
+    }
+}
+
+fn key_name((code, modifiers): Key) -> String {
+    let mut name = String::new();
+    for (modifier, prefix) in [
+        (KeyModifiers::CONTROL, "ctrl-"),
+        (KeyModifiers::ALT, "alt-"),
+        (KeyModifiers::SHIFT, "shift-"),
+    ] {
+        if modifiers.contains(modifier) {
+            name.push_str(prefix);
+        }
+    }
+    match code {
+        KeyCode::Char(' ') => name.push_str("space"),
+        KeyCode::Char(c) => name.push(c),
+        KeyCode::F(n) => name.push_str(&format!("f{}", n)),
+        code => name.push_str(&format!("{:?}", code).to_lowercase()),
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overrides(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn parses_modifiers_and_named_keys() {
+        assert_eq!(parse_key("ctrl-f"), Ok((KeyCode::Char('f'), KeyModifiers::CONTROL)));
+        assert_eq!(parse_key("C-F"), Ok((KeyCode::Char('f'), KeyModifiers::CONTROL)));
+        assert_eq!(
+            parse_key("ctrl-alt-enter"),
+            Ok((KeyCode::Enter, KeyModifiers::CONTROL | KeyModifiers::ALT))
+        );
+        assert_eq!(parse_key("shift-up"), Ok((KeyCode::Up, KeyModifiers::SHIFT)));
+        assert_eq!(parse_key("f2"), Ok((KeyCode::F(2), KeyModifiers::NONE)));
+        assert_eq!(parse_key("space"), Ok((KeyCode::Char(' '), KeyModifiers::NONE)));
+        assert_eq!(parse_key("ctrl--"), Ok((KeyCode::Char('-'), KeyModifiers::CONTROL)));
+        // Shift is part of the character
+        assert_eq!(parse_key("shift-n"), Ok((KeyCode::Char('n'), KeyModifiers::NONE)));
+        assert_eq!(parse_key("N"), Ok((KeyCode::Char('N'), KeyModifiers::NONE)));
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_modifiers() {
+        assert!(parse_key("hyper-x").is_err());
+        assert!(parse_key("f13").is_err());
+        assert!(parse_key("ctrl-foo").is_err());
+    }
+
+    #[test]
+    fn overrides_rebind_unbind_and_warn() {
+        let (keymap, warnings) = Keymap::new(&overrides(&[
+            ("ctrl-k", "search"),
+            ("ctrl-t", "none"),
+            ("ctrl-x", "explode"),
+            ("hyper-y", "quit"),
+        ]));
+        assert_eq!(keymap.action(press(KeyCode::Char('k'), KeyModifiers::CONTROL)), Some(Action::Search));
+        assert_eq!(keymap.action(press(KeyCode::Char('t'), KeyModifiers::CONTROL)), None);
+        // Defaults that weren't overridden still apply
+        assert_eq!(keymap.action(press(KeyCode::Char('f'), KeyModifiers::CONTROL)), Some(Action::Search));
+        assert_eq!(warnings.len(), 2, "{:?}", warnings);
+    }
+
+    #[test]
+    fn ctrl_letters_match_whatever_case_the_terminal_reports() {
+        let (keymap, _) = Keymap::new(&HashMap::new());
+        let shifted = press(KeyCode::Char('R'), KeyModifiers::CONTROL | KeyModifiers::SHIFT);
+        assert_eq!(keymap.action(shifted), Some(Action::HistorySearch));
+    }
+}
@@ -0,0 +1,29 @@
+//! The jean terminal client as a library: app state and how it reacts to
+//! events (`app`), drawing it (`ui`), and the tools the model can call
+//! (`tools`). The `jean` binary only adds the terminal and event loop.
+
+pub mod app;
+pub mod chat_view;
+pub mod checkpoints;
+pub mod clipboard;
+pub mod client;
+pub mod commands;
+pub mod config;
+pub mod conversation_logger;
+pub mod diff;
+pub mod highlight;
+pub mod history;
+pub mod input;
+pub mod keymap;
+pub mod markdown;
+pub mod mentions;
+pub mod search;
+pub mod sessions;
+pub mod status;
+pub mod theme;
+pub mod tools;
+pub mod transcript;
+pub mod turn;
+pub mod ui;
+pub mod usage;
+pub mod vim;
@@ -0,0 +1,523 @@
+use crate::highlight;
+use crate::theme;
+use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use ratatui::{
+    style::{Modifier, Style},
+    text::{Line, Span},
+};
+use std::ops::Range;
+use unicode_width::UnicodeWidthStr;
+
+/// Horizontal rules have no natural width, so draw them at a fixed one
+const RULE_WIDTH: usize = 40;
+
+/// Render markdown into styled lines for the chat view. `base` is the style
+/// of plain text; emphasis, code and headings are layered on top of it.
+///
+/// The input may be a message that is still streaming in. Unclosed
+/// constructs render as if closed at the end of the text (an unterminated
+/// code fence is shown as a code block so far), so re-rendering the growing
+/// text on every chunk gives a stable picture.
+pub fn render(text: &str, base: Style) -> Vec<Line<'static>> {
+    render_parts(text, base).0
+}
+
+/// A code block as rendered by `render`
+pub struct RenderedCodeBlock {
+    pub code: String,
+    /// The lines it takes up, including the frame
+    pub lines: Range<usize>,
+}
+
+/// The code blocks in `text`, with where `render` puts them
+pub fn code_blocks(text: &str) -> Vec<RenderedCodeBlock> {
+    render_parts(text, Style::default()).1
+}
+
+fn render_parts(text: &str, base: Style) -> (Vec<Line<'static>>, Vec<RenderedCodeBlock>) {
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let mut renderer = Renderer::new(base);
+    for event in Parser::new_ext(text, options) {
+        renderer.event(event);
+    }
+    renderer.finish()
+}
+
+struct CodeBlock {
+    lang: String,
+    code: String,
+}
+
+#[derive(Default)]
+struct Table {
+    rows: Vec<Vec<Vec<Span<'static>>>>,
+    /// Number of leading rows that form the header
+    header_rows: usize,
+}
+
+struct Renderer {
+    base: Style,
+    lines: Vec<Line<'static>>,
+    /// Spans of the line being built
+    current: Vec<Span<'static>>,
+    styles: Vec<Style>,
+    /// `None` for bullet lists, the next number for ordered ones
+    lists: Vec<Option<u64>>,
+    /// Marker for the first line of the current list item, e.g. "  • "
+    item_marker: Option<String>,
+    quote_depth: usize,
+    /// A blank separator line is owed before the next line of content.
+    /// Emitting it lazily avoids trailing blanks at the end of quotes,
+    /// lists and the message itself.
+    blank_pending: bool,
+    code_block: Option<CodeBlock>,
+    rendered_code_blocks: Vec<RenderedCodeBlock>,
+    link_url: Option<String>,
+    table: Option<Table>,
+}
+
+impl Renderer {
+    fn new(base: Style) -> Self {
+        Self {
+            base,
+            lines: Vec::new(),
+            current: Vec::new(),
+            styles: vec![base],
+            lists: Vec::new(),
+            item_marker: None,
+            quote_depth: 0,
+            blank_pending: false,
+            code_block: None,
+            rendered_code_blocks: Vec::new(),
+            link_url: None,
+            table: None,
+        }
+    }
+
+    fn style(&self) -> Style {
+        *self.styles.last().unwrap_or(&self.base)
+    }
+
+    fn push_style(&mut self, f: impl FnOnce(Style) -> Style) {
+        self.styles.push(f(self.style()));
+    }
+
+    fn pop_style(&mut self) {
+        if self.styles.len() > 1 {
+            self.styles.pop();
+        }
+    }
+
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => {
+                if let Some(block) = &mut self.code_block {
+                    block.code.push_str(&text);
+                } else {
+                    self.push_text(&text, self.style());
+                }
+            }
+            Event::Code(code) => {
+                let style = self.style().patch(theme::current().inline_code);
+                self.push_span(Span::styled(code.into_string(), style));
+            }
+            Event::Html(html) | Event::InlineHtml(html) => {
+                self.push_text(&html, self.style().add_modifier(Modifier::DIM));
+            }
+            Event::InlineMath(math) | Event::DisplayMath(math) => {
+                self.push_span(Span::styled(math.into_string(), self.style().patch(theme::current().attention)));
+            }
+            Event::FootnoteReference(name) => {
+                self.push_span(Span::styled(format!("[^{}]", name), self.style()));
+            }
+            Event::SoftBreak | Event::HardBreak => self.flush_line(),
+            Event::Rule => {
+                self.flush_line();
+                let rule = "─".repeat(RULE_WIDTH);
+                self.push_span(Span::styled(rule, self.base.add_modifier(Modifier::DIM)));
+                self.flush_line();
+                self.blank_line();
+            }
+            Event::TaskListMarker(checked) => {
+                let marker = if checked { "[x] " } else { "[ ] " };
+                self.push_span(Span::styled(marker, self.style()));
+            }
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Heading { level, .. } => {
+                self.flush_line();
+                let theme = theme::current();
+                let heading = match level {
+                    HeadingLevel::H1 => theme.heading1,
+                    HeadingLevel::H2 => theme.heading2,
+                    _ => theme.heading3,
+                };
+                self.push_style(|s| {
+                    let s = s.patch(heading).add_modifier(Modifier::BOLD);
+                    if level == HeadingLevel::H1 {
+                        s.add_modifier(Modifier::UNDERLINED)
+                    } else {
+                        s
+                    }
+                });
+            }
+            Tag::BlockQuote(_) => {
+                self.flush_line();
+                // The separator before a quote sits outside it
+                self.flush_blank();
+                self.quote_depth += 1;
+                self.push_style(|s| s.add_modifier(Modifier::ITALIC));
+            }
+            Tag::CodeBlock(kind) => {
+                self.flush_line();
+                let lang = match kind {
+                    CodeBlockKind::Fenced(info) => {
+                        info.split_whitespace().next().unwrap_or_default().to_string()
+                    }
+                    CodeBlockKind::Indented => String::new(),
+                };
+                self.code_block = Some(CodeBlock {
+                    lang,
+                    code: String::new(),
+                });
+            }
+            Tag::List(start) => {
+                // A nested list starts mid-item in tight lists
+                self.flush_line();
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush_line();
+                let depth = self.lists.len().saturating_sub(1);
+                let bullet = match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}. ", *n - 1)
+                    }
+                    _ => "• ".to_string(),
+                };
+                self.item_marker = Some(format!("{}{}", "  ".repeat(depth), bullet));
+            }
+            Tag::Emphasis => self.push_style(|s| s.add_modifier(Modifier::ITALIC)),
+            Tag::Strong => self.push_style(|s| s.add_modifier(Modifier::BOLD)),
+            Tag::Strikethrough => self.push_style(|s| s.add_modifier(Modifier::CROSSED_OUT)),
+            Tag::Link { dest_url, .. } => {
+                self.link_url = Some(dest_url.into_string());
+                self.push_style(|s| s.patch(theme::current().link));
+            }
+            Tag::Image { dest_url, .. } => {
+                self.link_url = Some(dest_url.into_string());
+                self.push_style(|s| s.patch(theme::current().link));
+                self.push_span(Span::styled("[image: ", self.style()));
+            }
+            Tag::Table(_) => {
+                self.flush_line();
+                self.table = Some(Table::default());
+            }
+            Tag::TableHead | Tag::TableRow => {
+                if let Some(table) = &mut self.table {
+                    table.rows.push(Vec::new());
+                }
+            }
+            Tag::TableCell => {
+                if let Some(row) = self.table.as_mut().and_then(|t| t.rows.last_mut()) {
+                    row.push(Vec::new());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph => {
+                self.flush_line();
+                // Items of loose lists are separated by the list itself
+                if self.lists.is_empty() {
+                    self.blank_line();
+                }
+            }
+            TagEnd::Heading(_) => {
+                self.pop_style();
+                self.flush_line();
+                self.blank_line();
+            }
+            TagEnd::BlockQuote(_) => {
+                self.flush_line();
+                self.pop_style();
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+                self.blank_line();
+            }
+            TagEnd::CodeBlock => {
+                if let Some(block) = self.code_block.take() {
+                    self.push_code_block(block);
+                }
+            }
+            TagEnd::List(_) => {
+                self.flush_line();
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.blank_line();
+                }
+            }
+            TagEnd::Item => {
+                self.flush_line();
+                self.item_marker = None;
+            }
+            TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough => self.pop_style(),
+            TagEnd::Link => {
+                self.pop_style();
+                if let Some(url) = self.link_url.take() {
+                    // Autolinks already show the URL as their text
+                    let shown = self.current.last().is_some_and(|s| s.content == url);
+                    if !shown && !url.is_empty() {
+                        let style = self.style().patch(theme::current().muted);
+                        self.push_span(Span::styled(format!(" ({})", url), style));
+                    }
+                }
+            }
+            TagEnd::Image => {
+                self.push_span(Span::styled("]", self.style()));
+                self.pop_style();
+                if let Some(url) = self.link_url.take() {
+                    let style = self.style().patch(theme::current().muted);
+                    self.push_span(Span::styled(format!(" ({})", url), style));
+                }
+            }
+            TagEnd::TableHead => {
+                if let Some(table) = &mut self.table {
+                    table.header_rows = table.rows.len();
+                }
+            }
+            TagEnd::Table => {
+                if let Some(table) = self.table.take() {
+                    self.push_table(table);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn push_text(&mut self, text: &str, style: Style) {
+        for (i, part) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.flush_line();
+            }
+            if !part.is_empty() {
+                self.push_span(Span::styled(part.to_string(), style));
+            }
+        }
+    }
+
+    fn push_span(&mut self, span: Span<'static>) {
+        let cell = self
+            .table
+            .as_mut()
+            .and_then(|t| t.rows.last_mut())
+            .and_then(|row| row.last_mut());
+        match cell {
+            Some(cell) => cell.push(span),
+            None => self.current.push(span),
+        }
+    }
+
+    /// Finish the current line, adding the quote bar and list indentation
+    fn flush_line(&mut self) {
+        if self.current.is_empty() {
+            return;
+        }
+        let spans = std::mem::take(&mut self.current);
+        self.push_line(spans);
+    }
+
+    fn push_line(&mut self, spans: Vec<Span<'static>>) {
+        self.flush_blank();
+        let mut line = self.prefix();
+        line.extend(spans);
+        self.lines.push(Line::from(line));
+    }
+
+    /// Quote bars plus either the item marker (first line of an item) or
+    /// matching indentation (continuation lines)
+    fn prefix(&mut self) -> Vec<Span<'static>> {
+        let mut prefix = self.quote_bars();
+        if let Some(marker) = self.item_marker.take() {
+            let indent = " ".repeat(marker.width());
+            prefix.push(Span::styled(marker, self.base.patch(theme::current().muted)));
+            // Later lines of the same item line up with its text
+            self.item_marker = Some(indent);
+        }
+        prefix
+    }
+
+    fn quote_bars(&self) -> Vec<Span<'static>> {
+        if self.quote_depth == 0 {
+            return Vec::new();
+        }
+        let bars = "│ ".repeat(self.quote_depth);
+        vec![Span::styled(bars, self.base.patch(theme::current().muted))]
+    }
+
+    fn blank_line(&mut self) {
+        self.blank_pending = true;
+    }
+
+    fn flush_blank(&mut self) {
+        if std::mem::take(&mut self.blank_pending) && !self.lines.is_empty() {
+            let bars = self.quote_bars();
+            self.lines.push(Line::from(bars));
+        }
+    }
+
+    fn push_code_block(&mut self, block: CodeBlock) {
+        let frame = self.base.patch(theme::current().muted);
+        let code_style = theme::current().code;
+
+        let header = if block.lang.is_empty() {
+            "╭─".to_string()
+        } else {
+            format!("╭─ {}", block.lang)
+        };
+        self.push_line(vec![Span::styled(header, frame)]);
+        let first_line = self.lines.len() - 1;
+        let code = block.code.trim_end_matches('\n');
+        match highlight::highlight_code(code, &block.lang) {
+            Some(highlighted) => {
+                for spans in highlighted.iter() {
+                    let mut line = vec![Span::styled("│ ", frame)];
+                    line.extend(spans.iter().cloned());
+                    self.push_line(line);
+                }
+            }
+            None => {
+                for line in code.split('\n') {
+                    self.push_line(vec![
+                        Span::styled("│ ", frame),
+                        Span::styled(line.to_string(), code_style),
+                    ]);
+                }
+            }
+        }
+        self.push_line(vec![Span::styled("╰─", frame)]);
+        self.rendered_code_blocks.push(RenderedCodeBlock {
+            code: code.to_string(),
+            lines: first_line..self.lines.len(),
+        });
+        if self.lists.is_empty() {
+            self.blank_line();
+        }
+    }
+
+    fn push_table(&mut self, table: Table) {
+        let columns = table.rows.iter().map(Vec::len).max().unwrap_or(0);
+        let mut widths = vec![0; columns];
+        for row in &table.rows {
+            for (i, cell) in row.iter().enumerate() {
+                widths[i] = widths[i].max(cell_width(cell));
+            }
+        }
+
+        let border = self.base.patch(theme::current().muted);
+        for (r, row) in table.rows.into_iter().enumerate() {
+            let header = r < table.header_rows;
+            let mut spans = Vec::new();
+            for (i, width) in widths.iter().enumerate() {
+                if i > 0 {
+                    spans.push(Span::styled(" │ ", border));
+                }
+                let cell = row.get(i).cloned().unwrap_or_default();
+                let padding = width - cell_width(&cell);
+                spans.extend(cell.into_iter().map(|span| {
+                    if header {
+                        span.patch_style(Style::default().add_modifier(Modifier::BOLD))
+                    } else {
+                        span
+                    }
+                }));
+                spans.push(Span::raw(" ".repeat(padding)));
+            }
+            self.push_line(spans);
+
+            if header && r + 1 == table.header_rows {
+                let separator = widths
+                    .iter()
+                    .map(|w| "─".repeat(*w))
+                    .collect::<Vec<_>>()
+                    .join("─┼─");
+                self.push_line(vec![Span::styled(separator, border)]);
+            }
+        }
+        self.blank_line();
+    }
+
+    fn finish(mut self) -> (Vec<Line<'static>>, Vec<RenderedCodeBlock>) {
+        if let Some(block) = self.code_block.take() {
+            self.push_code_block(block);
+        }
+        self.flush_line();
+        (self.lines, self.rendered_code_blocks)
+    }
+}
+
+fn cell_width(cell: &[Span]) -> usize {
+    cell.iter().map(|span| span.content.width()).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(text: &str) -> Vec<String> {
+        render(text, Style::default()).iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn an_unterminated_fence_shows_the_code_so_far() {
+        assert_eq!(
+            plain("Try this:\n\n```rust\nfn main() {\n    println!(\"hi\");\n"),
+            ["Try this:", "", "╭─ rust", "│ fn main() {", "│     println!(\"hi\");", "╰─"]
+        );
+        // Just the opening fence
+        assert_eq!(plain("```"), ["╭─", "│ ", "╰─"]);
+    }
+
+    #[test]
+    fn half_written_lists_and_tables_render_what_is_there() {
+        assert_eq!(plain("Steps:\n\n1. build\n2. te"), ["Steps:", "", "1. build", "2. te"]);
+        // An item shows up once it has text
+        assert_eq!(plain("- one\n  - nested\n- "), ["• one", "  • nested"]);
+
+        // Rows may be missing cells while the table streams in
+        assert_eq!(
+            plain("| name | size |\n|------|------|\n| a.rs | 1 kB |\n| b.rs"),
+            ["name │ size", "─────┼─────", "a.rs │ 1 kB", "b.rs │     "]
+        );
+        // Until the delimiter row arrives it is a paragraph
+        assert_eq!(plain("| name | size |\n|---"), ["| name | size |", "|---"]);
+    }
+
+    #[test]
+    fn code_block_ranges_match_the_rendered_lines() {
+        let text = "# Fix\n\nChange:\n\n```rust\nlet a = 1;\nlet b = 2;\n```\n\n- in a list:\n  ```\n  ls -la\n  ```\n\n> quoted\n\n```python\nprint(1)\n";
+        let lines = plain(text);
+        let blocks = code_blocks(text);
+
+        let codes: Vec<&str> = blocks.iter().map(|block| block.code.as_str()).collect();
+        assert_eq!(codes, ["let a = 1;\nlet b = 2;", "ls -la", "print(1)"]);
+        for block in &blocks {
+            let rendered = &lines[block.lines.clone()];
+            assert!(rendered[0].trim_start().starts_with("╭─"), "{:?}", rendered);
+            assert!(rendered[rendered.len() - 1].trim_start().starts_with("╰─"), "{:?}", rendered);
+            let body: Vec<&str> = rendered[1..rendered.len() - 1]
+                .iter()
+                .map(|line| line.trim_start().strip_prefix("│ ").unwrap())
+                .collect();
+            assert_eq!(body.join("\n"), block.code);
+        }
+    }
+}
@@ -0,0 +1,257 @@
+use crate::tools::walker;
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use std::fmt::Write;
+use std::path::Path;
+
+/// Suggestions shown in the picker at once
+pub const MAX_SUGGESTIONS: usize = 8;
+/// Stop walking huge trees; fuzzy matching beyond this gets sluggish
+const MAX_INDEXED_FILES: usize = 50_000;
+/// A single attached file is cut off after this many bytes
+const MAX_FILE_BYTES: usize = 100_000;
+/// Files are no longer attached once this much has been attached in total
+const MAX_TOTAL_BYTES: usize = 250_000;
+
+/// Attached files are appended to the user message after this, one
+/// `<file path="...">` block each
+const ATTACHMENT_SEPARATOR: &str = "\n\n<file path=\"";
+
+/// Fuzzy file picker shown while the word under the cursor is an `@mention`
+pub struct MentionPicker {
+    /// Byte offset of the `@` in the input
+    pub start: usize,
+    pub query: String,
+    pub matches: Vec<String>,
+    pub selected: usize,
+    files: Vec<String>,
+}
+
+impl MentionPicker {
+    /// Open the picker for the mention starting at `start`, indexing the
+    /// workspace files (respecting .gitignore) once per mention
+    pub fn open(start: usize) -> Self {
+        let mut picker = Self {
+            start,
+            query: String::new(),
+            matches: Vec::new(),
+            selected: 0,
+            files: workspace_files(Path::new(".")),
+        };
+        picker.filter();
+        picker
+    }
+
+    pub fn set_query(&mut self, query: &str) {
+        if query != self.query {
+            self.query = query.to_string();
+            self.filter();
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.matches.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn selection(&self) -> Option<&str> {
+        self.matches.get(self.selected).map(String::as_str)
+    }
+
+    fn filter(&mut self) {
+        self.selected = 0;
+        if self.query.is_empty() {
+            self.matches = self.files.iter().take(MAX_SUGGESTIONS).cloned().collect();
+            return;
+        }
+
+        let matcher = SkimMatcherV2::default().ignore_case();
+        let mut scored: Vec<(i64, &String)> = self
+            .files
+            .iter()
+            .filter_map(|path| matcher.fuzzy_match(path, &self.query).map(|score| (score, path)))
+            .collect();
+        // Best score first, shorter paths breaking ties
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.len().cmp(&b.1.len())));
+        self.matches = scored
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|(_, path)| path.clone())
+            .collect();
+    }
+}
+
+/// The `@mention` being typed at `cursor`: the byte offset of its `@` and
+/// the query typed after it so far. The `@` must start a word.
+pub fn active_mention(text: &str, cursor: usize) -> Option<(usize, &str)> {
+    let before = &text[..cursor];
+    let word_start = before
+        .rfind(char::is_whitespace)
+        .map(|i| i + before[i..].chars().next().map_or(1, char::len_utf8))
+        .unwrap_or(0);
+    let query = before[word_start..].strip_prefix('@')?;
+    Some((word_start, query))
+}
+
+/// Byte ranges of `@mentions` in `text`, for highlighting
+pub fn mention_ranges(text: &str) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut offset = 0;
+    for word in text.split_inclusive(char::is_whitespace) {
+        let trimmed = word.trim_end();
+        if trimmed.len() > 1 && trimmed.starts_with('@') {
+            ranges.push((offset, offset + trimmed.len()));
+        }
+        offset += word.len();
+    }
+    ranges
+}
+
+/// Paths mentioned in `text` that exist as files, in order, without
+/// duplicates. Trailing punctuation like "see @src/main.rs." is ignored.
+pub fn referenced_files(text: &str) -> Vec<String> {
+    let mut paths: Vec<String> = Vec::new();
+    for (start, end) in mention_ranges(text) {
+        let mention = text[start + 1..end].trim_end_matches([',', '.', ';', ':', '!', '?', ')']);
+        if Path::new(mention).is_file() && !paths.iter().any(|p| p == mention) {
+            paths.push(mention.to_string());
+        }
+    }
+    paths
+}
+
+/// Append the contents of every file mentioned in `prompt` so the model
+/// gets them as context with the message
+pub fn attach_files(prompt: &str) -> String {
+    let mut message = prompt.to_string();
+    let mut total = 0;
+
+    for path in referenced_files(prompt) {
+        let body = match std::fs::read(&path) {
+            Ok(_) if total >= MAX_TOTAL_BYTES => {
+                format!("(not attached: over the {} byte attachment limit)", MAX_TOTAL_BYTES)
+            }
+            Ok(bytes) => match String::from_utf8(bytes) {
+                Ok(mut content) => {
+                    let limit = MAX_FILE_BYTES.min(MAX_TOTAL_BYTES - total);
+                    if content.len() > limit {
+                        let original = content.len();
+                        let mut cut = limit;
+                        while !content.is_char_boundary(cut) {
+                            cut -= 1;
+                        }
+                        content.truncate(cut);
+                        let _ = write!(content, "\n... (truncated, {} of {} bytes)", cut, original);
+                    }
+                    total += content.len();
+                    content
+                }
+                Err(_) => "(not attached: binary file)".to_string(),
+            },
+            Err(e) => format!("(not attached: {})", e),
+        };
+
+        let _ = write!(message, "{}{}\">\n{}\n</file>", ATTACHMENT_SEPARATOR, path, body);
+    }
+    message
+}
+
+/// Split a user message into the typed prompt and the paths of the files
+/// attached to it by `attach_files`
+pub fn split_attachments(message: &str) -> (&str, Vec<&str>) {
+    let Some(index) = message.find(ATTACHMENT_SEPARATOR) else {
+        return (message, Vec::new());
+    };
+    let paths = message[index..]
+        .split(ATTACHMENT_SEPARATOR)
+        .filter_map(|block| block.split_once("\">\n").map(|(path, _)| path))
+        .collect();
+    (&message[..index], paths)
+}
+
+fn workspace_files(root: &Path) -> Vec<String> {
+    walker(root)
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .filter_map(|entry| {
+            let path = entry.path().strip_prefix(root).ok()?;
+            Some(path.to_string_lossy().into_owned())
+        })
+        .take(MAX_INDEXED_FILES)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// The body attached for each file, in order
+    fn bodies(message: &str) -> Vec<&str> {
+        message
+            .split(ATTACHMENT_SEPARATOR)
+            .skip(1)
+            .map(|block| block.split_once("\">\n").unwrap().1.strip_suffix("\n</file>").unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn attached_files_are_cut_off_per_file_and_in_total() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = |name: &str, content: &[u8]| {
+            let path = dir.path().join(name);
+            fs::write(&path, content).unwrap();
+            path.to_string_lossy().into_owned()
+        };
+        let small = file("small.txt", b"fn main() {}\n");
+        // The limit falls inside an `é`, so the cut moves back before it
+        let mut text = "x".to_string();
+        text.push_str(&"é".repeat(MAX_FILE_BYTES));
+        let wide = file("wide.txt", text.as_bytes());
+        let big = file("big.txt", &[b'a'; MAX_FILE_BYTES]);
+        let rest = file("rest.txt", &[b'b'; MAX_FILE_BYTES]);
+        let late = file("late.txt", b"too late");
+        let binary = file("image.png", &[0x89, b'P', b'N', b'G', 0xff, 0xfe]);
+
+        let prompt = format!("Compare @{} @{} @{} @{} @{}, ignore @{}", small, binary, wide, big, rest, late);
+        let message = attach_files(&prompt);
+        let bodies = bodies(&message);
+
+        assert_eq!(bodies[0], "fn main() {}\n");
+        assert_eq!(bodies[1], "(not attached: binary file)");
+        assert_eq!(
+            bodies[2],
+            format!("{}\n... (truncated, {} of {} bytes)", &text[..MAX_FILE_BYTES - 1], MAX_FILE_BYTES - 1, text.len())
+        );
+        assert_eq!(bodies[3].len(), MAX_FILE_BYTES);
+        // Only what is left of the total
+        let left = MAX_TOTAL_BYTES - bodies[0].len() - bodies[2].len() - MAX_FILE_BYTES;
+        assert!(bodies[4].starts_with(&"b".repeat(left)));
+        assert!(bodies[4].ends_with(&format!("\n... (truncated, {} of {} bytes)", left, MAX_FILE_BYTES)));
+        assert_eq!(bodies[5], format!("(not attached: over the {} byte attachment limit)", MAX_TOTAL_BYTES));
+    }
+
+    #[test]
+    fn attachments_split_back_into_the_prompt_and_their_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.rs").to_string_lossy().into_owned();
+        let b = dir.path().join("b.md").to_string_lossy().into_owned();
+        fs::write(&a, "// a\n").unwrap();
+        fs::write(&b, "# Notes\n\nSee a.rs.\n").unwrap();
+
+        // Mentioned twice, attached once
+        let prompt = format!("Why does @{} differ from @{}? And @{} again.", a, b, a);
+        let message = attach_files(&prompt);
+        let (typed, paths) = split_attachments(&message);
+        assert_eq!(typed, prompt);
+        assert_eq!(paths, [a.as_str(), b.as_str()]);
+
+        assert_eq!(split_attachments("no files here"), ("no files here", vec![]));
+    }
+}
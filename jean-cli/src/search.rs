@@ -0,0 +1,262 @@
+use crate::theme;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use regex::{Regex, RegexBuilder};
+
+/// A match in the rendered text of a transcript item: the line it's on and
+/// its byte range within that line's text
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchMatch {
+    pub item: usize,
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Search through the transcript, opened with Ctrl-F. Matching is
+/// case-insensitive unless the query has uppercase letters.
+#[derive(Debug, Default)]
+pub struct TranscriptSearch {
+    pub query: String,
+    /// Treat the query as a regular expression instead of literal text
+    pub regex: bool,
+    /// Still typing the query; once accepted, n/N move between matches
+    pub editing: bool,
+    /// Matches in transcript order
+    pub matches: Vec<SearchMatch>,
+    pub current: Option<usize>,
+    /// Why the query can't be searched for, e.g. an invalid regex
+    pub error: Option<String>,
+}
+
+impl TranscriptSearch {
+    pub fn new() -> Self {
+        Self {
+            editing: true,
+            ..Self::default()
+        }
+    }
+
+    /// Search the rendered lines of every transcript item again. The current
+    /// match becomes the newest one, as the search runs back from the end.
+    pub fn update<'a>(&mut self, items: impl Iterator<Item = Vec<Line<'a>>>) {
+        self.matches.clear();
+        self.current = None;
+        self.error = None;
+        if self.query.is_empty() {
+            return;
+        }
+
+        let pattern = if self.regex {
+            self.query.clone()
+        } else {
+            regex::escape(&self.query)
+        };
+        let regex = match RegexBuilder::new(&pattern)
+            .case_insensitive(!self.query.chars().any(char::is_uppercase))
+            .build()
+        {
+            Ok(regex) => regex,
+            Err(e) => {
+                self.error = Some(regex_error(&e));
+                return;
+            }
+        };
+
+        for (item, lines) in items.enumerate() {
+            for (line, text) in lines.iter().map(line_text).enumerate() {
+                self.matches.extend(
+                    find_all(&regex, &text).map(|(start, end)| SearchMatch { item, line, start, end }),
+                );
+            }
+        }
+        self.current = self.matches.len().checked_sub(1);
+    }
+
+    pub fn current_match(&self) -> Option<SearchMatch> {
+        self.matches.get(self.current?).copied()
+    }
+
+    /// Move to the match before the current one, wrapping around
+    pub fn older(&mut self) {
+        if let Some(current) = self.current {
+            self.current = Some(current.checked_sub(1).unwrap_or(self.matches.len() - 1));
+        }
+    }
+
+    /// Move to the match after the current one, wrapping around
+    pub fn newer(&mut self) {
+        if let Some(current) = self.current {
+            self.current = Some((current + 1) % self.matches.len());
+        }
+    }
+
+    /// Highlight the matches on line `line` of item `item`
+    pub fn highlight(&self, item: usize, line: usize, rendered: &mut Line<'static>) {
+        let first = self.matches.partition_point(|m| (m.item, m.line) < (item, line));
+        let ranges: Vec<(usize, usize, Style)> = self.matches[first..]
+            .iter()
+            .enumerate()
+            .take_while(|(_, m)| (m.item, m.line) == (item, line))
+            .map(|(i, m)| {
+                let style = if self.current == Some(first + i) {
+                    theme::current().search_current
+                } else {
+                    theme::current().search_match
+                };
+                (m.start, m.end, style)
+            })
+            .collect();
+        if !ranges.is_empty() {
+            restyle(rendered, &ranges);
+        }
+    }
+
+    /// "3/12", or why there's nothing to show
+    pub fn status(&self) -> String {
+        if let Some(error) = &self.error {
+            return error.clone();
+        }
+        match self.current {
+            Some(current) => format!("{}/{}", current + 1, self.matches.len()),
+            None if self.query.is_empty() => String::new(),
+            None => "no matches".to_string(),
+        }
+    }
+}
+
+fn line_text(line: &Line) -> String {
+    line.spans.iter().map(|span| span.content.as_ref()).collect()
+}
+
+/// Non-overlapping, non-empty matches
+fn find_all<'a>(regex: &'a Regex, text: &'a str) -> impl Iterator<Item = (usize, usize)> + 'a {
+    regex
+        .find_iter(text)
+        .filter(|m| !m.is_empty())
+        .map(|m| (m.start(), m.end()))
+}
+
+fn regex_error(e: &regex::Error) -> String {
+    match e {
+        regex::Error::Syntax(message) => {
+            let reason = message.lines().last().unwrap_or(message).trim();
+            format!("invalid regex: {}", reason.trim_start_matches("error: "))
+        }
+        _ => format!("invalid regex: {}", e),
+    }
+}
+
+/// Patch `style` onto the byte ranges of the line's text, splitting spans
+/// where a range starts or ends inside one. Ranges are sorted and disjoint.
+pub fn restyle(line: &mut Line<'static>, ranges: &[(usize, usize, Style)]) {
+    let mut spans = Vec::with_capacity(line.spans.len() + ranges.len() * 2);
+    let mut offset = 0;
+    for span in line.spans.drain(..) {
+        let text = span.content.as_ref();
+        let span_end = offset + text.len();
+        let mut pos = offset;
+        for &(start, end, style) in ranges {
+            let start = start.clamp(pos, span_end);
+            let end = end.clamp(pos, span_end);
+            if start >= end {
+                continue;
+            }
+            if start > pos {
+                spans.push(Span::styled(text[pos - offset..start - offset].to_string(), span.style));
+            }
+            spans.push(Span::styled(
+                text[start - offset..end - offset].to_string(),
+                span.style.patch(style),
+            ));
+            pos = end;
+        }
+        if pos == offset {
+            spans.push(span);
+        } else if pos < span_end {
+            spans.push(Span::styled(text[pos - offset..].to_string(), span.style));
+        }
+        offset = span_end;
+    }
+    line.spans = spans;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::{Color, Modifier};
+
+    fn search(query: &str, regex: bool, items: &[&[&str]]) -> TranscriptSearch {
+        let mut search = TranscriptSearch::new();
+        search.query = query.to_string();
+        search.regex = regex;
+        search.update(items.iter().map(|lines| lines.iter().map(|&line| Line::from(line)).collect()));
+        search
+    }
+
+    fn ranges(search: &TranscriptSearch) -> Vec<(usize, usize, usize, usize)> {
+        search.matches.iter().map(|m| (m.item, m.line, m.start, m.end)).collect()
+    }
+
+    #[test]
+    fn matches_run_through_every_line_and_start_at_the_newest() {
+        let mut search = search("cargo", false, &[&["cargo build", "run cargo test"], &["no match"], &["Cargo.toml"]]);
+        assert_eq!(ranges(&search), [(0, 0, 0, 5), (0, 1, 4, 9), (2, 0, 0, 5)]);
+        assert_eq!(search.status(), "3/3");
+        search.newer();
+        assert_eq!(search.status(), "1/3");
+        search.older();
+        search.older();
+        assert_eq!(search.current_match().map(|m| (m.item, m.line)), Some((0, 1)));
+    }
+
+    #[test]
+    fn uppercase_in_the_query_makes_it_case_sensitive() {
+        let items: &[&[&str]] = &[&["Cargo cargo CARGO"]];
+        assert_eq!(search("cargo", false, items).matches.len(), 3);
+        assert_eq!(ranges(&search("Cargo", false, items)), [(0, 0, 0, 5)]);
+        assert_eq!(search("Rust", false, items).status(), "no matches");
+    }
+
+    #[test]
+    fn matches_are_byte_ranges_in_multibyte_text() {
+        let search = search("über", false, &[&["Grüße, ÜBER alles über"]]);
+        let (first, last) = ("Grüße, ".len(), "Grüße, ÜBER alles ".len());
+        assert_eq!(ranges(&search), [(0, 0, first, first + "ÜBER".len()), (0, 0, last, last + "über".len())]);
+    }
+
+    #[test]
+    fn literal_queries_are_escaped_and_regexes_checked() {
+        assert_eq!(ranges(&search("a.c", false, &[&["abc a.c"]])), [(0, 0, 4, 7)]);
+        assert_eq!(ranges(&search("a.c", true, &[&["abc a.c"]])), [(0, 0, 0, 3), (0, 0, 4, 7)]);
+        // Empty matches are skipped rather than highlighting nothing
+        assert_eq!(search("x*", true, &[&["abc"]]).status(), "no matches");
+
+        let invalid = search("fn (", true, &[&["fn (x)"]]);
+        assert_eq!(invalid.status(), "invalid regex: unclosed group");
+        assert!(invalid.matches.is_empty());
+    }
+
+    #[test]
+    fn restyle_splits_spans_at_range_boundaries() {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let red = Style::default().fg(Color::Red);
+        let mut line = Line::from(vec![Span::raw("héllo "), Span::styled("wörld", bold), Span::raw("!")]);
+        // One range inside the first span, one crossing into the second
+        restyle(&mut line, &[(1, 3, red), ("héllo".len(), "héllo wö".len(), red)]);
+
+        let spans: Vec<(&str, Style)> = line.spans.iter().map(|s| (s.content.as_ref(), s.style)).collect();
+        assert_eq!(
+            spans,
+            [
+                ("h", Style::default()),
+                ("é", red),
+                ("llo", Style::default()),
+                (" ", red),
+                ("wö", bold.patch(red)),
+                ("rld", bold),
+                ("!", Style::default()),
+            ]
+        );
+    }
+}
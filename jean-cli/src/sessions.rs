@@ -0,0 +1,123 @@
+use crate::mentions;
+use crate::transcript::TranscriptItem;
+use anyhow::{Context, Result, bail};
+use chrono::Local;
+use jean_shared::MessageRole;
+use std::fs;
+use std::path::PathBuf;
+
+/// Saved conversations, by name, for `/save` and `/load`
+pub struct SessionStore {
+    dir: PathBuf,
+}
+
+impl SessionStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Save under `name`, or a timestamp when none is given. Returns the
+    /// name used.
+    pub fn save(&self, name: Option<&str>, transcript: &[TranscriptItem]) -> Result<String> {
+        let name = match name {
+            Some(name) => name.to_string(),
+            None => Local::now().format("%Y%m%d_%H%M%S").to_string(),
+        };
+        let path = self.path(&name)?;
+        fs::create_dir_all(&self.dir)?;
+        fs::write(&path, serde_json::to_string_pretty(transcript)?)
+            .with_context(|| format!("failed to write {:?}", path))?;
+        Ok(name)
+    }
+
+    pub fn load(&self, name: &str) -> Result<Vec<TranscriptItem>> {
+        let path = self.path(name)?;
+        let json = fs::read_to_string(&path)
+            .with_context(|| format!("no saved conversation named '{}'", name))?;
+        serde_json::from_str(&json).with_context(|| format!("corrupt saved conversation {:?}", path))
+    }
+
+    /// Saved conversation names, most recently saved first
+    pub fn list(&self) -> Vec<String> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut saved: Vec<(std::time::SystemTime, String)> = entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let path = entry.path();
+                if path.extension()? != "json" {
+                    return None;
+                }
+                let modified = entry.metadata().ok()?.modified().ok()?;
+                Some((modified, path.file_stem()?.to_string_lossy().into_owned()))
+            })
+            .collect();
+        saved.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+        saved.into_iter().map(|(_, name)| name).collect()
+    }
+
+    fn path(&self, name: &str) -> Result<PathBuf> {
+        if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+            bail!("invalid conversation name '{}'", name);
+        }
+        Ok(self.dir.join(format!("{}.json", name)))
+    }
+}
+
+/// Write the conversation as markdown to `path`, defaulting to a
+/// timestamped file in the working directory. Returns the path written.
+pub fn export_markdown(path: Option<&str>, transcript: &[TranscriptItem]) -> Result<PathBuf> {
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => PathBuf::from(format!("jean-conversation-{}.md", Local::now().format("%Y%m%d_%H%M%S"))),
+    };
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, to_markdown(transcript)).with_context(|| format!("failed to write {:?}", path))?;
+    Ok(path)
+}
+
+fn to_markdown(transcript: &[TranscriptItem]) -> String {
+    let mut out = format!("# Conversation exported {}\n", Local::now().format("%Y-%m-%d %H:%M"));
+
+    for item in transcript {
+        let msg = match item {
+            TranscriptItem::Message(msg) => msg,
+            // Not part of the conversation
+            TranscriptItem::Thinking(_) => continue,
+            TranscriptItem::Tool(run) => {
+                out.push_str(&format!(
+                    "\n### Tool: {}\n\n```json\n{}\n```\n",
+                    run.name,
+                    run.pretty_arguments()
+                ));
+                if let Some(result) = &run.result {
+                    out.push_str(&format!("\n```\n{}\n```\n", result));
+                }
+                continue;
+            }
+        };
+        match msg.role {
+            MessageRole::User => {
+                let (prompt, attached) = mentions::split_attachments(&msg.content);
+                out.push_str(&format!("\n## You\n\n{}\n", prompt));
+                for path in attached {
+                    out.push_str(&format!("\n- attached `{}`\n", path));
+                }
+            }
+            MessageRole::Assistant => {
+                out.push_str(&format!("\n## Assistant\n\n{}\n", msg.content));
+            }
+            MessageRole::System if msg.content.starts_with("[Command]") => {}
+            MessageRole::System => {
+                out.push_str(&format!("\n> {}\n", msg.content));
+            }
+            MessageRole::Tool => {
+                out.push_str(&format!("\n```\n{}\n```\n", msg.content));
+            }
+        }
+    }
+    out
+}
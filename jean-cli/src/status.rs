@@ -0,0 +1,79 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// How often the git branch is re-read; tools may switch it mid-session
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Working directory and git branch for the status bar
+pub struct WorkspaceInfo {
+    pub cwd: String,
+    pub branch: Option<String>,
+    refreshed: Option<Instant>,
+}
+
+impl Default for WorkspaceInfo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WorkspaceInfo {
+    pub fn new() -> Self {
+        let mut info = Self {
+            cwd: String::new(),
+            branch: None,
+            refreshed: None,
+        };
+        info.refresh();
+        info
+    }
+
+    /// Re-read the directory and branch if they haven't been for a while
+    pub fn refresh(&mut self) {
+        if self.refreshed.is_some_and(|at| at.elapsed() < REFRESH_INTERVAL) {
+            return;
+        }
+        self.refreshed = Some(Instant::now());
+        let Ok(cwd) = std::env::current_dir() else {
+            return;
+        };
+        self.cwd = display_path(&cwd);
+        self.branch = git_branch(&cwd);
+    }
+}
+
+/// The path with the home directory shortened to `~`
+fn display_path(path: &Path) -> String {
+    if let Some(home) = std::env::var_os("HOME").map(PathBuf::from)
+        && let Ok(rest) = path.strip_prefix(&home)
+    {
+        return if rest.as_os_str().is_empty() {
+            "~".to_string()
+        } else {
+            format!("~/{}", rest.display())
+        };
+    }
+    path.display().to_string()
+}
+
+/// The checked out branch, or the short commit hash when detached. Read
+/// from `.git/HEAD` directly so it costs no process per refresh.
+pub fn git_branch(dir: &Path) -> Option<String> {
+    let dot_git = dir.ancestors().map(|d| d.join(".git")).find(|p| p.exists())?;
+    // Worktrees and submodules have a `.git` file pointing at the real dir
+    let git_dir = if dot_git.is_file() {
+        let content = fs::read_to_string(&dot_git).ok()?;
+        let target = content.strip_prefix("gitdir:")?.trim();
+        dot_git.parent()?.join(target)
+    } else {
+        dot_git
+    };
+
+    let head = fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    let head = head.trim();
+    match head.strip_prefix("ref: ") {
+        Some(reference) => Some(reference.strip_prefix("refs/heads/").unwrap_or(reference).to_string()),
+        None => Some(head.chars().take(7).collect()),
+    }
+}
@@ -0,0 +1,470 @@
+use crate::highlight;
+use ratatui::style::{Color, Modifier, Style};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// Themes that can be picked by name without defining them
+pub const BUILTIN_THEMES: &[&str] = &["dark", "light", "high-contrast", "no-color"];
+
+/// The styles everything in the UI is drawn with
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub user: Style,
+    pub assistant: Style,
+    pub system: Style,
+    pub tool: Style,
+    /// Secondary text: hints, code frames, list markers, link targets
+    pub muted: Style,
+    pub border: Style,
+    /// Popups, `@mentions`, command names, the git branch
+    pub accent: Style,
+    /// Modes that take over the input, work in progress
+    pub attention: Style,
+    pub error: Style,
+    pub success: Style,
+    pub heading1: Style,
+    pub heading2: Style,
+    pub heading3: Style,
+    pub link: Style,
+    pub inline_code: Style,
+    /// Code that isn't syntax highlighted
+    pub code: Style,
+    /// Items picked in copy mode
+    pub selection: Style,
+    /// The selected entry of a popup list
+    pub popup_selected: Style,
+    pub search_match: Style,
+    pub search_current: Style,
+    /// The new-output notice over the chat
+    pub notice: Style,
+    /// Lines added and removed in diffs, patched over syntax highlighting
+    pub diff_added: Style,
+    pub diff_removed: Style,
+    /// The words that changed within a replaced line
+    pub diff_added_emphasis: Style,
+    pub diff_removed_emphasis: Style,
+    /// syntect theme for code blocks, or `None` to not highlight them
+    pub syntax: Option<String>,
+}
+
+/// A user theme in the config file: a built-in theme to start from, and
+/// styles to replace, e.g. `{ "base": "light", "user": "bold blue" }`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ThemeConfig {
+    pub base: Option<String>,
+    pub syntax: Option<String>,
+    #[serde(flatten)]
+    pub styles: HashMap<String, String>,
+}
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// The theme picked at startup
+pub fn current() -> &'static Theme {
+    THEME.get_or_init(|| Theme::select(None, &HashMap::new()).0)
+}
+
+/// Pick the theme for this run; must come before anything is drawn.
+/// Returns warnings about a theme that couldn't be used as configured.
+pub fn init(name: Option<&str>, themes: &HashMap<String, ThemeConfig>) -> Vec<String> {
+    let (theme, warnings) = Theme::select(name, themes);
+    let _ = THEME.set(theme);
+    warnings
+}
+
+impl Theme {
+    /// The named theme, from the config or built in. Without a name the
+    /// theme follows `NO_COLOR` and the terminal background.
+    pub fn select(name: Option<&str>, themes: &HashMap<String, ThemeConfig>) -> (Self, Vec<String>) {
+        let mut warnings = Vec::new();
+        let Some(name) = name else {
+            return (Self::default_for_terminal(), warnings);
+        };
+        if let Some(config) = themes.get(name) {
+            let theme = Self::from_config(name, config, &mut warnings);
+            return (theme, warnings);
+        }
+        match Self::builtin(name) {
+            Some(theme) => (theme, warnings),
+            None => {
+                warnings.push(format!(
+                    "unknown theme '{}', expected one of {} or a theme in \"themes\"",
+                    name,
+                    BUILTIN_THEMES.join(", ")
+                ));
+                (Self::default_for_terminal(), warnings)
+            }
+        }
+    }
+
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "high-contrast" => Some(Self::high_contrast()),
+            "no-color" => Some(Self::no_color()),
+            _ => None,
+        }
+    }
+
+    fn default_for_terminal() -> Self {
+        let no_color = std::env::var_os("NO_COLOR");
+        let colorfgbg = std::env::var("COLORFGBG").ok();
+        Self::default_for(no_color.as_deref(), colorfgbg.as_deref())
+    }
+
+    /// The theme for a terminal with these `NO_COLOR` and `COLORFGBG`
+    /// values. See https://no-color.org
+    fn default_for(no_color: Option<&OsStr>, colorfgbg: Option<&str>) -> Self {
+        if no_color.is_some_and(|value| !value.is_empty()) {
+            Self::no_color()
+        } else if terminal_is_light(colorfgbg) {
+            Self::light()
+        } else {
+            Self::dark()
+        }
+    }
+
+    fn from_config(name: &str, config: &ThemeConfig, warnings: &mut Vec<String>) -> Self {
+        let mut theme = match config.base.as_deref() {
+            None => Self::default_for_terminal(),
+            Some(base) => Self::builtin(base).unwrap_or_else(|| {
+                warnings.push(format!("theme '{}': unknown base theme '{}'", name, base));
+                Self::default_for_terminal()
+            }),
+        };
+        match config.syntax.as_deref() {
+            None => {}
+            Some("none") => theme.syntax = None,
+            Some(syntax) if highlight::has_theme(syntax) => theme.syntax = Some(syntax.to_string()),
+            Some(syntax) => warnings.push(format!("theme '{}': unknown syntax theme '{}'", name, syntax)),
+        }
+
+        let mut styles: Vec<_> = config.styles.iter().collect();
+        styles.sort();
+        for (field, spec) in styles {
+            let Some(style) = theme.style_mut(field) else {
+                warnings.push(format!("theme '{}': unknown style '{}'", name, field));
+                continue;
+            };
+            match parse_style(spec) {
+                Ok(parsed) => *style = parsed,
+                Err(e) => warnings.push(format!("theme '{}': {}: {}", name, field, e)),
+            }
+        }
+        theme
+    }
+
+    fn style_mut(&mut self, field: &str) -> Option<&mut Style> {
+        Some(match field {
+            "user" => &mut self.user,
+            "assistant" => &mut self.assistant,
+            "system" => &mut self.system,
+            "tool" => &mut self.tool,
+            "muted" => &mut self.muted,
+            "border" => &mut self.border,
+            "accent" => &mut self.accent,
+            "attention" => &mut self.attention,
+            "error" => &mut self.error,
+            "success" => &mut self.success,
+            "heading1" => &mut self.heading1,
+            "heading2" => &mut self.heading2,
+            "heading3" => &mut self.heading3,
+            "link" => &mut self.link,
+            "inline_code" => &mut self.inline_code,
+            "code" => &mut self.code,
+            "selection" => &mut self.selection,
+            "popup_selected" => &mut self.popup_selected,
+            "search_match" => &mut self.search_match,
+            "search_current" => &mut self.search_current,
+            "notice" => &mut self.notice,
+            "diff_added" => &mut self.diff_added,
+            "diff_removed" => &mut self.diff_removed,
+            "diff_added_emphasis" => &mut self.diff_added_emphasis,
+            "diff_removed_emphasis" => &mut self.diff_removed_emphasis,
+            _ => return None,
+        })
+    }
+
+    pub fn dark() -> Self {
+        let fg = |color| Style::default().fg(color);
+        Self {
+            user: fg(Color::Cyan),
+            assistant: fg(Color::Green),
+            system: fg(Color::Yellow),
+            tool: fg(Color::Magenta),
+            muted: fg(Color::DarkGray),
+            border: fg(Color::White),
+            accent: fg(Color::Cyan),
+            attention: fg(Color::Yellow),
+            error: fg(Color::Red),
+            success: fg(Color::Green),
+            heading1: fg(Color::Magenta),
+            heading2: fg(Color::Cyan),
+            heading3: fg(Color::Blue),
+            link: fg(Color::Blue).add_modifier(Modifier::UNDERLINED),
+            inline_code: fg(Color::Yellow).bg(Color::Rgb(40, 40, 40)),
+            code: fg(Color::White),
+            selection: Style::default().bg(Color::DarkGray),
+            popup_selected: fg(Color::White).bg(Color::Blue).add_modifier(Modifier::BOLD),
+            search_match: fg(Color::Black).bg(Color::DarkGray),
+            search_current: fg(Color::Black).bg(Color::Yellow).add_modifier(Modifier::BOLD),
+            notice: fg(Color::Black).bg(Color::Yellow),
+            diff_added: Style::default().bg(Color::Rgb(20, 50, 20)),
+            diff_removed: Style::default().bg(Color::Rgb(60, 20, 20)),
+            diff_added_emphasis: Style::default().bg(Color::Rgb(30, 100, 30)),
+            diff_removed_emphasis: Style::default().bg(Color::Rgb(120, 30, 30)),
+            syntax: Some("base16-ocean.dark".to_string()),
+        }
+    }
+
+    /// Darker colors from the 256-color palette, which read well on a
+    /// white background where the basic ANSI colors are often washed out
+    pub fn light() -> Self {
+        let fg = |n| Style::default().fg(Color::Indexed(n));
+        Self {
+            user: fg(25),
+            assistant: fg(22),
+            system: fg(130),
+            tool: fg(90),
+            muted: fg(244),
+            border: Style::default(),
+            accent: fg(31),
+            attention: fg(130),
+            error: fg(124),
+            success: fg(28),
+            heading1: fg(90),
+            heading2: fg(25),
+            heading3: fg(31),
+            link: fg(25).add_modifier(Modifier::UNDERLINED),
+            inline_code: fg(88).bg(Color::Indexed(254)),
+            code: Style::default(),
+            selection: Style::default().bg(Color::Indexed(252)),
+            popup_selected: Style::default().fg(Color::White).bg(Color::Indexed(25)).add_modifier(Modifier::BOLD),
+            search_match: Style::default().bg(Color::Indexed(252)),
+            search_current: Style::default().fg(Color::Black).bg(Color::Indexed(220)).add_modifier(Modifier::BOLD),
+            notice: Style::default().fg(Color::Black).bg(Color::Indexed(220)),
+            diff_added: Style::default().bg(Color::Indexed(194)),
+            diff_removed: Style::default().bg(Color::Indexed(224)),
+            diff_added_emphasis: Style::default().bg(Color::Indexed(157)),
+            diff_removed_emphasis: Style::default().bg(Color::Indexed(217)),
+            syntax: Some("InspiredGitHub".to_string()),
+        }
+    }
+
+    /// Bright colors and bold text, for dark terminals
+    pub fn high_contrast() -> Self {
+        let fg = |color| Style::default().fg(color);
+        let bold = |color| fg(color).add_modifier(Modifier::BOLD);
+        Self {
+            user: bold(Color::LightCyan),
+            assistant: fg(Color::White),
+            system: bold(Color::LightYellow),
+            tool: bold(Color::LightMagenta),
+            muted: fg(Color::Gray),
+            border: bold(Color::White),
+            accent: bold(Color::LightCyan),
+            attention: bold(Color::LightYellow),
+            error: bold(Color::LightRed),
+            success: bold(Color::LightGreen),
+            heading1: fg(Color::LightMagenta),
+            heading2: fg(Color::LightCyan),
+            heading3: fg(Color::LightBlue),
+            link: fg(Color::LightBlue).add_modifier(Modifier::UNDERLINED),
+            inline_code: fg(Color::LightYellow),
+            code: fg(Color::White),
+            selection: Style::default().add_modifier(Modifier::REVERSED),
+            popup_selected: fg(Color::Black).bg(Color::White).add_modifier(Modifier::BOLD),
+            search_match: fg(Color::Black).bg(Color::White),
+            search_current: fg(Color::Black).bg(Color::LightYellow).add_modifier(Modifier::BOLD),
+            notice: fg(Color::Black).bg(Color::LightYellow).add_modifier(Modifier::BOLD),
+            diff_added: Style::default().bg(Color::Indexed(22)),
+            diff_removed: Style::default().bg(Color::Indexed(52)),
+            diff_added_emphasis: Style::default().bg(Color::Indexed(34)).add_modifier(Modifier::BOLD),
+            diff_removed_emphasis: Style::default().bg(Color::Indexed(124)).add_modifier(Modifier::BOLD),
+            syntax: Some("base16-eighties.dark".to_string()),
+        }
+    }
+
+    /// No colors at all; emphasis, dimming and reverse video carry the
+    /// distinctions instead
+    pub fn no_color() -> Self {
+        let plain = Style::default();
+        let with = |modifier| Style::default().add_modifier(modifier);
+        Self {
+            user: with(Modifier::BOLD),
+            assistant: plain,
+            system: with(Modifier::ITALIC),
+            tool: plain,
+            muted: with(Modifier::DIM),
+            border: plain,
+            accent: plain,
+            attention: with(Modifier::BOLD),
+            error: with(Modifier::BOLD),
+            success: plain,
+            heading1: plain,
+            heading2: plain,
+            heading3: plain,
+            link: with(Modifier::UNDERLINED),
+            inline_code: with(Modifier::BOLD),
+            code: plain,
+            selection: with(Modifier::REVERSED),
+            popup_selected: with(Modifier::REVERSED),
+            search_match: with(Modifier::UNDERLINED),
+            search_current: with(Modifier::REVERSED | Modifier::BOLD),
+            notice: with(Modifier::REVERSED),
+            diff_added: plain,
+            diff_removed: with(Modifier::DIM),
+            diff_added_emphasis: with(Modifier::REVERSED),
+            diff_removed_emphasis: with(Modifier::REVERSED),
+            syntax: None,
+        }
+    }
+}
+
+/// Parse a style like "bold cyan", "black on yellow" or "#ff8700 on 236":
+/// modifiers, a foreground color, and a background color after "on".
+/// Colors are names, 256-color indices or hex.
+pub fn parse_style(spec: &str) -> Result<Style, String> {
+    let mut style = Style::default();
+    let mut words = spec.split_whitespace();
+    while let Some(word) = words.next() {
+        let modifier = match word.to_ascii_lowercase().as_str() {
+            "bold" => Modifier::BOLD,
+            "dim" => Modifier::DIM,
+            "italic" => Modifier::ITALIC,
+            "underlined" | "underline" => Modifier::UNDERLINED,
+            "reversed" | "reverse" => Modifier::REVERSED,
+            "crossed_out" | "strikethrough" => Modifier::CROSSED_OUT,
+            "on" => {
+                let color = words.next().ok_or("expected a color after 'on'")?;
+                style = style.bg(parse_color(color)?);
+                continue;
+            }
+            _ => {
+                style = style.fg(parse_color(word)?);
+                continue;
+            }
+        };
+        style = style.add_modifier(modifier);
+    }
+    Ok(style)
+}
+
+fn parse_color(name: &str) -> Result<Color, String> {
+    Color::from_str(name).map_err(|_| format!("unknown color '{}'", name))
+}
+
+/// Many terminals export `COLORFGBG="fg;bg"` with ANSI color indices; a
+/// light background is 7 (white) or a bright color other than 8 (gray).
+/// Without it we assume a dark terminal.
+fn terminal_is_light(colorfgbg: Option<&str>) -> bool {
+    colorfgbg
+        .and_then(|value| value.rsplit(';').next()?.parse::<u8>().ok())
+        .is_some_and(|bg| bg == 7 || (bg > 8 && bg < 16))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn themes(json: &str) -> HashMap<String, ThemeConfig> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn default_theme_follows_no_color_and_the_background() {
+        let no_color = |value: &str| Theme::default_for(Some(OsStr::new(value)), Some("0;15"));
+        assert_eq!(no_color("1").syntax, None);
+        // An empty NO_COLOR doesn't count
+        assert_eq!(no_color("").syntax.as_deref(), Some("InspiredGitHub"));
+
+        let background = |colorfgbg| Theme::default_for(None, colorfgbg).syntax;
+        assert_eq!(background(Some("0;7")).as_deref(), Some("InspiredGitHub"));
+        assert_eq!(background(Some("15;8")).as_deref(), Some("base16-ocean.dark"));
+        assert_eq!(background(Some("default;default")).as_deref(), Some("base16-ocean.dark"));
+        assert_eq!(background(None).as_deref(), Some("base16-ocean.dark"));
+    }
+
+    #[test]
+    fn named_themes_come_from_the_config_before_the_builtins() {
+        let (theme, warnings) = Theme::select(Some("high-contrast"), &HashMap::new());
+        assert_eq!(theme.syntax.as_deref(), Some("base16-eighties.dark"));
+        assert!(warnings.is_empty());
+
+        let config = themes(r#"{ "light": { "base": "no-color", "user": "bold blue" } }"#);
+        let (theme, warnings) = Theme::select(Some("light"), &config);
+        assert_eq!(theme.syntax, None);
+        assert_eq!(theme.user, Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD));
+        assert!(warnings.is_empty());
+
+        let (_, warnings) = Theme::select(Some("solarized"), &config);
+        assert_eq!(
+            warnings,
+            ["unknown theme 'solarized', expected one of dark, light, high-contrast, no-color or a theme in \"themes\""]
+        );
+    }
+
+    #[test]
+    fn user_themes_keep_what_they_can_and_warn_about_the_rest() {
+        let config = themes(
+            r#"{ "mine": {
+                "base": "dark",
+                "syntax": "none",
+                "error": "underline red on #300000",
+                "eror": "red",
+                "muted": "grey50",
+                "link": "on"
+            } }"#,
+        );
+        let mut warnings = Vec::new();
+        let theme = Theme::from_config("mine", &config["mine"], &mut warnings);
+        assert_eq!(theme.syntax, None);
+        assert_eq!(
+            theme.error,
+            Style::default().fg(Color::Red).bg(Color::Rgb(0x30, 0, 0)).add_modifier(Modifier::UNDERLINED)
+        );
+        // Styles that didn't parse keep the base theme's
+        assert_eq!(theme.muted, Theme::dark().muted);
+        assert_eq!(theme.link, Theme::dark().link);
+        assert_eq!(
+            warnings,
+            [
+                "theme 'mine': unknown style 'eror'",
+                "theme 'mine': link: expected a color after 'on'",
+                "theme 'mine': muted: unknown color 'grey50'",
+            ]
+        );
+
+        let config = themes(r#"{ "mine": { "base": "sepia", "syntax": "Monokai Extended Pro" } }"#);
+        let mut warnings = Vec::new();
+        Theme::from_config("mine", &config["mine"], &mut warnings);
+        assert_eq!(
+            warnings,
+            [
+                "theme 'mine': unknown base theme 'sepia'",
+                "theme 'mine': unknown syntax theme 'Monokai Extended Pro'",
+            ]
+        );
+    }
+
+    #[test]
+    fn styles_parse_modifiers_and_both_colors() {
+        assert_eq!(parse_style("bold cyan"), Ok(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)));
+        assert_eq!(
+            parse_style("#ff8700 on 236"),
+            Ok(Style::default().fg(Color::Rgb(0xff, 0x87, 0)).bg(Color::Indexed(236)))
+        );
+        assert_eq!(
+            parse_style("Reverse Strikethrough"),
+            Ok(Style::default().add_modifier(Modifier::REVERSED | Modifier::CROSSED_OUT))
+        );
+        assert_eq!(parse_style(""), Ok(Style::default()));
+        assert_eq!(parse_style("black on"), Err("expected a color after 'on'".to_string()));
+        assert_eq!(parse_style("#ff87"), Err("unknown color '#ff87'".to_string()));
+    }
+}
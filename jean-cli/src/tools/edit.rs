@@ -0,0 +1,185 @@
+use crate::checkpoints::{canonical_path, CheckpointStore};
+use crate::diff::FileChange;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WriteFileArgs {
+    pub filename: String,
+    pub content: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct EditFileArgs {
+    pub filename: String,
+    pub old_string: String,
+    pub new_string: String,
+    #[serde(default)]
+    pub replace_all: bool,
+}
+
+pub async fn execute_write_file(args: WriteFileArgs, checkpoints: &mut CheckpointStore) -> String {
+    let path = match in_project(&args.filename) {
+        Ok(path) => path,
+        Err(e) => return e,
+    };
+    let path = path.as_path();
+    if let Err(e) = checkpoints.snapshot(path) {
+        return format!("Refusing to write '{}': {}", args.filename, e);
+    }
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty())
+        && let Err(e) = tokio::fs::create_dir_all(parent).await
+    {
+        return format!("Error creating directory for '{}': {}", args.filename, e);
+    }
+
+    match tokio::fs::write(path, &args.content).await {
+        Ok(()) => format!(
+            "Wrote {} bytes ({} lines) to '{}'",
+            args.content.len(),
+            args.content.lines().count(),
+            args.filename
+        ),
+        Err(e) => format!("Error writing file '{}': {}", args.filename, e),
+    }
+}
+
+pub async fn execute_edit_file(args: EditFileArgs, checkpoints: &mut CheckpointStore) -> String {
+    let path = match in_project(&args.filename) {
+        Ok(path) => path,
+        Err(e) => return e,
+    };
+    let content = match tokio::fs::read_to_string(&path).await {
+        Ok(content) => content,
+        Err(e) => return format!("Error reading file '{}': {}", args.filename, e),
+    };
+
+    let (updated, occurrences) = match apply_edit(&content, &args) {
+        Ok(edited) => edited,
+        Err(e) => return e,
+    };
+
+    if let Err(e) = checkpoints.snapshot(&path) {
+        return format!("Refusing to edit '{}': {}", args.filename, e);
+    }
+
+    match tokio::fs::write(&path, updated).await {
+        Ok(()) => format!(
+            "Replaced {} occurrence{} in '{}'",
+            occurrences,
+            if occurrences == 1 { "" } else { "s" },
+            args.filename
+        ),
+        Err(e) => format!("Error writing file '{}': {}", args.filename, e),
+    }
+}
+
+/// Where `filename` really is, as long as that's inside the project: the
+/// directory jean runs in. Absolute paths, `..` and symlinks that lead
+/// elsewhere are refused.
+fn in_project(filename: &str) -> Result<PathBuf, String> {
+    let root = std::env::current_dir().map_err(|e| format!("Refusing to write '{}': {}", filename, e))?;
+    confine(&root, filename)
+}
+
+fn confine(root: &Path, filename: &str) -> Result<PathBuf, String> {
+    let refuse = |reason: String| format!("Refusing to write '{}': {}", filename, reason);
+    let root = root.canonicalize().map_err(|e| refuse(e.to_string()))?;
+    let path = canonical_path(&root.join(filename)).map_err(|e| refuse(e.to_string()))?;
+    if !path.starts_with(&root) {
+        return Err(refuse(format!("it is outside the project ({})", root.display())));
+    }
+    Ok(path)
+}
+
+/// The file's contents after the edit, and how many places it changes
+fn apply_edit(content: &str, args: &EditFileArgs) -> Result<(String, usize), String> {
+    if args.old_string.is_empty() {
+        return Err("old_string must not be empty; use write_file to create files".to_string());
+    }
+
+    let occurrences = content.matches(&args.old_string).count();
+    match (occurrences, args.replace_all) {
+        (0, _) => Err(format!("old_string not found in '{}'", args.filename)),
+        (1, _) | (_, true) => Ok((content.replace(&args.old_string, &args.new_string), occurrences)),
+        (n, false) => Err(format!(
+            "old_string occurs {} times in '{}'; add surrounding context to make it unique or set replace_all",
+            n, args.filename
+        )),
+    }
+}
+
+/// What a `write_file` or `edit_file` call would do to the file, for
+/// review before it runs. `None` for other tools and for calls that would
+/// fail anyway.
+pub async fn proposed_change(name: &str, arguments: &str) -> Option<FileChange> {
+    match name {
+        "write_file" => {
+            let args: WriteFileArgs = serde_json::from_str(arguments).ok()?;
+            let path = in_project(&args.filename).ok()?;
+            let before = tokio::fs::read_to_string(&path).await.ok();
+            Some(FileChange {
+                path: args.filename,
+                before,
+                after: Some(args.content),
+            })
+        }
+        "edit_file" => {
+            let args: EditFileArgs = serde_json::from_str(arguments).ok()?;
+            let path = in_project(&args.filename).ok()?;
+            let before = tokio::fs::read_to_string(&path).await.ok()?;
+            let (after, _) = apply_edit(&before, &args).ok()?;
+            Some(FileChange {
+                path: args.filename,
+                before: Some(before),
+                after: Some(after),
+            })
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn paths_inside_the_project_resolve() {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir(root.path().join("src")).unwrap();
+        let canonical_root = root.path().canonicalize().unwrap();
+
+        assert_eq!(confine(root.path(), "src/lib.rs").unwrap(), canonical_root.join("src/lib.rs"));
+        assert_eq!(confine(root.path(), "src/../README.md").unwrap(), canonical_root.join("README.md"));
+        // Directories that don't exist yet are fine too
+        assert_eq!(confine(root.path(), "new/dir/mod.rs").unwrap(), canonical_root.join("new/dir/mod.rs"));
+    }
+
+    #[test]
+    fn paths_leaving_the_project_are_refused() {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir(root.path().join("src")).unwrap();
+
+        for filename in ["../outside.txt", "src/../../outside.txt", "new/../../outside.txt", "/etc/hosts"] {
+            let result = confine(root.path(), filename);
+            assert!(
+                result.as_ref().is_err_and(|e| e.starts_with("Refusing")),
+                "{} should be refused, got {:?}",
+                filename,
+                result
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_out_of_the_project_are_refused() {
+        let root = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::os::unix::fs::symlink(outside.path(), root.path().join("link")).unwrap();
+
+        assert!(confine(root.path(), "link/file.txt").is_err());
+    }
+}
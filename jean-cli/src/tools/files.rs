@@ -0,0 +1,240 @@
+use globset::Glob;
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+const MAX_LISTED_ENTRIES: usize = 1000;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FindFilesArgs {
+    pub pattern: String,
+    #[serde(default = "default_max_results")]
+    pub max_results: usize,
+}
+
+fn default_max_results() -> usize {
+    200
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ListDirectoryArgs {
+    #[serde(default = "default_list_path")]
+    pub path: String,
+    #[serde(default = "default_depth")]
+    pub depth: usize,
+}
+
+fn default_list_path() -> String {
+    ".".to_string()
+}
+
+fn default_depth() -> usize {
+    2
+}
+
+pub async fn execute_find_files(args: FindFilesArgs) -> String {
+    tokio::task::spawn_blocking(move || find_files(Path::new("."), &args))
+        .await
+        .unwrap_or_else(|e| format!("find_files task failed: {}", e))
+}
+
+pub async fn execute_list_directory(args: ListDirectoryArgs) -> String {
+    tokio::task::spawn_blocking(move || list_directory(&args))
+        .await
+        .unwrap_or_else(|e| format!("list_directory task failed: {}", e))
+}
+
+/// Walker shared by the file tools and the `@mention` picker: .gitignore,
+/// .ignore and git excludes are respected, hidden files are left to the
+/// ignore rules, and `.git` itself, which no ignore rule covers, is skipped.
+pub(crate) fn walker(root: &Path) -> WalkBuilder {
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .standard_filters(true)
+        .hidden(false)
+        .git_ignore(true)
+        .git_global(true)
+        .git_exclude(true)
+        .filter_entry(|entry| entry.file_name() != ".git");
+    builder
+}
+
+/// List files under `root` whose workspace-relative path matches the glob,
+/// most recently modified first, capped at `max_results`.
+fn find_files(root: &Path, args: &FindFilesArgs) -> String {
+    let glob = match Glob::new(&args.pattern) {
+        Ok(g) => g.compile_matcher(),
+        Err(e) => return format!("Invalid glob pattern '{}': {}", args.pattern, e),
+    };
+
+    let mut found: Vec<(PathBuf, SystemTime)> = walker(root)
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_some_and(|ft| ft.is_file()))
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+            if !glob.is_match(relative) {
+                return None;
+            }
+            let modified = entry
+                .metadata()
+                .ok()
+                .and_then(|m| m.modified().ok())
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            Some((relative.to_path_buf(), modified))
+        })
+        .collect();
+
+    if found.is_empty() {
+        return format!("No files found matching '{}'", args.pattern);
+    }
+
+    found.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    let total = found.len();
+    let shown = total.min(args.max_results);
+    let mut output = if shown < total {
+        format!(
+            "Found {} files matching '{}' (showing {} most recently modified):\n",
+            total, args.pattern, shown
+        )
+    } else {
+        format!("Found {} files matching '{}':\n", total, args.pattern)
+    };
+
+    for (path, _) in found.iter().take(shown) {
+        output.push_str(&path.display().to_string());
+        output.push('\n');
+    }
+    output
+}
+
+/// Render a directory tree down to `args.depth` levels, with file sizes.
+/// Depth 1 lists the directory's own entries.
+fn list_directory(args: &ListDirectoryArgs) -> String {
+    let root = Path::new(&args.path);
+    if !root.is_dir() {
+        return format!("Not a directory: '{}'", args.path);
+    }
+    if args.depth == 0 {
+        return "depth must be at least 1".to_string();
+    }
+
+    let mut builder = walker(root);
+    builder
+        .max_depth(Some(args.depth))
+        .sort_by_file_name(|a, b| a.cmp(b));
+
+    let mut lines = vec![format!("{}/", root.display())];
+    let mut truncated = false;
+
+    for entry in builder.build().filter_map(Result::ok) {
+        if entry.depth() == 0 {
+            continue;
+        }
+        if lines.len() > MAX_LISTED_ENTRIES {
+            truncated = true;
+            break;
+        }
+
+        let indent = "  ".repeat(entry.depth());
+        let name = entry.file_name().to_string_lossy();
+        if entry.file_type().is_some_and(|ft| ft.is_dir()) {
+            lines.push(format!("{}{}/", indent, name));
+        } else {
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            lines.push(format!("{}{} ({})", indent, name, human_size(size)));
+        }
+    }
+
+    if truncated {
+        lines.push(format!(
+            "... (truncated after {} entries, use a smaller depth or a subdirectory)",
+            MAX_LISTED_ENTRIES
+        ));
+    }
+    lines.join("\n")
+}
+
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// A git repository with an ignored build directory
+    fn workspace() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for (path, contents) in [
+            (".gitignore", "target/\n"),
+            (".git/HEAD", "ref: refs/heads/main\n"),
+            (".git/config", "[core]\n"),
+            ("Cargo.toml", "[package]\n"),
+            ("src/main.rs", "fn main() {}\n"),
+            ("src/tools/mod.rs", "pub mod grep;\n"),
+            ("target/debug/main.rs", "// generated\n"),
+        ] {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        dir
+    }
+
+    fn find(root: &Path, pattern: &str) -> Vec<String> {
+        let args = FindFilesArgs { pattern: pattern.to_string(), max_results: 200 };
+        let mut found: Vec<String> = find_files(root, &args).lines().skip(1).map(str::to_string).collect();
+        found.sort();
+        found
+    }
+
+    fn list(path: &Path, depth: usize) -> String {
+        list_directory(&ListDirectoryArgs { path: path.display().to_string(), depth })
+    }
+
+    #[test]
+    fn find_files_matches_the_glob_against_relative_paths() {
+        let dir = workspace();
+        assert_eq!(find(dir.path(), "**/*.rs"), ["src/main.rs", "src/tools/mod.rs"]);
+        assert_eq!(find(dir.path(), "src/*/mod.rs"), ["src/tools/mod.rs"]);
+        let args = FindFilesArgs { pattern: "*.py".to_string(), max_results: 200 };
+        assert_eq!(find_files(dir.path(), &args), "No files found matching '*.py'");
+    }
+
+    #[test]
+    fn find_files_skips_ignored_files_and_git_internals() {
+        let dir = workspace();
+        assert_eq!(find(dir.path(), "**/*"), [".gitignore", "Cargo.toml", "src/main.rs", "src/tools/mod.rs"]);
+    }
+
+    #[test]
+    fn list_directory_descends_to_the_given_depth() {
+        let dir = workspace();
+        let tree = list(dir.path(), 1);
+        assert!(tree.contains("  src/"), "{}", tree);
+        assert!(!tree.contains("main.rs"), "{}", tree);
+        assert!(!tree.contains(".git/") && !tree.contains("target/"), "{}", tree);
+
+        let tree = list(dir.path(), 3);
+        assert!(tree.contains("      mod.rs (14 B)"), "{}", tree);
+        assert!(!tree.contains("HEAD"), "{}", tree);
+
+        assert_eq!(list(dir.path(), 0), "depth must be at least 1");
+    }
+}
@@ -0,0 +1,267 @@
+use crate::status::git_branch;
+use jean_shared::GitContext;
+use serde::{Deserialize, Serialize};
+use std::process::{Output, Stdio};
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+use tokio::process::Command;
+
+/// Git output beyond this is cut off so a huge diff can't flood the context
+const MAX_OUTPUT_BYTES: usize = 50_000;
+const GIT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Whether the worktree had uncommitted changes when last checked, `None`
+/// before the first check is done. Kept here so sending a prompt never
+/// waits on git.
+static DIRTY: Mutex<Option<bool>> = Mutex::new(None);
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GitDiffArgs {
+    /// Diff the index against HEAD instead of the worktree against the index
+    #[serde(default)]
+    pub staged: bool,
+    /// Diff the worktree against this commit, branch or tag
+    #[serde(default)]
+    pub against: Option<String>,
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GitLogArgs {
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default = "default_max_count")]
+    pub max_count: usize,
+}
+
+fn default_max_count() -> usize {
+    20
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GitBlameArgs {
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+pub async fn execute_git_status() -> String {
+    run_git(&["status", "--short", "--branch"]).await
+}
+
+pub async fn execute_git_diff(args: GitDiffArgs) -> String {
+    let mut cmd = vec!["diff".to_string()];
+    if args.staged {
+        cmd.push("--cached".to_string());
+    }
+    if let Some(against) = args.against {
+        if let Err(e) = validate_rev(&against) {
+            return e;
+        }
+        cmd.push(against);
+    }
+    push_path(&mut cmd, args.path);
+
+    let output = run_git(&cmd).await;
+    if output.is_empty() {
+        "No differences".to_string()
+    } else {
+        output
+    }
+}
+
+pub async fn execute_git_log(args: GitLogArgs) -> String {
+    let mut cmd = vec![
+        "log".to_string(),
+        format!("--max-count={}", args.max_count.max(1)),
+        "--date=short".to_string(),
+        "--format=%h %ad %an%n    %s".to_string(),
+    ];
+    push_path(&mut cmd, args.path);
+    run_git(&cmd).await
+}
+
+pub async fn execute_git_blame(args: GitBlameArgs) -> String {
+    if args.start_line == 0 || args.end_line < args.start_line {
+        return format!(
+            "Invalid line range {}-{} (lines are 1-based and end must be >= start)",
+            args.start_line, args.end_line
+        );
+    }
+    let cmd = vec![
+        "blame".to_string(),
+        "--date=short".to_string(),
+        format!("-L{},{}", args.start_line, args.end_line),
+        "--".to_string(),
+        args.path,
+    ];
+    run_git(&cmd).await
+}
+
+/// Current branch and whether the worktree had uncommitted changes at the
+/// last `refresh_git_status` (unknown until one has finished), or `None`
+/// when the working directory is not inside a git repository.
+pub fn git_context() -> Option<GitContext> {
+    let branch = git_branch(&std::env::current_dir().ok()?)?;
+    Some(GitContext {
+        branch,
+        dirty: *DIRTY.lock().unwrap_or_else(PoisonError::into_inner),
+    })
+}
+
+/// Check for uncommitted changes in the background, for the next
+/// `git_context`
+pub fn refresh_git_status() {
+    let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+        return;
+    };
+    runtime.spawn(async {
+        if let Some(status) = git_stdout(&["status", "--porcelain"]).await {
+            *DIRTY.lock().unwrap_or_else(PoisonError::into_inner) = Some(!status.trim().is_empty());
+        }
+    });
+}
+
+fn push_path(cmd: &mut Vec<String>, path: Option<String>) {
+    if let Some(path) = path {
+        cmd.push("--".to_string());
+        cmd.push(path);
+    }
+}
+
+/// Refs come from the model, so make sure they can't be read as options
+fn validate_rev(rev: &str) -> Result<(), String> {
+    if rev.is_empty() || rev.starts_with('-') {
+        return Err(format!("Invalid git revision '{}'", rev));
+    }
+    Ok(())
+}
+
+async fn git_stdout<S: AsRef<str>>(args: &[S]) -> Option<String> {
+    let output = git_output(args).await.ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Run a read-only git command, returning stdout (or the error) capped at
+/// `MAX_OUTPUT_BYTES`
+async fn run_git<S: AsRef<str>>(args: &[S]) -> String {
+    let output = match git_output(args).await {
+        Ok(output) => output,
+        Err(e) => return e,
+    };
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return format!("git exited with {}: {}", output.status, stderr.trim());
+    }
+    truncate_output(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Run git, giving up after `GIT_TIMEOUT`
+async fn git_output<S: AsRef<str>>(args: &[S]) -> Result<Output, String> {
+    match tokio::time::timeout(GIT_TIMEOUT, git_command(args).output()).await {
+        Ok(Ok(output)) => Ok(output),
+        Ok(Err(e)) => Err(format!("Failed to run git: {}", e)),
+        Err(_) => Err(format!("git timed out after {}s", GIT_TIMEOUT.as_secs())),
+    }
+}
+
+fn git_command<S: AsRef<str>>(args: &[S]) -> Command {
+    let mut cmd = Command::new("git");
+    cmd.args(["--no-pager", "-c", "color.ui=never"])
+        .args(args.iter().map(AsRef::as_ref))
+        .env("GIT_OPTIONAL_LOCKS", "0")
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+    cmd
+}
+
+fn truncate_output(mut output: String) -> String {
+    if output.len() <= MAX_OUTPUT_BYTES {
+        return output;
+    }
+    let total = output.len();
+    let mut cut = MAX_OUTPUT_BYTES;
+    while !output.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    output.truncate(cut);
+    output.push_str(&format!(
+        "\n... (output truncated, {} of {} bytes shown)",
+        cut, total
+    ));
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// A repository with one commit of `files`
+    fn repo(files: &[(&str, &str)]) -> TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, content) in files {
+            fs::write(dir.path().join(name), content).unwrap();
+        }
+        let commit = ["-c", "user.name=Jean", "-c", "user.email=jean@example.com", "commit", "-qm", "Initial"];
+        for args in [&["init", "-q"][..], &["add", "."], &commit] {
+            let status = std::process::Command::new("git").args(args).current_dir(dir.path()).status().unwrap();
+            assert!(status.success());
+        }
+        dir
+    }
+
+    #[tokio::test]
+    async fn option_like_revisions_are_refused_before_git_runs() {
+        let dir = tempfile::tempdir().unwrap();
+        let leak = dir.path().join("leak.diff");
+        let revs = [format!("--output={}", leak.display()), "-p".to_string(), "--ext-diff".to_string(), String::new()];
+        for rev in revs {
+            let args = GitDiffArgs { staged: false, against: Some(rev.clone()), path: None };
+            assert_eq!(execute_git_diff(args).await, format!("Invalid git revision '{}'", rev));
+        }
+        assert!(!leak.exists());
+
+        assert!(validate_rev("HEAD~3").is_ok());
+        assert!(validate_rev("origin/main").is_ok());
+    }
+
+    #[tokio::test]
+    async fn output_is_capped() {
+        let big = format!("{}\n", "x".repeat(MAX_OUTPUT_BYTES * 2));
+        let dir = repo(&[("big.txt", &big)]);
+        let root = dir.path().to_string_lossy().into_owned();
+
+        let output = run_git(&["-C", &root, "show", "HEAD:big.txt"]).await;
+        let (shown, note) = output.split_at(MAX_OUTPUT_BYTES);
+        assert_eq!(shown, &big[..MAX_OUTPUT_BYTES]);
+        assert_eq!(note, format!("\n... (output truncated, {} of {} bytes shown)", MAX_OUTPUT_BYTES, big.len()));
+
+        // Never cut inside a character
+        let wide = format!("x{}", "é".repeat(MAX_OUTPUT_BYTES));
+        assert!(truncate_output(wide).contains(&format!("truncated, {} of", MAX_OUTPUT_BYTES - 1)));
+    }
+
+    #[tokio::test]
+    async fn blame_ranges_are_checked_before_git_runs() {
+        for (start_line, end_line) in [(0, 3), (5, 4)] {
+            let args = GitBlameArgs { path: "Cargo.toml".to_string(), start_line, end_line };
+            assert_eq!(
+                execute_git_blame(args).await,
+                format!(
+                    "Invalid line range {}-{} (lines are 1-based and end must be >= start)",
+                    start_line, end_line
+                )
+            );
+        }
+        // A valid range goes on to git, which reports the missing file
+        let args = GitBlameArgs { path: "no/such/file.rs".to_string(), start_line: 1, end_line: 1 };
+        assert!(execute_git_blame(args).await.starts_with("git exited with"));
+    }
+}
@@ -0,0 +1,239 @@
+use super::files::walker;
+use globset::{Glob, GlobMatcher};
+use grep_regex::RegexMatcher;
+use grep_searcher::{
+    BinaryDetection, MmapChoice, Searcher, SearcherBuilder, Sink, SinkContext, SinkContextKind,
+    SinkMatch,
+};
+use ignore::WalkState;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GrepArgs {
+    pub search_term: String,
+    pub filter: String,
+    #[serde(default = "default_context_lines")]
+    pub context_lines: usize,
+}
+
+fn default_context_lines() -> usize {
+    2
+}
+
+/// A single line of output, either a match or surrounding context
+#[derive(Debug, Clone)]
+pub struct GrepLine {
+    pub line_number: u64,
+    pub text: String,
+    pub is_match: bool,
+}
+
+/// All matches found in one file, grouped into contiguous blocks of lines
+#[derive(Debug)]
+pub struct FileMatches {
+    pub path: PathBuf,
+    pub blocks: Vec<Vec<GrepLine>>,
+    pub match_count: usize,
+}
+
+/// Entry point used by the tool dispatcher. The search itself is blocking
+/// (mmap + parallel walker threads), so it runs off the async runtime.
+pub async fn execute_grep(args: GrepArgs) -> String {
+    let result = tokio::task::spawn_blocking(move || {
+        search(Path::new("."), &args).map(|files| format_results(&args, &files))
+    })
+    .await;
+
+    match result {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => e,
+        Err(e) => format!("Grep task failed: {}", e),
+    }
+}
+
+/// Search every file under `root` matching `args.filter` for `args.search_term`.
+///
+/// Files are walked in parallel with the walker `find_files` uses (so
+/// .gitignore and friends are respected and `.git` is skipped) and searched with `grep-searcher`, which memory-maps
+/// large files when it is safe to do so. Results are sorted by path so the
+/// output is stable regardless of thread scheduling.
+pub fn search(root: &Path, args: &GrepArgs) -> Result<Vec<FileMatches>, String> {
+    let matcher = RegexMatcher::new(&args.search_term)
+        .map_err(|e| format!("Invalid regex pattern '{}': {}", args.search_term, e))?;
+
+    let glob = Glob::new(&args.filter)
+        .map_err(|e| format!("Invalid filter pattern '{}': {}", args.filter, e))?
+        .compile_matcher();
+
+    let (tx, rx) = mpsc::channel::<FileMatches>();
+
+    walker(root).build_parallel().run(|| {
+        let tx = tx.clone();
+        let matcher = matcher.clone();
+        let glob = glob.clone();
+        let mut searcher = build_searcher(args.context_lines);
+
+        Box::new(move |entry| {
+            let entry = match entry {
+                Ok(e) => e,
+                Err(_) => return WalkState::Continue,
+            };
+
+            if !entry.file_type().is_some_and(|ft| ft.is_file()) {
+                return WalkState::Continue;
+            }
+
+            let path = entry.path();
+            if !matches_filter(&glob, root, path) {
+                return WalkState::Continue;
+            }
+
+            let mut sink = BlockSink::default();
+            if searcher.search_path(&matcher, path, &mut sink).is_err() {
+                return WalkState::Continue;
+            }
+
+            if let Some(file_matches) = sink.into_file_matches(path)
+                && tx.send(file_matches).is_err()
+            {
+                return WalkState::Quit;
+            }
+            WalkState::Continue
+        })
+    });
+    drop(tx);
+
+    let mut files: Vec<FileMatches> = rx.into_iter().collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// Render search results in the format the model has been prompted with:
+/// a `=== path ===` header per block, `N:> ` for matching lines and `N:  `
+/// for context lines.
+pub fn format_results(args: &GrepArgs, files: &[FileMatches]) -> String {
+    let total: usize = files.iter().map(|f| f.match_count).sum();
+    if total == 0 {
+        return format!(
+            "No matches found for '{}' in files matching '{}'",
+            args.search_term, args.filter
+        );
+    }
+
+    let mut blocks = Vec::new();
+    for file in files {
+        for block in &file.blocks {
+            let mut out = vec![format!("=== {} ===", file.path.display())];
+            for line in block {
+                let marker = if line.is_match { ">" } else { " " };
+                out.push(format!("{}:{} {}", line.line_number, marker, line.text));
+            }
+            blocks.push(out.join("\n"));
+        }
+    }
+
+    format!("Found {} matches:\n\n{}", total, blocks.join("\n\n"))
+}
+
+fn build_searcher(context_lines: usize) -> Searcher {
+    // SAFETY: the files may be modified while mapped, which at worst yields
+    // garbled results for that file; this is the same trade-off ripgrep makes.
+    let mmap = unsafe { MmapChoice::auto() };
+    SearcherBuilder::new()
+        .line_number(true)
+        .before_context(context_lines)
+        .after_context(context_lines)
+        .binary_detection(BinaryDetection::quit(b'\x00'))
+        .memory_map(mmap)
+        .build()
+}
+
+/// Match the filter against the path relative to the search root, so that
+/// `src/**/*.rs` works as well as plain `*.rs`.
+fn matches_filter(glob: &GlobMatcher, root: &Path, path: &Path) -> bool {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    glob.is_match(relative)
+}
+
+/// Collects matched and context lines, starting a new block whenever the
+/// searcher reports a gap between context windows.
+#[derive(Default)]
+struct BlockSink {
+    blocks: Vec<Vec<GrepLine>>,
+    current: Vec<GrepLine>,
+    match_count: usize,
+}
+
+impl BlockSink {
+    fn push(&mut self, line_number: Option<u64>, bytes: &[u8], is_match: bool) {
+        let text = String::from_utf8_lossy(bytes);
+        self.current.push(GrepLine {
+            line_number: line_number.unwrap_or(0),
+            text: text.trim_end_matches(['\n', '\r']).to_string(),
+            is_match,
+        });
+    }
+
+    fn flush(&mut self) {
+        if !self.current.is_empty() {
+            self.blocks.push(std::mem::take(&mut self.current));
+        }
+    }
+
+    fn into_file_matches(mut self, path: &Path) -> Option<FileMatches> {
+        self.flush();
+        if self.match_count == 0 {
+            return None;
+        }
+        Some(FileMatches {
+            path: path.to_path_buf(),
+            blocks: self.blocks,
+            match_count: self.match_count,
+        })
+    }
+}
+
+impl Sink for BlockSink {
+    type Error = io::Error;
+
+    fn matched(&mut self, _searcher: &Searcher, mat: &SinkMatch<'_>) -> Result<bool, io::Error> {
+        self.match_count += 1;
+        self.push(mat.line_number(), mat.bytes(), true);
+        Ok(true)
+    }
+
+    fn context(&mut self, _searcher: &Searcher, ctx: &SinkContext<'_>) -> Result<bool, io::Error> {
+        if matches!(ctx.kind(), SinkContextKind::Other) {
+            return Ok(true);
+        }
+        self.push(ctx.line_number(), ctx.bytes(), false);
+        Ok(true)
+    }
+
+    fn context_break(&mut self, _searcher: &Searcher) -> Result<bool, io::Error> {
+        self.flush();
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn search_skips_git_internals() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".git")).unwrap();
+        fs::write(dir.path().join(".git/COMMIT_EDITMSG"), "needle in the last commit\n").unwrap();
+        fs::write(dir.path().join("notes.txt"), "a needle here\n").unwrap();
+
+        let args = GrepArgs { search_term: "needle".to_string(), filter: "**/*".to_string(), context_lines: 0 };
+        let files = search(dir.path(), &args).unwrap();
+        let paths: Vec<&Path> = files.iter().map(|f| f.path.strip_prefix(dir.path()).unwrap()).collect();
+        assert_eq!(paths, [Path::new("notes.txt")]);
+    }
+}
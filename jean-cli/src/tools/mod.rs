@@ -0,0 +1,157 @@
+mod edit;
+mod files;
+mod git;
+pub mod grep;
+mod symbols;
+
+use crate::checkpoints::CheckpointStore;
+use edit::{execute_edit_file, execute_write_file, EditFileArgs, WriteFileArgs};
+pub use edit::proposed_change;
+use files::{execute_find_files, execute_list_directory, FindFilesArgs, ListDirectoryArgs};
+pub(crate) use files::walker;
+pub use git::refresh_git_status;
+use git::{
+    execute_git_blame, execute_git_diff, execute_git_log, execute_git_status, git_context,
+    GitBlameArgs, GitDiffArgs, GitLogArgs,
+};
+use grep::{execute_grep, GrepArgs};
+use jean_shared::SessionContext;
+use symbols::{execute_find_symbol, FindSymbolArgs};
+use serde::{Deserialize, Serialize};
+
+// Tool argument structs
+#[derive(Debug, Deserialize, Serialize)]
+struct ReadFileArgs {
+    filename: String,
+}
+
+pub async fn execute_tool(name: &str, arguments: &str, checkpoints: &mut CheckpointStore) -> String {
+    match name {
+        "read_file" => {
+            // Parse arguments with typed struct
+            let args: ReadFileArgs = match serde_json::from_str(arguments) {
+                Ok(args) => args,
+                Err(e) => {
+                    return format!("Error parsing read_file arguments: {}", e);
+                }
+            };
+
+            // Read the file
+            match tokio::fs::read_to_string(&args.filename).await {
+                Ok(content) => content,
+                Err(e) => format!("Error reading file '{}': {}", args.filename, e),
+            }
+        }
+        "write_file" => {
+            let args: WriteFileArgs = match serde_json::from_str(arguments) {
+                Ok(args) => args,
+                Err(e) => {
+                    return format!("Error parsing write_file arguments: {}", e);
+                }
+            };
+
+            let result = execute_write_file(args, checkpoints).await;
+            symbols::invalidate();
+            result
+        }
+        "edit_file" => {
+            let args: EditFileArgs = match serde_json::from_str(arguments) {
+                Ok(args) => args,
+                Err(e) => {
+                    return format!("Error parsing edit_file arguments: {}", e);
+                }
+            };
+
+            let result = execute_edit_file(args, checkpoints).await;
+            symbols::invalidate();
+            result
+        }
+        "grep" => {
+            // Parse arguments with typed struct
+            let args: GrepArgs = match serde_json::from_str(arguments) {
+                Ok(args) => args,
+                Err(e) => {
+                    return format!("Error parsing grep arguments: {}", e);
+                }
+            };
+
+            execute_grep(args).await
+        }
+        "find_files" => {
+            let args: FindFilesArgs = match serde_json::from_str(arguments) {
+                Ok(args) => args,
+                Err(e) => {
+                    return format!("Error parsing find_files arguments: {}", e);
+                }
+            };
+
+            execute_find_files(args).await
+        }
+        "list_directory" => {
+            let args: ListDirectoryArgs = match serde_json::from_str(arguments) {
+                Ok(args) => args,
+                Err(e) => {
+                    return format!("Error parsing list_directory arguments: {}", e);
+                }
+            };
+
+            execute_list_directory(args).await
+        }
+        "find_symbol" => {
+            let args: FindSymbolArgs = match serde_json::from_str(arguments) {
+                Ok(args) => args,
+                Err(e) => {
+                    return format!("Error parsing find_symbol arguments: {}", e);
+                }
+            };
+
+            execute_find_symbol(args).await
+        }
+        "git_status" => execute_git_status().await,
+        "git_diff" => {
+            let args: GitDiffArgs = match serde_json::from_str(arguments) {
+                Ok(args) => args,
+                Err(e) => {
+                    return format!("Error parsing git_diff arguments: {}", e);
+                }
+            };
+
+            execute_git_diff(args).await
+        }
+        "git_log" => {
+            let args: GitLogArgs = match serde_json::from_str(arguments) {
+                Ok(args) => args,
+                Err(e) => {
+                    return format!("Error parsing git_log arguments: {}", e);
+                }
+            };
+
+            execute_git_log(args).await
+        }
+        "git_blame" => {
+            let args: GitBlameArgs = match serde_json::from_str(arguments) {
+                Ok(args) => args,
+                Err(e) => {
+                    return format!("Error parsing git_blame arguments: {}", e);
+                }
+            };
+
+            execute_git_blame(args).await
+        }
+        _ => format!("Unknown tool: {}", name),
+    }
+}
+
+/// Workspace details sent along with each chat request. The branch is read
+/// fresh; whether there are uncommitted changes is as of the end of the
+/// last turn, so a slow git never holds up sending.
+pub fn session_context() -> SessionContext {
+    let cwd = std::env::current_dir()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|_| ".".to_string());
+
+    SessionContext {
+        cwd,
+        git: git_context(),
+    }
+}
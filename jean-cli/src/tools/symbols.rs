@@ -0,0 +1,638 @@
+use super::files::walker;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use tree_sitter::{Language, Node, Parser};
+
+const MAX_DEFINITION_LINES: usize = 80;
+const MAX_SYMBOL_RESULTS: usize = 20;
+/// Queries closer together than this reuse the last walk of the tree
+const REWALK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Index shared across tool calls so only files that changed since the last
+/// query get re-parsed.
+static INDEX: LazyLock<Mutex<SymbolIndex>> = LazyLock::new(|| Mutex::new(SymbolIndex::default()));
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FindSymbolArgs {
+    /// Symbol to look up; omit together with `file` to get an outline
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Restrict the lookup to one file, or outline it when `name` is omitted
+    #[serde(default)]
+    pub file: Option<String>,
+    /// Only return symbols of this kind (function, method, struct, ...)
+    #[serde(default)]
+    pub kind: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    Method,
+    Struct,
+    Enum,
+    Trait,
+    Impl,
+    Interface,
+    Class,
+    Type,
+    Const,
+    Module,
+    Macro,
+}
+
+impl SymbolKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SymbolKind::Function => "function",
+            SymbolKind::Method => "method",
+            SymbolKind::Struct => "struct",
+            SymbolKind::Enum => "enum",
+            SymbolKind::Trait => "trait",
+            SymbolKind::Impl => "impl",
+            SymbolKind::Interface => "interface",
+            SymbolKind::Class => "class",
+            SymbolKind::Type => "type",
+            SymbolKind::Const => "const",
+            SymbolKind::Module => "module",
+            SymbolKind::Macro => "macro",
+        }
+    }
+}
+
+impl fmt::Display for SymbolKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Enclosing impl, class, trait or module, if any
+    pub container: Option<String>,
+    pub path: PathBuf,
+    /// 1-based, inclusive
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Nesting depth, used to indent outlines
+    pub depth: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Lang {
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    Tsx,
+    Go,
+}
+
+impl Lang {
+    fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "rs" => Some(Lang::Rust),
+            "py" => Some(Lang::Python),
+            "js" | "jsx" | "mjs" | "cjs" => Some(Lang::JavaScript),
+            "ts" | "mts" | "cts" => Some(Lang::TypeScript),
+            "tsx" => Some(Lang::Tsx),
+            "go" => Some(Lang::Go),
+            _ => None,
+        }
+    }
+
+    fn language(&self) -> Language {
+        match self {
+            Lang::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Lang::Python => tree_sitter_python::LANGUAGE.into(),
+            Lang::JavaScript => tree_sitter_javascript::LANGUAGE.into(),
+            Lang::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Lang::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            Lang::Go => tree_sitter_go::LANGUAGE.into(),
+        }
+    }
+
+    /// Map a definition node to its symbol kind. `in_type` is true when the
+    /// node sits inside an impl, trait or class body, which makes functions
+    /// methods; a module body doesn't.
+    fn classify(&self, kind: &str, in_type: bool) -> Option<SymbolKind> {
+        let function = if in_type {
+            SymbolKind::Method
+        } else {
+            SymbolKind::Function
+        };
+        match self {
+            Lang::Rust => match kind {
+                "function_item" | "function_signature_item" => Some(function),
+                "struct_item" | "union_item" => Some(SymbolKind::Struct),
+                "enum_item" => Some(SymbolKind::Enum),
+                "trait_item" => Some(SymbolKind::Trait),
+                "impl_item" => Some(SymbolKind::Impl),
+                "type_item" => Some(SymbolKind::Type),
+                "const_item" | "static_item" => Some(SymbolKind::Const),
+                "mod_item" => Some(SymbolKind::Module),
+                "macro_definition" => Some(SymbolKind::Macro),
+                _ => None,
+            },
+            Lang::Python => match kind {
+                "function_definition" => Some(function),
+                "class_definition" => Some(SymbolKind::Class),
+                _ => None,
+            },
+            Lang::JavaScript | Lang::TypeScript | Lang::Tsx => match kind {
+                "function_declaration" | "generator_function_declaration" => Some(function),
+                "method_definition" | "method_signature" => Some(SymbolKind::Method),
+                "class_declaration" | "abstract_class_declaration" => Some(SymbolKind::Class),
+                "interface_declaration" => Some(SymbolKind::Interface),
+                "type_alias_declaration" => Some(SymbolKind::Type),
+                "enum_declaration" => Some(SymbolKind::Enum),
+                _ => None,
+            },
+            Lang::Go => match kind {
+                "function_declaration" => Some(SymbolKind::Function),
+                "method_declaration" => Some(SymbolKind::Method),
+                "type_spec" => Some(SymbolKind::Type),
+                _ => None,
+            },
+        }
+    }
+}
+
+struct IndexedFile {
+    modified: SystemTime,
+    symbols: Vec<Symbol>,
+}
+
+#[derive(Default)]
+pub struct SymbolIndex {
+    files: HashMap<PathBuf, IndexedFile>,
+    /// When the tree was last walked, and from which root
+    walked: Option<(PathBuf, Instant)>,
+}
+
+impl SymbolIndex {
+    /// Bring the index up to date with the tree under `root`: parse new and
+    /// modified files, drop deleted ones, and leave everything else alone.
+    /// The walk itself is skipped when the last one was recent; returns
+    /// whether it happened.
+    pub fn refresh(&mut self, root: &Path) -> bool {
+        if self
+            .walked
+            .as_ref()
+            .is_some_and(|(walked, at)| walked == root && at.elapsed() < REWALK_INTERVAL)
+        {
+            return false;
+        }
+
+        let mut seen = HashSet::new();
+        for entry in walker(root).build().filter_map(Result::ok) {
+            if !entry.file_type().is_some_and(|ft| ft.is_file()) {
+                continue;
+            }
+            let path = entry.path();
+            let relative = path.strip_prefix(root).unwrap_or(path).to_path_buf();
+            if self.refresh_file(path, &relative) {
+                seen.insert(relative);
+            }
+        }
+        self.files.retain(|path, _| seen.contains(path));
+        self.walked = Some((root.to_path_buf(), Instant::now()));
+        true
+    }
+
+    /// Re-parse one file if it changed since it was indexed. Returns false
+    /// for files in languages that aren't indexed.
+    fn refresh_file(&mut self, path: &Path, relative: &Path) -> bool {
+        let Some(lang) = Lang::from_path(path) else {
+            return false;
+        };
+        let modified = std::fs::metadata(path)
+            .and_then(|m| m.modified())
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        if self.files.get(relative).is_some_and(|f| f.modified == modified) {
+            return true;
+        }
+
+        let symbols = std::fs::read(path)
+            .ok()
+            .map(|source| parse_symbols(lang, relative, &source))
+            .unwrap_or_default();
+        self.files.insert(relative.to_path_buf(), IndexedFile { modified, symbols });
+        true
+    }
+
+    pub fn symbols(&self) -> impl Iterator<Item = &Symbol> {
+        self.files.values().flat_map(|f| f.symbols.iter())
+    }
+
+    pub fn file_symbols(&self, path: &Path) -> Option<&[Symbol]> {
+        self.files.get(path).map(|f| f.symbols.as_slice())
+    }
+}
+
+/// Walk the tree again on the next query, e.g. after a tool wrote a file
+pub fn invalidate() {
+    let mut index = match INDEX.lock() {
+        Ok(index) => index,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    index.walked = None;
+}
+
+pub async fn execute_find_symbol(args: FindSymbolArgs) -> String {
+    tokio::task::spawn_blocking(move || find_symbol(Path::new("."), &args))
+        .await
+        .unwrap_or_else(|e| format!("find_symbol task failed: {}", e))
+}
+
+fn find_symbol(root: &Path, args: &FindSymbolArgs) -> String {
+    let mut index = match INDEX.lock() {
+        Ok(index) => index,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    let walked = index.refresh(root);
+
+    let file = args.file.as_deref().map(normalize_path);
+    // The file asked about is always current, even between walks
+    if let Some(file) = &file
+        && root.join(file).is_file()
+    {
+        index.refresh_file(&root.join(file), file);
+    }
+    // A name missing from a reused walk may be in a file created outside
+    // jean since, so walk again before answering that it doesn't exist
+    if let Some(name) = &args.name
+        && !walked
+        && !index
+            .symbols()
+            .any(|s| s.name == *name && in_scope(s, file.as_deref(), args.kind.as_deref()))
+    {
+        index.walked = None;
+        index.refresh(root);
+    }
+    match (&args.name, &file) {
+        (None, None) => "Provide a symbol `name`, a `file` to outline, or both".to_string(),
+        (None, Some(file)) => outline(&index, file, args.kind.as_deref()),
+        (Some(name), file) => definitions(&index, root, name, file.as_deref(), args.kind.as_deref()),
+    }
+}
+
+fn normalize_path(path: &str) -> PathBuf {
+    Path::new(path)
+        .strip_prefix("./")
+        .unwrap_or(Path::new(path))
+        .to_path_buf()
+}
+
+fn kind_matches(symbol: &Symbol, kind: Option<&str>) -> bool {
+    kind.is_none_or(|k| symbol.kind.as_str().eq_ignore_ascii_case(k))
+}
+
+/// Whether `symbol` is in `file` when one is given, and of `kind`
+fn in_scope(symbol: &Symbol, file: Option<&Path>, kind: Option<&str>) -> bool {
+    file.is_none_or(|f| symbol.path == f) && kind_matches(symbol, kind)
+}
+
+fn outline(index: &SymbolIndex, file: &Path, kind: Option<&str>) -> String {
+    let Some(symbols) = index.file_symbols(file) else {
+        return format!(
+            "No outline available for '{}' (missing, ignored or unsupported language)",
+            file.display()
+        );
+    };
+
+    let mut lines = vec![format!("Outline of {}:", file.display())];
+    for symbol in symbols.iter().filter(|s| kind_matches(s, kind)) {
+        lines.push(format!(
+            "{}{} {} (lines {}-{})",
+            "  ".repeat(symbol.depth),
+            symbol.kind,
+            symbol.name,
+            symbol.start_line,
+            symbol.end_line
+        ));
+    }
+    if lines.len() == 1 {
+        lines.push("(no symbols found)".to_string());
+    }
+    lines.join("\n")
+}
+
+fn definitions(
+    index: &SymbolIndex,
+    root: &Path,
+    name: &str,
+    file: Option<&Path>,
+    kind: Option<&str>,
+) -> String {
+    let mut matches: Vec<&Symbol> = index
+        .symbols()
+        .filter(|s| in_scope(s, file, kind))
+        .filter(|s| s.name == name)
+        .collect();
+
+    // Fall back to a case-insensitive substring search so near-misses
+    // (e.g. `handle_socket` vs `handle_socket_message`) still help.
+    let exact = !matches.is_empty();
+    if !exact {
+        let needle = name.to_lowercase();
+        matches = index
+            .symbols()
+            .filter(|s| in_scope(s, file, kind))
+            .filter(|s| s.name.to_lowercase().contains(&needle))
+            .collect();
+    }
+
+    if matches.is_empty() {
+        return format!("No symbol named '{}' found", name);
+    }
+
+    matches.sort_by(|a, b| a.path.cmp(&b.path).then(a.start_line.cmp(&b.start_line)));
+    let total = matches.len();
+    matches.truncate(MAX_SYMBOL_RESULTS);
+
+    let mut sections = Vec::new();
+    if !exact {
+        sections.push(format!(
+            "No exact match for '{}'; {} partial matches:",
+            name, total
+        ));
+    }
+    for symbol in &matches {
+        let container = symbol
+            .container
+            .as_ref()
+            .map(|c| format!(" in {}", c))
+            .unwrap_or_default();
+        let mut section = format!(
+            "=== {}:{}-{} ({} {}{}) ===",
+            symbol.path.display(),
+            symbol.start_line,
+            symbol.end_line,
+            symbol.kind,
+            symbol.name,
+            container
+        );
+        // Only print bodies for exact hits; partial matches are a listing
+        if exact && let Some(body) = read_lines(&root.join(&symbol.path), symbol) {
+            section.push('\n');
+            section.push_str(&body);
+        }
+        sections.push(section);
+    }
+    if total > matches.len() {
+        sections.push(format!("... and {} more", total - matches.len()));
+    }
+    sections.join("\n\n")
+}
+
+fn read_lines(path: &Path, symbol: &Symbol) -> Option<String> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let span = symbol.end_line + 1 - symbol.start_line;
+    let mut lines: Vec<String> = content
+        .lines()
+        .enumerate()
+        .skip(symbol.start_line - 1)
+        .take(span.min(MAX_DEFINITION_LINES))
+        .map(|(i, line)| format!("{}:  {}", i + 1, line))
+        .collect();
+    if span > MAX_DEFINITION_LINES {
+        lines.push(format!("... ({} more lines)", span - MAX_DEFINITION_LINES));
+    }
+    Some(lines.join("\n"))
+}
+
+fn parse_symbols(lang: Lang, path: &Path, source: &[u8]) -> Vec<Symbol> {
+    let mut parser = Parser::new();
+    if parser.set_language(&lang.language()).is_err() {
+        return Vec::new();
+    }
+    let Some(tree) = parser.parse(source, None) else {
+        return Vec::new();
+    };
+
+    let mut symbols = Vec::new();
+    collect(lang, path, source, tree.root_node(), None, 0, &mut symbols);
+    symbols
+}
+
+/// The symbol whose body is being walked, e.g. an impl or a module
+#[derive(Clone, Copy)]
+struct Container<'a> {
+    name: &'a str,
+    kind: SymbolKind,
+}
+
+impl Container<'_> {
+    /// Functions defined directly inside are methods
+    fn is_type(&self) -> bool {
+        matches!(
+            self.kind,
+            SymbolKind::Impl | SymbolKind::Trait | SymbolKind::Class | SymbolKind::Interface
+        )
+    }
+}
+
+fn collect(
+    lang: Lang,
+    path: &Path,
+    source: &[u8],
+    node: Node,
+    container: Option<Container>,
+    depth: usize,
+    out: &mut Vec<Symbol>,
+) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        let in_type = container.is_some_and(|c| c.is_type());
+        let Some(kind) = lang.classify(child.kind(), in_type) else {
+            collect(lang, path, source, child, container, depth, out);
+            continue;
+        };
+        let Some(name) = symbol_name(lang, child, source) else {
+            collect(lang, path, source, child, container, depth, out);
+            continue;
+        };
+
+        out.push(Symbol {
+            name: name.clone(),
+            kind,
+            container: container.map(|c| c.name.to_string()),
+            path: path.to_path_buf(),
+            start_line: child.start_position().row + 1,
+            end_line: child.end_position().row + 1,
+            depth,
+        });
+
+        // Descend into bodies that define members
+        if matches!(
+            kind,
+            SymbolKind::Impl
+                | SymbolKind::Trait
+                | SymbolKind::Class
+                | SymbolKind::Interface
+                | SymbolKind::Module
+        ) {
+            let container = Container { name: &name, kind };
+            collect(lang, path, source, child, Some(container), depth + 1, out);
+        }
+    }
+}
+
+fn symbol_name(lang: Lang, node: Node, source: &[u8]) -> Option<String> {
+    let text = |n: Node| n.utf8_text(source).ok().map(str::to_string);
+
+    if matches!(lang, Lang::Rust) && node.kind() == "impl_item" {
+        let ty = text(node.child_by_field_name("type")?)?;
+        return Some(match node.child_by_field_name("trait").and_then(text) {
+            Some(trait_name) => format!("{} for {}", trait_name, ty),
+            None => ty,
+        });
+    }
+
+    text(node.child_by_field_name("name")?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbols(file: &str, source: &str) -> Vec<(String, SymbolKind, Option<String>)> {
+        let path = Path::new(file);
+        let lang = Lang::from_path(path).expect("supported language");
+        parse_symbols(lang, path, source.as_bytes())
+            .into_iter()
+            .map(|s| (s.name, s.kind, s.container))
+            .collect()
+    }
+
+    fn entry(name: &str, kind: SymbolKind, container: Option<&str>) -> (String, SymbolKind, Option<String>) {
+        (name.to_string(), kind, container.map(str::to_string))
+    }
+
+    #[test]
+    fn rust_items_and_methods() {
+        let source = "
+struct Parser { pos: usize }
+
+impl Parser {
+    fn new() -> Self { Parser { pos: 0 } }
+}
+
+impl Iterator for Parser {
+    type Item = u8;
+    fn next(&mut self) -> Option<u8> { None }
+}
+
+trait Visit {
+    fn visit(&self);
+}
+
+fn parse() {}
+";
+        assert_eq!(
+            symbols("parser.rs", source),
+            vec![
+                entry("Parser", SymbolKind::Struct, None),
+                entry("Parser", SymbolKind::Impl, None),
+                entry("new", SymbolKind::Method, Some("Parser")),
+                entry("Iterator for Parser", SymbolKind::Impl, None),
+                entry("Item", SymbolKind::Type, Some("Iterator for Parser")),
+                entry("next", SymbolKind::Method, Some("Iterator for Parser")),
+                entry("Visit", SymbolKind::Trait, None),
+                entry("visit", SymbolKind::Method, Some("Visit")),
+                entry("parse", SymbolKind::Function, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn functions_in_a_module_are_not_methods() {
+        let source = "
+mod tests {
+    fn helper() {}
+
+    impl super::Parser {
+        fn reset(&mut self) {}
+    }
+}
+";
+        assert_eq!(
+            symbols("lib.rs", source),
+            vec![
+                entry("tests", SymbolKind::Module, None),
+                entry("helper", SymbolKind::Function, Some("tests")),
+                entry("super::Parser", SymbolKind::Impl, Some("tests")),
+                entry("reset", SymbolKind::Method, Some("super::Parser")),
+            ]
+        );
+    }
+
+    #[test]
+    fn python_classes_and_functions() {
+        let source = "
+class Lexer:
+    def tokens(self):
+        pass
+
+def main():
+    pass
+";
+        assert_eq!(
+            symbols("lexer.py", source),
+            vec![
+                entry("Lexer", SymbolKind::Class, None),
+                entry("tokens", SymbolKind::Method, Some("Lexer")),
+                entry("main", SymbolKind::Function, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn typescript_declarations() {
+        let source = "
+interface Shape { area(): number; }
+
+type Id = string;
+
+class Circle implements Shape {
+    area() { return 1; }
+}
+
+function draw(shape: Shape) {}
+";
+        assert_eq!(
+            symbols("shapes.ts", source),
+            vec![
+                entry("Shape", SymbolKind::Interface, None),
+                entry("area", SymbolKind::Method, Some("Shape")),
+                entry("Id", SymbolKind::Type, None),
+                entry("Circle", SymbolKind::Class, None),
+                entry("area", SymbolKind::Method, Some("Circle")),
+                entry("draw", SymbolKind::Function, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn files_created_between_walks_are_found_on_a_miss() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("lib.rs"), "fn first() {}\n").unwrap();
+        let find = |name: &str| {
+            let args = FindSymbolArgs { name: Some(name.to_string()), file: None, kind: None };
+            find_symbol(dir.path(), &args)
+        };
+        assert!(find("first").starts_with("=== lib.rs:1-1 (function first) ==="));
+
+        // Written by something other than jean's tools, so nothing invalidated the walk
+        std::fs::write(dir.path().join("new.rs"), "fn second() {}\n").unwrap();
+        assert!(find("second").starts_with("=== new.rs:1-1 (function second) ==="));
+        assert_eq!(find("third"), "No symbol named 'third' found");
+    }
+}
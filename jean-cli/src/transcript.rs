@@ -0,0 +1,121 @@
+use crate::diff::FileChange;
+use jean_shared::ChatMessage;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Argument names worth showing in a tool's one-line summary, in order of
+/// preference
+const KEY_ARGUMENTS: [&str; 7] = ["filename", "path", "pattern", "search_term", "name", "file", "against"];
+const MAX_KEY_ARGUMENT_CHARS: usize = 60;
+
+/// One entry in the chat view
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TranscriptItem {
+    Message(ChatMessage),
+    Tool(ToolRun),
+    Thinking(Thinking),
+}
+
+/// The reasoning a model streamed before its answer. Only shown, never
+/// sent back; collapsed to one line until expanded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Thinking {
+    pub text: String,
+    #[serde(default)]
+    pub expanded: bool,
+}
+
+/// A tool call made by the model and what it returned. Shown as a one-line
+/// summary that can be expanded to the full arguments and output.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolRun {
+    pub id: String,
+    pub name: String,
+    pub arguments: String,
+    /// `None` while the tool is still running
+    pub result: Option<String>,
+    #[serde(default)]
+    pub duration_ms: u64,
+    #[serde(default)]
+    pub expanded: bool,
+    /// What a file editing tool changed, shown as a diff
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub change: Option<FileChange>,
+}
+
+impl ToolRun {
+    pub fn new(id: String, name: String, arguments: String) -> Self {
+        Self {
+            id,
+            name,
+            arguments,
+            result: None,
+            duration_ms: 0,
+            expanded: false,
+            change: None,
+        }
+    }
+
+    pub fn finish(&mut self, result: String, duration: Duration) {
+        self.result = Some(result);
+        self.duration_ms = duration.as_millis() as u64;
+    }
+
+    /// Tools report failures as text; these are the prefixes they use
+    pub fn failed(&self) -> bool {
+        self.result.as_deref().is_some_and(|result| {
+            ["Error", "Failed", "Invalid", "Unknown tool", "Refusing", "Rejected", "Cancelled", "git exited"]
+                .iter()
+                .any(|prefix| result.starts_with(prefix))
+        })
+    }
+
+    /// The most telling argument, e.g. the file being read
+    pub fn key_argument(&self) -> Option<String> {
+        let args: serde_json::Value = serde_json::from_str(&self.arguments).ok()?;
+        let args = args.as_object()?;
+        let value = KEY_ARGUMENTS
+            .iter()
+            .find_map(|key| args.get(*key)?.as_str())
+            .or_else(|| args.values().find_map(|v| v.as_str()))?;
+
+        let mut value: String = value.lines().next().unwrap_or_default().to_string();
+        if value.chars().count() > MAX_KEY_ARGUMENT_CHARS {
+            value = value.chars().take(MAX_KEY_ARGUMENT_CHARS).collect();
+            value.push('…');
+        }
+        Some(value)
+    }
+
+    /// Arguments as indented JSON, or verbatim if they don't parse
+    pub fn pretty_arguments(&self) -> String {
+        serde_json::from_str::<serde_json::Value>(&self.arguments)
+            .ok()
+            .and_then(|value| serde_json::to_string_pretty(&value).ok())
+            .unwrap_or_else(|| self.arguments.clone())
+    }
+
+    /// Size of the output, e.g. "4.2 KB, 120 lines"
+    pub fn output_size(&self) -> Option<String> {
+        let result = self.result.as_ref()?;
+        let bytes = result.len();
+        let size = if bytes < 1024 {
+            format!("{} B", bytes)
+        } else if bytes < 1024 * 1024 {
+            format!("{:.1} KB", bytes as f64 / 1024.0)
+        } else {
+            format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+        };
+        let lines = result.lines().count();
+        Some(format!("{}, {} line{}", size, lines, if lines == 1 { "" } else { "s" }))
+    }
+
+    pub fn duration(&self) -> String {
+        if self.duration_ms < 1000 {
+            format!("{}ms", self.duration_ms)
+        } else {
+            format!("{:.1}s", self.duration_ms as f64 / 1000.0)
+        }
+    }
+}
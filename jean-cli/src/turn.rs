@@ -0,0 +1,464 @@
+use jean_shared::StreamChunk;
+
+/// Where the turn in progress stands
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TurnState {
+    Idle,
+    /// A request went out and nothing of the response has arrived yet
+    AwaitingModel,
+    /// Response text or reasoning is arriving
+    Streaming,
+    /// Tool calls are queued or running
+    RunningTools,
+    /// An edit waits for the user to approve it
+    AwaitingApproval,
+    /// Cancel was sent; everything up to the server's acknowledgement is
+    /// dropped
+    Cancelling,
+    /// The last turn ended with an error
+    Error(String),
+}
+
+/// What to do with a chunk from the server
+#[derive(Debug, PartialEq, Eq)]
+pub enum ChunkAction {
+    Ignore,
+    AppendText,
+    /// Add to the model's reasoning for the response being streamed
+    AppendReasoning,
+    /// Queue the tool call to run
+    RunTool,
+    RecordUsage,
+    /// The turn is over; with the error it ended with, if any
+    EndTurn(Option<String>),
+}
+
+/// Tracks a turn across the requests it takes. Every request sent, the
+/// prompt and each tool result, gets a response ending in a `done` chunk,
+/// and those can arrive before or after the tool results are sent; the
+/// turn is only over when no response is outstanding and no tool is left,
+/// or as soon as any response ends in an error.
+#[derive(Debug)]
+pub struct Turn {
+    state: TurnState,
+    /// Requests sent whose response hasn't finished
+    outstanding: usize,
+    /// Tool calls received but not answered
+    tools: usize,
+}
+
+impl Default for Turn {
+    fn default() -> Self {
+        Self {
+            state: TurnState::Idle,
+            outstanding: 0,
+            tools: 0,
+        }
+    }
+}
+
+impl Turn {
+    pub fn state(&self) -> &TurnState {
+        &self.state
+    }
+
+    pub fn in_progress(&self) -> bool {
+        !matches!(self.state, TurnState::Idle | TurnState::Error(_))
+    }
+
+    /// There is something for Ctrl-C to cancel: a turn in progress whose
+    /// cancel hasn't been sent yet
+    pub fn cancellable(&self) -> bool {
+        self.in_progress() && self.state != TurnState::Cancelling
+    }
+
+    /// The model's response is still being waited for or streamed, as
+    /// opposed to tools, approval or a cancel holding the turn up
+    pub fn awaiting_response(&self) -> bool {
+        matches!(self.state, TurnState::AwaitingModel | TurnState::Streaming)
+    }
+
+    /// A prompt went out, starting a turn
+    pub fn submitted(&mut self) {
+        self.outstanding = 1;
+        self.tools = 0;
+        self.state = TurnState::AwaitingModel;
+    }
+
+    /// The request just sent never reached the server
+    pub fn send_failed(&mut self, error: String) {
+        self.outstanding = 0;
+        self.tools = 0;
+        self.state = TurnState::Error(error);
+    }
+
+    pub fn chunk(&mut self, chunk: &StreamChunk) -> ChunkAction {
+        if let StreamChunk::Usage { .. } = chunk {
+            return ChunkAction::RecordUsage;
+        }
+        if self.state == TurnState::Cancelling {
+            if let StreamChunk::Cancelled = chunk {
+                self.reset(TurnState::Idle);
+            }
+            return ChunkAction::Ignore;
+        }
+        if !self.in_progress() {
+            return ChunkAction::Ignore;
+        }
+
+        match chunk {
+            StreamChunk::Text { delta, done: false } => {
+                if self.state == TurnState::AwaitingModel && !delta.is_empty() {
+                    self.state = TurnState::Streaming;
+                }
+                ChunkAction::AppendText
+            }
+            StreamChunk::Reasoning { .. } => {
+                if self.state == TurnState::AwaitingModel {
+                    self.state = TurnState::Streaming;
+                }
+                ChunkAction::AppendReasoning
+            }
+            StreamChunk::Text { done: true, .. } => {
+                self.outstanding = self.outstanding.saturating_sub(1);
+                if self.tools > 0 || self.outstanding > 0 {
+                    // The response that asked for tools, or one already
+                    // superseded by a tool result sent before it finished
+                    if self.tools == 0 {
+                        self.state = TurnState::AwaitingModel;
+                    }
+                    return ChunkAction::Ignore;
+                }
+                self.reset(TurnState::Idle);
+                ChunkAction::EndTurn(None)
+            }
+            StreamChunk::Error { message } => {
+                // Whatever else is outstanding, the turn can't carry on
+                self.reset(TurnState::Error(message.clone()));
+                ChunkAction::EndTurn(Some(message.clone()))
+            }
+            StreamChunk::ToolCall { .. } => {
+                self.tools += 1;
+                if self.state != TurnState::AwaitingApproval {
+                    self.state = TurnState::RunningTools;
+                }
+                ChunkAction::RunTool
+            }
+            StreamChunk::ToolResult { .. } | StreamChunk::Cancelled | StreamChunk::Usage { .. } => {
+                ChunkAction::Ignore
+            }
+        }
+    }
+
+    /// A tool call is shown for approval before it runs
+    pub fn approval_requested(&mut self) {
+        self.state = TurnState::AwaitingApproval;
+    }
+
+    /// The user answered the approval prompt
+    pub fn approval_resolved(&mut self) {
+        if self.state == TurnState::AwaitingApproval {
+            self.state = TurnState::RunningTools;
+        }
+    }
+
+    /// A tool's result was sent, which asks for another response
+    pub fn tool_answered(&mut self) {
+        self.tools = self.tools.saturating_sub(1);
+        self.outstanding += 1;
+        if self.tools == 0 {
+            self.state = TurnState::AwaitingModel;
+        }
+    }
+
+    /// Cancel was sent to the server
+    pub fn cancel(&mut self) {
+        self.reset(TurnState::Cancelling);
+    }
+
+    /// The connection dropped; a new one won't carry on with this turn
+    pub fn disconnected(&mut self) {
+        match self.state {
+            TurnState::Cancelling => self.reset(TurnState::Idle),
+            _ if self.in_progress() => self.reset(TurnState::Error("Connection lost".to_string())),
+            _ => {}
+        }
+    }
+
+    fn reset(&mut self, state: TurnState) {
+        self.outstanding = 0;
+        self.tools = 0;
+        self.state = state;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(delta: &str) -> StreamChunk {
+        StreamChunk::Text { delta: delta.to_string(), done: false }
+    }
+
+    fn done() -> StreamChunk {
+        StreamChunk::Text { delta: String::new(), done: true }
+    }
+
+    fn tool_call(id: &str) -> StreamChunk {
+        StreamChunk::ToolCall {
+            id: id.to_string(),
+            name: "read_file".to_string(),
+            arguments: "{}".to_string(),
+        }
+    }
+
+    fn reasoning(delta: &str) -> StreamChunk {
+        StreamChunk::Reasoning { delta: delta.to_string() }
+    }
+
+    fn usage() -> StreamChunk {
+        StreamChunk::Usage {
+            model: "gpt-5-mini".to_string(),
+            prompt_tokens: 10,
+            completion_tokens: 5,
+        }
+    }
+
+    /// Feed chunks in order, checking the action and resulting state of each
+    fn script(turn: &mut Turn, steps: &[(StreamChunk, ChunkAction, TurnState)]) {
+        for (i, (chunk, action, state)) in steps.iter().enumerate() {
+            assert_eq!(turn.chunk(chunk), *action, "action for step {} ({:?})", i, chunk);
+            assert_eq!(turn.state(), state, "state after step {} ({:?})", i, chunk);
+        }
+    }
+
+    #[test]
+    fn plain_response_streams_then_ends() {
+        let mut turn = Turn::default();
+        turn.submitted();
+        assert_eq!(turn.state(), &TurnState::AwaitingModel);
+        script(
+            &mut turn,
+            &[
+                (text(""), ChunkAction::AppendText, TurnState::AwaitingModel),
+                (text("Hello"), ChunkAction::AppendText, TurnState::Streaming),
+                (text(" there"), ChunkAction::AppendText, TurnState::Streaming),
+                (usage(), ChunkAction::RecordUsage, TurnState::Streaming),
+                (done(), ChunkAction::EndTurn(None), TurnState::Idle),
+            ],
+        );
+        assert!(!turn.in_progress());
+    }
+
+    #[test]
+    fn a_turn_being_cancelled_cannot_be_cancelled_again() {
+        let mut turn = Turn::default();
+        assert!(!turn.cancellable());
+        turn.submitted();
+        assert!(turn.cancellable());
+        turn.cancel();
+        // Still in progress until the server acknowledges, but a second
+        // Ctrl-C should quit rather than cancel again
+        assert!(turn.in_progress());
+        assert!(!turn.cancellable());
+        script(&mut turn, &[(StreamChunk::Cancelled, ChunkAction::Ignore, TurnState::Idle)]);
+        assert!(!turn.cancellable());
+    }
+
+    #[test]
+    fn reasoning_streams_before_the_answer() {
+        let mut turn = Turn::default();
+        turn.submitted();
+        script(
+            &mut turn,
+            &[
+                (reasoning("The user wants"), ChunkAction::AppendReasoning, TurnState::Streaming),
+                (reasoning(" a greeting"), ChunkAction::AppendReasoning, TurnState::Streaming),
+                (text("Hello"), ChunkAction::AppendText, TurnState::Streaming),
+                (done(), ChunkAction::EndTurn(None), TurnState::Idle),
+            ],
+        );
+    }
+
+    #[test]
+    fn tool_result_sent_before_the_response_finishes() {
+        let mut turn = Turn::default();
+        turn.submitted();
+        script(&mut turn, &[(tool_call("a"), ChunkAction::RunTool, TurnState::RunningTools)]);
+        turn.tool_answered();
+        assert_eq!(turn.state(), &TurnState::AwaitingModel);
+        script(
+            &mut turn,
+            &[
+                // Ends the response that asked for the tool, not the turn
+                (done(), ChunkAction::Ignore, TurnState::AwaitingModel),
+                (text("Read it"), ChunkAction::AppendText, TurnState::Streaming),
+                (done(), ChunkAction::EndTurn(None), TurnState::Idle),
+            ],
+        );
+    }
+
+    #[test]
+    fn tool_result_sent_after_the_response_finishes() {
+        let mut turn = Turn::default();
+        turn.submitted();
+        script(
+            &mut turn,
+            &[
+                (text("Let me look"), ChunkAction::AppendText, TurnState::Streaming),
+                (tool_call("a"), ChunkAction::RunTool, TurnState::RunningTools),
+                (done(), ChunkAction::Ignore, TurnState::RunningTools),
+            ],
+        );
+        turn.tool_answered();
+        script(
+            &mut turn,
+            &[
+                (text("Done"), ChunkAction::AppendText, TurnState::Streaming),
+                (done(), ChunkAction::EndTurn(None), TurnState::Idle),
+            ],
+        );
+    }
+
+    #[test]
+    fn several_tool_calls_in_one_response() {
+        let mut turn = Turn::default();
+        turn.submitted();
+        script(
+            &mut turn,
+            &[
+                (tool_call("a"), ChunkAction::RunTool, TurnState::RunningTools),
+                (tool_call("b"), ChunkAction::RunTool, TurnState::RunningTools),
+                (done(), ChunkAction::Ignore, TurnState::RunningTools),
+            ],
+        );
+        turn.tool_answered();
+        assert_eq!(turn.state(), &TurnState::RunningTools);
+        // The response to the first result finishes while the second runs
+        script(&mut turn, &[(done(), ChunkAction::Ignore, TurnState::RunningTools)]);
+        turn.tool_answered();
+        script(
+            &mut turn,
+            &[
+                (text("Both"), ChunkAction::AppendText, TurnState::Streaming),
+                (done(), ChunkAction::EndTurn(None), TurnState::Idle),
+            ],
+        );
+    }
+
+    #[test]
+    fn approval_holds_the_turn_until_answered() {
+        let mut turn = Turn::default();
+        turn.submitted();
+        script(&mut turn, &[(tool_call("edit"), ChunkAction::RunTool, TurnState::RunningTools)]);
+        turn.approval_requested();
+        script(&mut turn, &[(done(), ChunkAction::Ignore, TurnState::AwaitingApproval)]);
+        assert!(turn.in_progress());
+
+        turn.approval_resolved();
+        assert_eq!(turn.state(), &TurnState::RunningTools);
+        turn.tool_answered();
+        script(
+            &mut turn,
+            &[
+                (text("Edited"), ChunkAction::AppendText, TurnState::Streaming),
+                (done(), ChunkAction::EndTurn(None), TurnState::Idle),
+            ],
+        );
+    }
+
+    #[test]
+    fn cancel_drops_everything_until_acknowledged() {
+        let mut turn = Turn::default();
+        turn.submitted();
+        script(&mut turn, &[(text("Partial"), ChunkAction::AppendText, TurnState::Streaming)]);
+        turn.cancel();
+        script(
+            &mut turn,
+            &[
+                (text(" more"), ChunkAction::Ignore, TurnState::Cancelling),
+                (tool_call("a"), ChunkAction::Ignore, TurnState::Cancelling),
+                (usage(), ChunkAction::RecordUsage, TurnState::Cancelling),
+                (done(), ChunkAction::Ignore, TurnState::Cancelling),
+                (StreamChunk::Cancelled, ChunkAction::Ignore, TurnState::Idle),
+                // A late acknowledgement with nothing cancelled
+                (StreamChunk::Cancelled, ChunkAction::Ignore, TurnState::Idle),
+            ],
+        );
+    }
+
+    fn error(message: &str) -> StreamChunk {
+        StreamChunk::Error { message: message.to_string() }
+    }
+
+    #[test]
+    fn error_chunk_ends_the_turn_in_error() {
+        let mut turn = Turn::default();
+        turn.submitted();
+        let message = "Anthropic API Error: Overloaded";
+        script(
+            &mut turn,
+            &[(
+                error(message),
+                ChunkAction::EndTurn(Some(message.to_string())),
+                TurnState::Error(message.to_string()),
+            )],
+        );
+        assert!(!turn.in_progress());
+
+        turn.submitted();
+        assert_eq!(turn.state(), &TurnState::AwaitingModel);
+    }
+
+    #[test]
+    fn error_while_tools_run_ends_the_turn() {
+        let mut turn = Turn::default();
+        turn.submitted();
+        script(&mut turn, &[(tool_call("a"), ChunkAction::RunTool, TurnState::RunningTools)]);
+        turn.tool_answered();
+        script(&mut turn, &[(tool_call("b"), ChunkAction::RunTool, TurnState::RunningTools)]);
+        let message = "OpenAI API Error: The server had an error processing your request.";
+        script(
+            &mut turn,
+            &[
+                (
+                    error(message),
+                    ChunkAction::EndTurn(Some(message.to_string())),
+                    TurnState::Error(message.to_string()),
+                ),
+                // The rest of the failed turn is dropped
+                (done(), ChunkAction::Ignore, TurnState::Error(message.to_string())),
+            ],
+        );
+    }
+
+    #[test]
+    fn disconnect_ends_a_turn_in_progress() {
+        let mut turn = Turn::default();
+        turn.disconnected();
+        assert_eq!(turn.state(), &TurnState::Idle);
+
+        turn.submitted();
+        turn.chunk(&text("Hal"));
+        turn.disconnected();
+        assert_eq!(turn.state(), &TurnState::Error("Connection lost".to_string()));
+
+        turn.submitted();
+        turn.cancel();
+        turn.disconnected();
+        assert_eq!(turn.state(), &TurnState::Idle);
+    }
+
+    #[test]
+    fn chunks_outside_a_turn_are_ignored() {
+        let mut turn = Turn::default();
+        script(
+            &mut turn,
+            &[
+                (text("stray"), ChunkAction::Ignore, TurnState::Idle),
+                (done(), ChunkAction::Ignore, TurnState::Idle),
+                (tool_call("a"), ChunkAction::Ignore, TurnState::Idle),
+            ],
+        );
+    }
+}
@@ -0,0 +1,643 @@
+use crate::app::{App, CopySelection};
+use crate::client::ConnectionStatus;
+use crate::diff::DiffLayout;
+use crate::highlight;
+use crate::history::HistorySearch;
+use crate::markdown;
+use crate::mentions::{self, MentionPicker};
+use crate::search::TranscriptSearch;
+use crate::theme;
+use crate::transcript::{Thinking, ToolRun, TranscriptItem};
+use crate::turn::TurnState;
+use crate::vim::VimMode;
+use jean_shared::MessageRole;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
+    Frame,
+};
+use std::collections::VecDeque;
+use unicode_width::UnicodeWidthStr;
+
+/// The input box grows with its content up to this many rows
+const MAX_INPUT_ROWS: usize = 10;
+
+/// Draw the whole app: chat, input, status bar and whatever is open over them
+pub fn ui(f: &mut Frame, app: &mut App) {
+    let area = f.area();
+
+    // The input box grows with its content, up to MAX_INPUT_ROWS
+    let input_width = area.width.saturating_sub(2) as usize;
+    app.input.set_wrap_width(input_width);
+    let input_rows = app
+        .input
+        .layout(input_width)
+        .rows
+        .len()
+        .clamp(1, MAX_INPUT_ROWS);
+
+    // Split into main area, input area and status bar
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(1),                           // Chat area takes remaining space
+            Constraint::Length(input_rows as u16 + 2),    // Input rows plus borders
+            Constraint::Length(1),                        // Status bar
+        ])
+        .split(area);
+
+    // Render chat messages
+    render_chat(f, app, chunks[0]);
+    
+    // Render input box
+    render_input(f, app, chunks[1]);
+
+    render_status_bar(f, app, chunks[2]);
+
+    if let Some(viewer) = &mut app.diff_viewer {
+        viewer.render(f, chunks[0]);
+    }
+
+    if let Some(picker) = &app.mention_picker {
+        render_mention_picker(f, picker, chunks[1]);
+    } else {
+        render_command_suggestions(f, app, chunks[1]);
+    }
+}
+
+fn render_chat(f: &mut Frame, app: &mut App, area: Rect) {
+    let mut tail = if app.turn.awaiting_response() || !app.streaming_text.is_empty() {
+        streaming_lines(&app.streaming_text, &app.streaming_reasoning)
+    } else {
+        Vec::new()
+    };
+    tail.extend(queued_lines(&app.queued));
+    let transcript = &app.transcript;
+    let selected_tool = app.selected_tool;
+    let search = &app.search;
+    let copy_selection = &app.copy_selection;
+    app.chat_view.render(
+        f,
+        area,
+        transcript.len(),
+        |index| item_lines(&transcript[index], selected_tool == Some(index)),
+        |index, line, rendered| {
+            if let Some(search) = search {
+                search.highlight(index, line, rendered);
+            }
+            if let Some(selection) = copy_selection
+                && selection.item == index
+                && selection.lines.as_ref().is_none_or(|lines| lines.contains(&line))
+            {
+                *rendered = std::mem::take(rendered).patch_style(theme::current().selection);
+            }
+        },
+        tail,
+    );
+}
+
+/// One row below the input: connection, turn in progress, model, context
+/// use, cost, tool approval, git branch and working directory
+fn render_status_bar(f: &mut Frame, app: &App, area: Rect) {
+    let theme = theme::current();
+    let dim = theme.muted;
+    let separator = || Span::styled(" │ ", dim);
+
+    let (status_text, status_style) = match &app.connection_status {
+        ConnectionStatus::Connected => ("● Connected".to_string(), theme.success),
+        ConnectionStatus::Connecting => ("● Connecting...".to_string(), theme.attention),
+        ConnectionStatus::Disconnected => ("● Disconnected".to_string(), theme.error),
+        ConnectionStatus::Error(e) => (format!("● Error: {}", e), theme.error),
+    };
+    let mut spans = vec![Span::styled(status_text, status_style)];
+
+    if app.quit_armed_at.is_some() {
+        spans.push(separator());
+        spans.push(Span::styled("Press again to quit", theme.error));
+    }
+    if let Some(vim) = &app.vim {
+        let (mode, style) = match vim.mode {
+            VimMode::Normal => ("NORMAL", theme.accent),
+            VimMode::Insert => ("INSERT", theme.success),
+        };
+        spans.push(separator());
+        spans.push(Span::styled(mode, style.add_modifier(Modifier::BOLD)));
+    }
+
+    let activity = match app.turn.state() {
+        TurnState::Idle | TurnState::Error(_) => None,
+        TurnState::AwaitingModel => Some("waiting for the model".to_string()),
+        TurnState::Streaming if app.streaming_text.is_empty() && !app.streaming_reasoning.is_empty() => {
+            Some("thinking".to_string())
+        }
+        TurnState::Streaming => Some("responding".to_string()),
+        TurnState::RunningTools => {
+            let running = app.transcript.iter().rev().find_map(|item| match item {
+                TranscriptItem::Tool(run) if run.result.is_none() => Some(run.name.as_str()),
+                _ => None,
+            });
+            Some(format!("running {}", running.unwrap_or("tools")))
+        }
+        TurnState::AwaitingApproval => Some("awaiting approval".to_string()),
+        TurnState::Cancelling => Some("cancelling".to_string()),
+    };
+    if let Some(activity) = activity {
+        let elapsed = app.turn_started.map(|started| format!(" {}s", started.elapsed().as_secs()));
+        spans.push(separator());
+        spans.push(Span::styled(
+            format!("{}{}", activity, elapsed.unwrap_or_default()),
+            theme.attention,
+        ));
+    }
+
+    let model = match (&app.model, &app.usage.last_model) {
+        (Some(model), _) | (None, Some(model)) => model.as_str(),
+        (None, None) => "default model",
+    };
+    spans.push(separator());
+    spans.push(Span::raw(model.to_string()));
+
+    if let Some(percent) = app.usage.context_percent() {
+        let style = match percent {
+            p if p >= 90.0 => theme.error,
+            p if p >= 70.0 => theme.attention,
+            _ => Style::default(),
+        };
+        spans.push(separator());
+        spans.push(Span::styled(format!("ctx {:.0}%", percent), style));
+    }
+    if !app.queued.is_empty() {
+        spans.push(separator());
+        spans.push(Span::styled(format!("{} queued", app.queued.len()), theme.accent));
+    }
+    if app.usage.requests > 0 {
+        spans.push(separator());
+        spans.push(Span::raw(format!("${:.4}", app.usage.cost)));
+    }
+
+    spans.push(separator());
+    spans.push(Span::raw(if app.approve_edits { "tools: ask before edits" } else { "tools: auto" }));
+
+    if let Some(branch) = &app.workspace.branch {
+        spans.push(separator());
+        spans.push(Span::styled(format!("⎇ {}", branch), theme.accent));
+    }
+    spans.push(separator());
+    spans.push(Span::styled(app.workspace.cwd.clone(), dim));
+
+    f.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+/// Lines for one transcript entry, followed by a blank separator line
+pub fn item_lines(item: &TranscriptItem, selected: bool) -> Vec<Line<'static>> {
+    let msg = match item {
+        TranscriptItem::Message(msg) => msg,
+        TranscriptItem::Tool(run) => return tool_run_lines(run, selected),
+        TranscriptItem::Thinking(thinking) => return thinking_lines(thinking, selected),
+    };
+    let mut lines = Vec::new();
+    let theme = theme::current();
+    let style = match msg.role {
+        MessageRole::System => theme.system,
+        MessageRole::User => theme.user,
+        MessageRole::Assistant => theme.assistant,
+        MessageRole::Tool => theme.tool,
+    };
+
+    let prefix = match msg.role {
+        MessageRole::System => "System",
+        MessageRole::User => "You",
+        MessageRole::Assistant => "Assistant",
+        MessageRole::Tool => "Tool",
+    };
+
+    // Add role prefix
+    lines.push(Line::from(Span::styled(
+        format!("{}:", prefix),
+        style.add_modifier(Modifier::BOLD),
+    )));
+
+    // Add message content lines; the model writes markdown
+    if msg.role == MessageRole::Assistant {
+        lines.extend(markdown::render(&msg.content, style));
+    } else if msg.role == MessageRole::User {
+        // Attached file contents are only listed, not shown
+        let (prompt, attached) = mentions::split_attachments(&msg.content);
+        for line in prompt.lines() {
+            lines.push(Line::from(Span::styled(line.to_string(), style)));
+        }
+        for path in attached {
+            lines.push(Line::from(Span::styled(
+                format!("📎 {}", path),
+                theme.muted,
+            )));
+        }
+    } else {
+        for line in msg.content.lines() {
+            lines.push(Line::from(Span::styled(line.to_string(), style)));
+        }
+    }
+
+    // Add spacing after message
+    lines.push(Line::from(""));
+    lines
+}
+
+/// The response being streamed, re-rendered as it grows. Reasoning is
+/// shown in full until the answer starts, then folded to one line.
+fn streaming_lines(streaming: &str, reasoning: &str) -> Vec<Line<'static>> {
+    let theme = theme::current();
+    let style = theme.assistant;
+    let thinking_style = theme.muted.add_modifier(Modifier::ITALIC);
+    let mut lines = vec![Line::from(Span::styled(
+        "Assistant:",
+        style.add_modifier(Modifier::BOLD),
+    ))];
+    let reasoning = reasoning.trim_start();
+    if !reasoning.is_empty() && streaming.is_empty() {
+        lines.push(Line::from(Span::styled("▾ thinking", thinking_style)));
+        let mut thought: Vec<Line> = reasoning
+            .lines()
+            .map(|line| Line::from(vec![Span::raw("    "), Span::styled(line.to_string(), thinking_style)]))
+            .collect();
+        match thought.last_mut() {
+            Some(last) => last.push_span(Span::styled("▌", thinking_style)),
+            None => thought.push(Line::from(Span::styled("▌", thinking_style))),
+        }
+        lines.extend(thought);
+        lines.push(Line::from(""));
+        return lines;
+    }
+    if !reasoning.is_empty() {
+        lines.push(Line::from(Span::styled(
+            format!("▸ thinking  {}", line_count(reasoning.trim_end())),
+            thinking_style,
+        )));
+    }
+    if streaming.is_empty() {
+        lines.push(Line::from(Span::styled("●●●", style)));
+    } else {
+        // The cursor shows it's still streaming
+        let mut rendered = markdown::render(streaming, style);
+        match rendered.last_mut() {
+            Some(last) => last.push_span(Span::styled("▌", style)),
+            None => rendered.push(Line::from(Span::styled("▌", style))),
+        }
+        lines.extend(rendered);
+    }
+    lines.push(Line::from(""));
+    lines
+}
+
+/// Prompts waiting for the turn to end, below the response
+fn queued_lines(queued: &VecDeque<String>) -> Vec<Line<'static>> {
+    if queued.is_empty() {
+        return Vec::new();
+    }
+    let dim = theme::current().muted;
+    let mut lines = vec![Line::from(Span::styled(
+        "Queued (↑ on an empty prompt to edit):",
+        dim.add_modifier(Modifier::BOLD),
+    ))];
+    for prompt in queued {
+        for (i, line) in prompt.lines().enumerate() {
+            let bullet = if i == 0 { "  • " } else { "    " };
+            lines.push(Line::from(Span::styled(format!("{}{}", bullet, line), dim)));
+        }
+    }
+    lines.push(Line::from(""));
+    lines
+}
+
+/// The model's reasoning, dimmed: one line until expanded with Ctrl-O
+pub fn thinking_lines(thinking: &Thinking, selected: bool) -> Vec<Line<'static>> {
+    let style = theme::current().muted.add_modifier(Modifier::ITALIC);
+    let mut summary = vec![Span::styled(
+        format!(
+            "{} thinking  {}",
+            if thinking.expanded { "▾" } else { "▸" },
+            line_count(&thinking.text)
+        ),
+        style,
+    )];
+    if selected {
+        summary.push(Span::styled("  (Ctrl-O to expand/collapse)", theme::current().muted));
+    }
+
+    let mut summary = Line::from(summary);
+    if selected {
+        summary = summary.patch_style(Style::default().add_modifier(Modifier::REVERSED));
+    }
+    let mut lines = vec![summary];
+    if thinking.expanded {
+        for line in thinking.text.lines() {
+            lines.push(Line::from(vec![Span::raw("    "), Span::styled(line.to_string(), style)]));
+        }
+    }
+    lines.push(Line::from(""));
+    lines
+}
+
+fn line_count(text: &str) -> String {
+    let lines = text.lines().count();
+    format!("{} line{}", lines, if lines == 1 { "" } else { "s" })
+}
+
+/// A tool run as a one-line summary, followed by the arguments and output
+/// when expanded. File contents read by `read_file` are highlighted.
+pub fn tool_run_lines(run: &ToolRun, selected: bool) -> Vec<Line<'static>> {
+    let theme = theme::current();
+    let dim = theme.muted;
+    let mut summary = vec![
+        Span::styled(if run.expanded { "▾ " } else { "▸ " }, dim),
+        Span::styled(run.name.clone(), theme.tool.add_modifier(Modifier::BOLD)),
+    ];
+    if let Some(argument) = run.key_argument() {
+        summary.push(Span::raw(format!(" {}", argument)));
+    }
+    match &run.result {
+        None => summary.push(Span::styled("  running…", theme.attention)),
+        Some(_) => {
+            let (mark, style) = if run.failed() { ("✗", theme.error) } else { ("✓", theme.success) };
+            summary.push(Span::styled(format!("  {}", mark), style));
+            if let Some(change) = &run.change {
+                let (added, removed) = change.stats();
+                summary.push(Span::styled(format!("  +{}", added), theme.success));
+                summary.push(Span::styled(format!(" -{}", removed), theme.error));
+            }
+            summary.push(Span::styled(
+                format!("  {}  {}", run.duration(), run.output_size().unwrap_or_default()),
+                dim,
+            ));
+        }
+    }
+    if selected {
+        summary.push(Span::styled("  (Ctrl-O to expand/collapse)", dim));
+    }
+
+    let mut summary = Line::from(summary);
+    if selected {
+        summary = summary.patch_style(Style::default().add_modifier(Modifier::REVERSED));
+    }
+    let mut lines = vec![summary];
+
+    if run.expanded {
+        let indent = || Span::raw("    ");
+        // Edits read better as the diff they made than as their arguments
+        if let Some(change) = &run.change {
+            lines.push(Line::from(Span::styled("  changes:", dim)));
+            for line in change.render(DiffLayout::Unified) {
+                let mut spans = vec![indent()];
+                spans.extend(line.spans);
+                lines.push(Line::from(spans));
+            }
+        } else {
+            lines.push(Line::from(Span::styled("  arguments:", dim)));
+            for line in run.pretty_arguments().lines() {
+                lines.push(Line::from(vec![indent(), Span::raw(line.to_string())]));
+            }
+        }
+
+        if let Some(result) = &run.result {
+            lines.push(Line::from(Span::styled("  output:", dim)));
+            let highlighted = match run.key_argument() {
+                Some(path) if run.name == "read_file" && !run.failed() => {
+                    highlight::highlight_file(result, &path)
+                }
+                _ => None,
+            };
+            match highlighted {
+                Some(highlighted) => {
+                    for spans in highlighted.iter() {
+                        let mut line = vec![indent()];
+                        line.extend(spans.iter().cloned());
+                        lines.push(Line::from(line));
+                    }
+                }
+                None => {
+                    for line in result.lines() {
+                        lines.push(Line::from(vec![indent(), Span::raw(line.to_string())]));
+                    }
+                }
+            }
+        }
+    }
+
+    lines.push(Line::from(""));
+    lines
+}
+
+fn render_input(f: &mut Frame, app: &App, area: Rect) {
+    if let Some(search) = &app.history_search {
+        render_history_search(f, app, search, area);
+        return;
+    }
+    if let Some(search) = &app.search {
+        render_transcript_search(f, search, area);
+        return;
+    }
+    if let Some(selection) = &app.copy_selection {
+        render_copy_mode(f, app, selection, area);
+        return;
+    }
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Input (Enter send, Shift/Alt-Enter newline, Ctrl-R history, Ctrl-F search, Ctrl-Y copy, Ctrl-T mouse, Ctrl-C cancel, /help keys)")
+        .border_style(theme::current().border);
+
+    if app.input.is_empty() {
+        let placeholder = Paragraph::new("Type your message... (/help lists commands)")
+            .style(theme::current().muted)
+            .block(block);
+        f.render_widget(placeholder, area);
+        f.set_cursor_position((area.x + 1, area.y + 1));
+        return;
+    }
+
+    let inner_width = area.width.saturating_sub(2) as usize;
+    let visible_rows = area.height.saturating_sub(2) as usize;
+    let layout = app.input.layout(inner_width);
+
+    // Keep the cursor row in view when the text is taller than the box
+    let first_row = layout.cursor.0.saturating_sub(visible_rows.saturating_sub(1));
+    let mentions = mentions::mention_ranges(app.input.text());
+    let lines: Vec<Line> = layout
+        .rows
+        .iter()
+        .zip(&layout.offsets)
+        .skip(first_row)
+        .take(visible_rows)
+        .map(|(row, &offset)| highlight_mentions(row, offset, &mentions))
+        .collect();
+
+    f.render_widget(Paragraph::new(lines).block(block), area);
+
+    let cursor_x = area.x + 1 + layout.cursor.1 as u16;
+    let cursor_y = area.y + 1 + (layout.cursor.0 - first_row) as u16;
+    f.set_cursor_position((cursor_x, cursor_y));
+}
+
+fn render_history_search(f: &mut Frame, app: &App, search: &HistorySearch, area: Rect) {
+    let theme = theme::current();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("History search (Ctrl-R older, Enter accept, Esc cancel)")
+        .border_style(theme.attention);
+
+    let query = format!("(reverse-i-search)`{}", search.query);
+    let (matched, style) = match search.matched.and_then(|i| app.history.get(i)) {
+        Some(entry) => (entry, Style::default()),
+        None if search.query.is_empty() => ("", Style::default()),
+        None => ("no match", theme.muted),
+    };
+
+    let mut lines = vec![Line::from(vec![
+        Span::styled(format!("{}': ", query), theme.attention),
+        Span::styled(matched.lines().next().unwrap_or_default().to_string(), style),
+    ])];
+    lines.extend(matched.lines().skip(1).map(|line| Line::styled(line.to_string(), style)));
+
+    f.render_widget(
+        Paragraph::new(lines).block(block).wrap(Wrap { trim: false }),
+        area,
+    );
+
+    // Cursor sits after the query, where typing goes
+    let cursor_x = (area.x + 1 + query.width() as u16).min(area.right().saturating_sub(2));
+    f.set_cursor_position((cursor_x, area.y + 1));
+}
+
+fn render_copy_mode(f: &mut Frame, app: &App, selection: &CopySelection, area: Rect) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Copy (Up/Down select, Tab code blocks, Enter copy, Esc cancel)")
+        .border_style(theme::current().attention);
+
+    let mut target = format!("Item {} of {}", selection.item + 1, app.transcript.len());
+    let blocks = app.code_block_count(selection.item);
+    match selection.block {
+        Some(block) => target.push_str(&format!(", code block {} of {}", block + 1, blocks)),
+        None if blocks > 0 => target.push_str(&format!(" ({} code block(s), Tab to select one)", blocks)),
+        None => {}
+    }
+    f.render_widget(Paragraph::new(target).block(block), area);
+}
+
+fn render_transcript_search(f: &mut Frame, search: &TranscriptSearch, area: Rect) {
+    let theme = theme::current();
+    let title = if search.editing {
+        "Search transcript (Enter accept, Up/Down move, Ctrl-R regex, Esc close)"
+    } else {
+        "Search transcript (n older, N newer, Ctrl-F edit, Esc close)"
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .border_style(theme.attention);
+
+    let prompt = if search.regex { "regex/" } else { "/" };
+    let status_style = if search.error.is_some() || (search.current.is_none() && !search.query.is_empty()) {
+        theme.error
+    } else {
+        theme.muted
+    };
+    let line = Line::from(vec![
+        Span::styled(prompt, theme.attention),
+        Span::raw(search.query.clone()),
+        Span::styled(format!("  {}", search.status()), status_style),
+    ]);
+    f.render_widget(Paragraph::new(line).block(block), area);
+
+    if search.editing {
+        let cursor_x = area.x + 1 + (prompt.width() + search.query.width()) as u16;
+        f.set_cursor_position((cursor_x.min(area.right().saturating_sub(2)), area.y + 1));
+    }
+}
+
+/// Style the parts of an input row that belong to an `@mention`. `offset`
+/// is where the row starts in the input text, `mentions` are byte ranges
+/// in the text, so mentions wrapped across rows are styled on both.
+fn highlight_mentions<'a>(row: &'a str, offset: usize, mentions: &[(usize, usize)]) -> Line<'a> {
+    let mention_style = theme::current().accent.add_modifier(Modifier::UNDERLINED);
+    let mut spans = Vec::new();
+    let mut pos = 0;
+    for &(start, end) in mentions {
+        let start = start.saturating_sub(offset).min(row.len());
+        let end = end.saturating_sub(offset).min(row.len());
+        if end <= pos {
+            continue;
+        }
+        let start = start.max(pos);
+        if start > pos {
+            spans.push(Span::raw(&row[pos..start]));
+        }
+        spans.push(Span::styled(&row[start..end], mention_style));
+        pos = end;
+    }
+    if pos < row.len() {
+        spans.push(Span::raw(&row[pos..]));
+    }
+    Line::from(spans)
+}
+
+/// Suggestions for the `@mention` being typed, drawn just above the input
+fn render_mention_picker(f: &mut Frame, picker: &MentionPicker, input_area: Rect) {
+    let items: Vec<ListItem> = picker
+        .matches
+        .iter()
+        .enumerate()
+        .map(|(i, path)| {
+            let style = if i == picker.selected {
+                theme::current().popup_selected
+            } else {
+                Style::default()
+            };
+            ListItem::new(path.as_str()).style(style)
+        })
+        .collect();
+
+    render_popup(f, "Files (↑↓ select, Tab/Enter insert, Esc close)", items, input_area);
+}
+
+/// Matching commands with their descriptions while a `/command` name is
+/// being typed
+fn render_command_suggestions(f: &mut Frame, app: &App, input_area: Rect) {
+    let suggestions = app.commands.completions(app.input.text());
+    let width = suggestions.iter().map(|(usage, _)| usage.width()).max().unwrap_or(0);
+    let items: Vec<ListItem> = suggestions
+        .into_iter()
+        .map(|(usage, description)| {
+            let padding = " ".repeat(width - usage.width() + 2);
+            ListItem::new(Line::from(vec![
+                Span::styled(usage, theme::current().accent),
+                Span::raw(padding),
+                Span::styled(description, theme::current().muted),
+            ]))
+        })
+        .collect();
+
+    render_popup(f, "Commands (Tab to complete)", items, input_area);
+}
+
+/// A bordered list drawn over the bottom of the chat, just above the input
+fn render_popup(f: &mut Frame, title: &str, items: Vec<ListItem>, input_area: Rect) {
+    if items.is_empty() {
+        return;
+    }
+
+    let height = (items.len() as u16 + 2).min(input_area.y);
+    let width = input_area.width.min(90);
+    let area = Rect::new(input_area.x, input_area.y - height, width, height);
+
+    let list = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title.to_string())
+            .border_style(theme::current().accent),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(list, area);
+}
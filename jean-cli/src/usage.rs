@@ -0,0 +1,142 @@
+/// Published prices in USD per million (prompt, completion) tokens. Dated
+/// model names like `gpt-4o-2024-08-06` match by the longest prefix.
+const PRICES: &[(&str, f64, f64)] = &[
+    ("gpt-5-nano", 0.05, 0.40),
+    ("gpt-5-mini", 0.25, 2.00),
+    ("gpt-5", 1.25, 10.00),
+    ("gpt-4.1-nano", 0.10, 0.40),
+    ("gpt-4.1-mini", 0.40, 1.60),
+    ("gpt-4.1", 2.00, 8.00),
+    ("gpt-4o-mini", 0.15, 0.60),
+    ("gpt-4o", 2.50, 10.00),
+    ("gpt-4-turbo", 10.00, 30.00),
+    ("gpt-3.5-turbo", 0.50, 1.50),
+    ("o4-mini", 1.10, 4.40),
+    ("o3-mini", 1.10, 4.40),
+    ("o3", 2.00, 8.00),
+    ("o1-mini", 1.10, 4.40),
+    ("o1", 15.00, 60.00),
+    ("claude-opus-4-5", 5.00, 25.00),
+    ("claude-opus-4", 15.00, 75.00),
+    ("claude-sonnet-4", 3.00, 15.00),
+    ("claude-haiku-4-5", 1.00, 5.00),
+    ("claude-3-7-sonnet", 3.00, 15.00),
+    ("claude-3-5-sonnet", 3.00, 15.00),
+    ("claude-3-5-haiku", 0.80, 4.00),
+    ("claude-3-opus", 15.00, 75.00),
+    ("claude-3-haiku", 0.25, 1.25),
+];
+
+/// Context window sizes in tokens, matched like `PRICES`
+const CONTEXT_WINDOWS: &[(&str, u64)] = &[
+    ("gpt-5", 400_000),
+    ("gpt-4.1", 1_047_576),
+    ("gpt-4o", 128_000),
+    ("gpt-4-turbo", 128_000),
+    ("gpt-3.5-turbo", 16_385),
+    ("o4-mini", 200_000),
+    ("o3", 200_000),
+    ("o1-mini", 128_000),
+    ("o1", 200_000),
+    // Every Claude model so far
+    ("claude", 200_000),
+];
+
+/// Tokens used and what they cost over the session
+#[derive(Debug, Default)]
+pub struct UsageTracker {
+    pub requests: u32,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Cost of the requests whose model has a known price
+    pub cost: f64,
+    /// Requests made with models missing from the price table
+    pub unpriced_requests: u32,
+    /// Model named in the latest usage report
+    pub last_model: Option<String>,
+    /// Tokens of the latest request, i.e. how full the context is
+    pub last_request_tokens: u64,
+}
+
+impl UsageTracker {
+    pub fn record(&mut self, model: &str, prompt_tokens: u32, completion_tokens: u32) {
+        self.requests += 1;
+        self.last_model = Some(model.to_string());
+        self.last_request_tokens = u64::from(prompt_tokens) + u64::from(completion_tokens);
+        self.prompt_tokens += u64::from(prompt_tokens);
+        self.completion_tokens += u64::from(completion_tokens);
+        match price(model) {
+            Some((prompt_price, completion_price)) => {
+                self.cost += (f64::from(prompt_tokens) * prompt_price
+                    + f64::from(completion_tokens) * completion_price)
+                    / 1_000_000.0;
+            }
+            None => self.unpriced_requests += 1,
+        }
+    }
+
+    /// Percentage of the model's context window the latest request used
+    pub fn context_percent(&self) -> Option<f64> {
+        let window = context_window(self.last_model.as_deref()?)?;
+        Some(self.last_request_tokens as f64 * 100.0 / window as f64)
+    }
+
+    pub fn summary(&self) -> String {
+        if self.requests == 0 {
+            return "No model requests yet this session".to_string();
+        }
+        let mut summary = format!(
+            "{} request(s): {} prompt + {} completion tokens, ${:.4}",
+            self.requests, self.prompt_tokens, self.completion_tokens, self.cost
+        );
+        if self.unpriced_requests > 0 {
+            summary.push_str(&format!(
+                " (excluding {} request(s) to models with unknown pricing)",
+                self.unpriced_requests
+            ));
+        }
+        summary
+    }
+}
+
+fn price(model: &str) -> Option<(f64, f64)> {
+    PRICES
+        .iter()
+        .filter(|(prefix, _, _)| model.starts_with(prefix))
+        .max_by_key(|(prefix, _, _)| prefix.len())
+        .map(|&(_, prompt, completion)| (prompt, completion))
+}
+
+fn context_window(model: &str) -> Option<u64> {
+    CONTEXT_WINDOWS
+        .iter()
+        .filter(|(prefix, _)| model.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|&(_, window)| window)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dated_models_match_the_longest_prefix() {
+        assert_eq!(price("gpt-5-mini-2025-08-07"), Some((0.25, 2.00)));
+        assert_eq!(price("gpt-5-2025-08-07"), Some((1.25, 10.00)));
+        assert_eq!(price("claude-opus-4-5-20251101"), Some((5.00, 25.00)));
+        assert_eq!(price("claude-opus-4-1-20250805"), Some((15.00, 75.00)));
+        assert_eq!(price("llama-3.3-70b"), None);
+
+        assert_eq!(context_window("gpt-4o-mini-2024-07-18"), Some(128_000));
+        assert_eq!(context_window("claude-sonnet-4-20250514"), Some(200_000));
+    }
+
+    #[test]
+    fn anthropic_usage_is_priced_and_fills_the_context() {
+        let mut usage = UsageTracker::default();
+        usage.record("claude-sonnet-4-20250514", 100_000, 1_000);
+        assert_eq!(usage.unpriced_requests, 0);
+        assert!((usage.cost - 0.315).abs() < 1e-9, "{}", usage.cost);
+        assert_eq!(usage.context_percent(), Some(50.5));
+    }
+}
@@ -0,0 +1,211 @@
+use crate::input::InputEditor;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VimMode {
+    Normal,
+    Insert,
+}
+
+/// What a normal mode key asks of the app beyond editing the input
+#[derive(Debug, PartialEq, Eq)]
+pub enum VimOutcome {
+    Handled,
+    Submit,
+    HistoryPrevious,
+    HistoryNext,
+    Search,
+    /// Not a normal mode key; handle it through the keymap
+    Unhandled,
+}
+
+/// Vim-style modal editing of the prompt. Insert mode types as usual and
+/// Esc switches to normal mode, where keys are motions and commands.
+#[derive(Debug)]
+pub struct Vim {
+    pub mode: VimMode,
+    /// First key of a two-key command such as `dd`
+    pending: Option<char>,
+}
+
+impl Default for Vim {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Vim {
+    pub fn new() -> Self {
+        Self {
+            mode: VimMode::Insert,
+            pending: None,
+        }
+    }
+
+    pub fn enter_normal(&mut self, input: &mut InputEditor) {
+        self.mode = VimMode::Normal;
+        self.pending = None;
+        // As in vim, leaving insert mode steps back onto the last character,
+        // but not onto the line before
+        input.move_left_in_line();
+    }
+
+    pub fn normal_key(&mut self, key: KeyEvent, input: &mut InputEditor) -> VimOutcome {
+        if key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+            self.pending = None;
+            return VimOutcome::Unhandled;
+        }
+
+        if let Some(first) = self.pending.take() {
+            match (first, key.code) {
+                ('d', KeyCode::Char('d')) => input.delete_line(),
+                ('c', KeyCode::Char('c')) => {
+                    input.move_line_start();
+                    input.delete_to_line_end();
+                    self.mode = VimMode::Insert;
+                }
+                ('d', KeyCode::Char('w')) => input.delete_to_next_word_start(),
+                ('d', KeyCode::Char('b')) => input.delete_word_before(),
+                ('d', KeyCode::Char('$')) => input.delete_to_line_end(),
+                ('d', KeyCode::Char('0')) => input.delete_to_line_start(),
+                // Like vim, `cw` changes to the end of the word, as `ce`
+                ('c', KeyCode::Char('w' | 'e')) => {
+                    input.delete_word_after();
+                    self.mode = VimMode::Insert;
+                }
+                _ => {}
+            }
+            return VimOutcome::Handled;
+        }
+
+        match key.code {
+            KeyCode::Enter => return VimOutcome::Submit,
+            // h and l stay on the line; Backspace and space wrap, as in vim
+            KeyCode::Char('h') | KeyCode::Left => input.move_left_in_line(),
+            KeyCode::Char('l') | KeyCode::Right => input.move_right_in_line(),
+            KeyCode::Backspace => input.move_left(),
+            KeyCode::Char(' ') => input.move_right(),
+            KeyCode::Char('w') => input.move_next_word_start(),
+            KeyCode::Char('e') => input.move_word_end(),
+            KeyCode::Char('b') => input.move_word_left(),
+            KeyCode::Char('0' | '^') | KeyCode::Home => input.move_line_start(),
+            KeyCode::Char('$') | KeyCode::End => input.move_line_end(),
+            KeyCode::Char('k') | KeyCode::Up => {
+                if !input.move_up() {
+                    return VimOutcome::HistoryPrevious;
+                }
+            }
+            KeyCode::Char('j') | KeyCode::Down => {
+                if !input.move_down() {
+                    return VimOutcome::HistoryNext;
+                }
+            }
+            KeyCode::Char('/') => return VimOutcome::Search,
+            KeyCode::Char('x') | KeyCode::Delete => input.delete_after(),
+            KeyCode::Char('X') => input.delete_before(),
+            KeyCode::Char('D') => input.delete_to_line_end(),
+            KeyCode::Char('C') => {
+                input.delete_to_line_end();
+                self.mode = VimMode::Insert;
+            }
+            KeyCode::Char(c @ ('d' | 'c')) => self.pending = Some(c),
+            KeyCode::Char('i') => self.mode = VimMode::Insert,
+            KeyCode::Char('a') => {
+                input.move_right();
+                self.mode = VimMode::Insert;
+            }
+            KeyCode::Char('I') => {
+                input.move_line_start();
+                self.mode = VimMode::Insert;
+            }
+            KeyCode::Char('A') => {
+                input.move_line_end();
+                self.mode = VimMode::Insert;
+            }
+            KeyCode::Char('o') => {
+                input.move_line_end();
+                input.newline();
+                self.mode = VimMode::Insert;
+            }
+            KeyCode::Char('O') => {
+                input.move_line_start();
+                input.newline();
+                input.move_left();
+                self.mode = VimMode::Insert;
+            }
+            // Anything else is swallowed rather than typed
+            KeyCode::Char(_) | KeyCode::Esc => {}
+            _ => return VimOutcome::Unhandled,
+        }
+        VimOutcome::Handled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Type `text` in insert mode, press Esc, then `keys` in normal mode
+    fn normal(text: &str, keys: &str) -> (InputEditor, Vim) {
+        let mut input = InputEditor::new();
+        input.insert_str(text);
+        let mut vim = Vim::new();
+        vim.enter_normal(&mut input);
+        for c in keys.chars() {
+            vim.normal_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE), &mut input);
+        }
+        (input, vim)
+    }
+
+    #[test]
+    fn esc_steps_back_but_not_onto_the_previous_line() {
+        let (input, vim) = normal("abc", "");
+        assert_eq!(vim.mode, VimMode::Normal);
+        assert_eq!(input.cursor(), 2);
+
+        let (input, _) = normal("abc\n", "");
+        assert_eq!(input.cursor(), 4);
+    }
+
+    #[test]
+    fn h_and_l_stop_at_the_line_edges() {
+        let (input, _) = normal("ab\ncd", "0hh");
+        assert_eq!(input.cursor(), 3);
+        let (input, _) = normal("ab\ncd", "kll");
+        assert_eq!(input.cursor(), 2);
+    }
+
+    #[test]
+    fn w_moves_to_the_start_of_the_next_word() {
+        let (input, _) = normal("let x = foo(bar);", "0w");
+        assert_eq!(input.cursor(), 4);
+        let (input, _) = normal("let x = foo(bar);", "0wwww");
+        assert_eq!(&input.text()[input.cursor()..], "(bar);");
+        let (input, _) = normal("one\n  two", "0w");
+        assert_eq!(&input.text()[input.cursor()..], "two");
+    }
+
+    #[test]
+    fn e_moves_to_the_end_of_the_word() {
+        let (input, _) = normal("alpha beta", "0e");
+        assert_eq!(input.cursor(), 4);
+        let (input, _) = normal("alpha beta", "0ee");
+        assert_eq!(input.cursor(), 9);
+    }
+
+    #[test]
+    fn dw_deletes_the_word_and_the_space_after_it() {
+        let (input, _) = normal("alpha beta gamma", "0wdw");
+        assert_eq!(input.text(), "alpha gamma");
+        // The last word of a line keeps the line break
+        let (input, _) = normal("alpha beta\ngamma", "kwdw");
+        assert_eq!(input.text(), "alpha \ngamma");
+    }
+
+    #[test]
+    fn cw_changes_to_the_end_of_the_word() {
+        let (input, vim) = normal("alpha beta", "0cw");
+        assert_eq!(input.text(), " beta");
+        assert_eq!(vim.mode, VimMode::Insert);
+    }
+}
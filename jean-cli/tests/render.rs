@@ -0,0 +1,272 @@
+//! Snapshot tests: representative conversations are driven through the
+//! app the way the event loop drives it, drawn on a `TestBackend`, and the
+//! screen text compared with `tests/snapshots/<name>.txt`. After a
+//! deliberate change to the UI, rewrite the snapshots with
+//! `JEAN_UPDATE_SNAPSHOTS=1 cargo test -p jean-cli --test render`.
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use jean_cli::app::App;
+use jean_cli::client::BackendClient;
+use jean_cli::config::Config;
+use jean_cli::conversation_logger::ConversationLogger;
+use jean_cli::transcript::TranscriptItem;
+use jean_cli::ui::ui;
+use jean_shared::{ClientMessage, StreamChunk};
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::TempDir;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+const WIDTH: u16 = 72;
+const HEIGHT: u16 = 20;
+
+struct Harness {
+    app: App,
+    client: BackendClient,
+    sent: UnboundedReceiver<ClientMessage>,
+    _dir: TempDir,
+}
+
+impl Harness {
+    /// An app with its logs and state in a temp dir and a fixed workspace
+    /// in the status bar
+    fn new() -> Self {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let logger = ConversationLogger::in_dir(dir.path().to_path_buf()).expect("create logger");
+        let mut app = App::with_logger(logger, &Config::default());
+        app.workspace.cwd = "~/project".to_string();
+        app.workspace.branch = Some("main".to_string());
+        let (client, sent) = BackendClient::detached();
+        Self { app, client, sent, _dir: dir }
+    }
+
+    async fn type_and_submit(&mut self, prompt: &str) {
+        self.app.input.insert_str(prompt);
+        let enter = Event::Key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert!(!self.app.handle_event(enter, &self.client).await);
+    }
+
+    async fn press(&mut self, code: KeyCode, modifiers: KeyModifiers) {
+        let key = Event::Key(KeyEvent::new(code, modifiers));
+        assert!(!self.app.handle_event(key, &self.client).await);
+    }
+
+    /// Handle chunks as the event loop does, running tool calls as they come
+    async fn receive(&mut self, chunks: Vec<StreamChunk>) {
+        for chunk in chunks {
+            self.app.handle_chunk(chunk, &self.client).await;
+            if self.app.tools_ready() {
+                self.app.advance_tools(&self.client).await;
+            }
+        }
+    }
+
+    fn sent(&mut self) -> Vec<ClientMessage> {
+        let mut sent = Vec::new();
+        while let Ok(message) = self.sent.try_recv() {
+            sent.push(message);
+        }
+        sent
+    }
+
+    /// The screen as text, one line per row with trailing spaces trimmed
+    fn screen(&mut self) -> String {
+        // Timings would make the snapshots flaky
+        self.app.turn_started = None;
+        for item in &mut self.app.transcript {
+            if let TranscriptItem::Tool(run) = item {
+                run.duration_ms = 12;
+            }
+        }
+
+        let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).expect("create terminal");
+        terminal.draw(|f| ui(f, &mut self.app)).expect("draw");
+        let buffer = terminal.backend().buffer();
+        let mut screen = String::new();
+        for y in 0..buffer.area.height {
+            let row: String = (0..buffer.area.width).map(|x| buffer[(x, y)].symbol()).collect();
+            screen.push_str(row.trim_end());
+            screen.push('\n');
+        }
+        screen
+    }
+}
+
+fn text(delta: &str) -> StreamChunk {
+    StreamChunk::Text { delta: delta.to_string(), done: false }
+}
+
+fn done() -> StreamChunk {
+    StreamChunk::Text { delta: String::new(), done: true }
+}
+
+fn assert_snapshot(name: &str, screen: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots").join(format!("{}.txt", name));
+    if std::env::var_os("JEAN_UPDATE_SNAPSHOTS").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).expect("create snapshot dir");
+        fs::write(&path, screen).expect("write snapshot");
+        return;
+    }
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("missing snapshot {:?} ({}); run with JEAN_UPDATE_SNAPSHOTS=1", path, e));
+    assert!(
+        screen == expected,
+        "screen doesn't match snapshot {:?}\n--- expected\n{}--- actual\n{}",
+        path,
+        expected,
+        screen
+    );
+}
+
+#[tokio::test]
+async fn streaming_response() {
+    let mut h = Harness::new();
+    h.type_and_submit("How does the parser handle errors?").await;
+    assert!(matches!(h.sent().as_slice(), [ClientMessage::ChatRequest(_)]));
+
+    h.receive(vec![
+        text("The parser **recovers** at statement boundaries:\n\n"),
+        text("- it records the error\n- skips to the next `;`"),
+    ])
+    .await;
+    assert_snapshot("streaming_response", &h.screen());
+}
+
+#[tokio::test]
+async fn tool_call_and_final_answer() {
+    let mut h = Harness::new();
+    h.type_and_submit("What's in missing.txt?").await;
+    h.sent();
+
+    h.receive(vec![
+        text("Let me read it."),
+        StreamChunk::ToolCall {
+            id: "call_1".to_string(),
+            name: "read_file".to_string(),
+            arguments: r#"{"filename": "missing.txt"}"#.to_string(),
+        },
+        done(),
+    ])
+    .await;
+    let sent = h.sent();
+    assert!(
+        matches!(sent.as_slice(), [ClientMessage::ToolResult { id, .. }] if id == "call_1"),
+        "expected the tool result, got {:?}",
+        sent
+    );
+
+    h.receive(vec![text("There's no file called `missing.txt`."), done()]).await;
+    assert!(!h.app.turn_in_progress());
+    assert_snapshot("tool_call_and_final_answer", &h.screen());
+}
+
+#[tokio::test]
+async fn error_ends_the_turn() {
+    let mut h = Harness::new();
+    h.type_and_submit("Hello").await;
+    h.receive(vec![StreamChunk::Error {
+        message: "OpenAI API Error: Rate limit reached for gpt-5-mini (Code: Some(String(\"rate_limit_exceeded\")), \
+                  Type: Some(\"requests\"))"
+            .to_string(),
+    }])
+    .await;
+    assert!(!h.app.turn_in_progress());
+    assert_snapshot("error_ends_the_turn", &h.screen());
+}
+
+#[tokio::test]
+async fn long_lines_wrap() {
+    let mut h = Harness::new();
+    h.type_and_submit(
+        "Please summarise why the configuration loader falls back to the defaults when the file \
+         exists but one of the nested theme tables has an unknown key in it",
+    )
+    .await;
+    h.receive(vec![
+        text(
+            "Because `Config` is deserialized with `deny_unknown_fields`, a single unknown key \
+             anywhere makes the whole file fail to parse, and the loader reports the error and \
+             carries on with `Config::default()`.",
+        ),
+        done(),
+    ])
+    .await;
+    h.app.input.insert_str(
+        "and what would it take to only drop the bad theme while keeping the rest of the file working",
+    );
+    assert_snapshot("long_lines_wrap", &h.screen());
+}
+
+#[tokio::test]
+async fn prompt_during_a_turn_is_queued() {
+    let mut h = Harness::new();
+    h.type_and_submit("Refactor the lexer").await;
+    h.receive(vec![text("Starting with the tokenizer")]).await;
+    h.type_and_submit("Keep the public API unchanged").await;
+    assert!(matches!(h.sent().as_slice(), [ClientMessage::ChatRequest(_)]));
+    assert_snapshot("prompt_during_a_turn_is_queued", &h.screen());
+
+    // Sent once the turn is over
+    h.receive(vec![done()]).await;
+    assert!(matches!(h.sent().as_slice(), [ClientMessage::ChatRequest(_)]));
+    assert!(h.app.queued.is_empty());
+}
+
+#[tokio::test]
+async fn reasoning_is_folded_above_the_answer() {
+    let mut h = Harness::new();
+    h.type_and_submit("Is the cache thread-safe?").await;
+    h.receive(vec![
+        StreamChunk::Reasoning { delta: "The cache wraps its map in a Mutex,\n".to_string() },
+        StreamChunk::Reasoning { delta: "so concurrent access is serialized.".to_string() },
+        text("Yes, every access goes through a `Mutex`."),
+        done(),
+    ])
+    .await;
+    assert_snapshot("reasoning_is_folded_above_the_answer", &h.screen());
+
+    // Only shown, never sent back
+    h.sent();
+    h.type_and_submit("Thanks").await;
+    let sent = h.sent();
+    let [ClientMessage::ChatRequest(request)] = sent.as_slice() else {
+        panic!("expected a chat request, got {:?}", sent);
+    };
+    assert!(request.messages.iter().all(|m| !m.content.contains("Mutex,")));
+}
+
+#[tokio::test]
+async fn expanding_a_tool_run_or_thinking_redraws_it() {
+    let mut h = Harness::new();
+    h.type_and_submit("What's in missing.txt?").await;
+    h.receive(vec![
+        StreamChunk::Reasoning { delta: "Reading the file will tell.".to_string() },
+        StreamChunk::ToolCall {
+            id: "call_1".to_string(),
+            name: "read_file".to_string(),
+            arguments: r#"{"filename": "missing.txt"}"#.to_string(),
+        },
+        done(),
+    ])
+    .await;
+    h.receive(vec![text("There's no such file."), done()]).await;
+    let folded = h.screen();
+    assert!(folded.contains("▸ thinking"), "{}", folded);
+    assert!(folded.contains("▸ read_file"), "{}", folded);
+
+    // Selecting redraws the item with a highlight; expanding it has to
+    // drop that layout again
+    h.press(KeyCode::Up, KeyModifiers::ALT).await;
+    h.screen();
+    h.press(KeyCode::Char('o'), KeyModifiers::CONTROL).await;
+    let screen = h.screen();
+    assert!(screen.contains("▾ read_file"), "{}", screen);
+
+    h.press(KeyCode::Up, KeyModifiers::ALT).await;
+    h.screen();
+    h.press(KeyCode::Char('o'), KeyModifiers::CONTROL).await;
+    let screen = h.screen();
+    assert!(screen.contains("Reading the file will tell."), "{}", screen);
+}
@@ -1,196 +1,249 @@
 use async_openai::{
-    config::OpenAIConfig,
+    error::ApiError,
     types::{
         ChatCompletionRequestMessage, ChatCompletionRequestSystemMessageArgs,
         ChatCompletionRequestUserMessageArgs, ChatCompletionRequestAssistantMessageArgs,
         ChatCompletionRequestToolMessageArgs,
-        ChatCompletionMessageToolCall,
-        FunctionCall,
+        ChatCompletionMessageToolCall, ChatCompletionStreamOptions, FunctionCall,
         CreateChatCompletionRequestArgs, ChatCompletionTool, FunctionObject, ChatCompletionToolType,
     },
-    Client,
 };
 use futures_util::StreamExt;
-use jean_shared::{ChatMessage, MessageRole, StreamChunk};
+use jean_shared::{ChatMessage, MessageRole, ReasoningEffort, SessionContext, StreamChunk};
+use reqwest_eventsource::{Event, RequestBuilderExt};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
 use std::error::Error;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc;
 use tracing::{info, error};
 
+use crate::stream::StreamParser;
+
+/// Anthropic API version the requests and the stream parser are written for
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Room for the answer on top of Anthropic's thinking budget
+const ANTHROPIC_ANSWER_TOKENS: u32 = 8192;
+
+/// The API models are reached through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+    /// Chat Completions, on OpenAI or a compatible server; reasoning is
+    /// streamed when the server sends `reasoning_content` or `reasoning`
+    /// deltas
+    OpenAi,
+    /// OpenAI's Responses API, opted into for its reasoning summaries. The
+    /// reasoning items aren't sent back on later requests.
+    OpenAiResponses,
+    /// Anthropic's Messages API, with extended thinking
+    Anthropic,
+}
+
+impl Provider {
+    /// Environment variable holding the API key
+    pub fn key_var(self) -> &'static str {
+        match self {
+            Self::OpenAi | Self::OpenAiResponses => "OPENAI_API_KEY",
+            Self::Anthropic => "ANTHROPIC_API_KEY",
+        }
+    }
+
+    /// Environment variable naming the default model
+    pub fn model_var(self) -> &'static str {
+        match self {
+            Self::OpenAi | Self::OpenAiResponses => "OPENAI_MODEL",
+            Self::Anthropic => "ANTHROPIC_MODEL",
+        }
+    }
+
+    /// Environment variable that points the provider at another server
+    pub fn base_url_var(self) -> &'static str {
+        match self {
+            Self::OpenAi | Self::OpenAiResponses => "OPENAI_BASE_URL",
+            Self::Anthropic => "ANTHROPIC_BASE_URL",
+        }
+    }
+
+    fn default_base_url(self) -> &'static str {
+        match self {
+            Self::OpenAi | Self::OpenAiResponses => "https://api.openai.com/v1",
+            Self::Anthropic => "https://api.anthropic.com/v1",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::OpenAi | Self::OpenAiResponses => "OpenAI",
+            Self::Anthropic => "Anthropic",
+        }
+    }
+}
+
+impl FromStr for Provider {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "openai" => Ok(Self::OpenAi),
+            "openai-responses" => Ok(Self::OpenAiResponses),
+            "anthropic" => Ok(Self::Anthropic),
+            _ => Err(format!("Unknown provider '{}' (one of openai, openai-responses, anthropic)", s)),
+        }
+    }
+}
+
+/// OpenAI only accepts reasoning settings for its reasoning models
+fn openai_reasons(model: &str) -> bool {
+    model.starts_with("gpt-5") || ["o1", "o3", "o4"].iter().any(|family| model.starts_with(family))
+}
+
+/// Anthropic thinks only when asked to, with a budget instead of an effort
+fn thinking_budget(effort: ReasoningEffort) -> u32 {
+    match effort {
+        // The smallest budget the API accepts
+        ReasoningEffort::Minimal => 1024,
+        ReasoningEffort::Low => 4096,
+        ReasoningEffort::Medium => 8192,
+        ReasoningEffort::High => 16384,
+    }
+}
+
+/// Anthropic thinking blocks by the id of the first tool call they led to,
+/// sent back with those calls as the API requires. Kept per connection, as
+/// the tool call ids are.
+#[derive(Debug, Clone, Default)]
+pub struct ThinkingBlocks(Arc<Mutex<HashMap<String, Vec<Value>>>>);
+
+/// The body of a failed request
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    error: ApiError,
+}
+
 pub struct LlmService {
-    client: Client<OpenAIConfig>,
+    provider: Provider,
+    http: reqwest::Client,
+    api_key: String,
+    api_base: String,
     model: String,
 }
 
 impl LlmService {
-    pub fn new(api_key: String, model: String) -> Self {
-        info!("Initializing LLM service with model: {}", model);
-        let config = OpenAIConfig::new().with_api_key(api_key);
-        let client = Client::with_config(config);
-        Self { client, model }
+    /// `api_base` points the service at another server speaking the
+    /// provider's API
+    pub fn new(provider: Provider, api_key: String, model: String, api_base: Option<String>) -> Self {
+        info!("Initializing {:?} LLM service with model: {}", provider, model);
+        let api_base = api_base.unwrap_or_else(|| provider.default_base_url().to_string());
+        info!("Using API base {}", api_base);
+        Self {
+            provider,
+            http: reqwest::Client::new(),
+            api_key,
+            api_base: api_base.trim_end_matches('/').to_string(),
+            model,
+        }
     }
 
     pub fn model(&self) -> &str {
         &self.model
     }
 
+    /// Stream a response. `thinking` holds the connection's thinking
+    /// blocks, to send back and to add this response's to.
     pub async fn stream_chat(
         &self,
         messages: Vec<ChatMessage>,
+        context: Option<&SessionContext>,
+        model: Option<&str>,
+        reasoning_effort: Option<ReasoningEffort>,
+        thinking: &ThinkingBlocks,
     ) -> Result<mpsc::UnboundedReceiver<StreamChunk>, Box<dyn Error + Send + Sync>> {
-        let user_messages = messages
-            .into_iter()
-            .map(|msg| self.convert_to_openai_message(msg))
-            .collect::<Result<Vec<_>, _>>()?;
-
-        let system_message = ChatCompletionRequestMessage::System(
-            ChatCompletionRequestSystemMessageArgs::default()
-                .content(self.system_prompt())
-                .build()?
-        );
-
-        let mut messages = Vec::with_capacity(1 + user_messages.len());
-        messages.push(system_message);
-        messages.extend(user_messages);
-
-        
-        let request = CreateChatCompletionRequestArgs::default()
-            .model(&self.model)
-            .messages(messages)
-            .tools(self.tool_definitions())
-            .stream(true)
-            .build()?;
+        let model = model.unwrap_or(&self.model);
+        let system_prompt = self.system_prompt(context);
+        let (path, body) = match self.provider {
+            Provider::OpenAi => {
+                ("/chat/completions", self.chat_request(messages, system_prompt, model, reasoning_effort)?)
+            }
+            Provider::OpenAiResponses => {
+                ("/responses", self.responses_request(messages, system_prompt, model, reasoning_effort))
+            }
+            Provider::Anthropic => {
+                ("/messages", self.anthropic_request(messages, system_prompt, model, reasoning_effort, thinking))
+            }
+        };
 
         // Dump the actual JSON that will be sent
-        let request_json = serde_json::to_string_pretty(&request)?;
-        info!("=== JSON PAYLOAD TO OPENAI ===");
+        let request_json = serde_json::to_string_pretty(&body)?;
+        info!("=== JSON PAYLOAD TO {} ===", self.provider.name().to_uppercase());
         info!("{}", request_json);
         info!("=== END JSON PAYLOAD ===");
 
-        let stream_result = self.client.chat().create_stream(request).await;
-        
-        let mut stream = match stream_result {
-            Ok(s) => {
-                s
-            },
-            Err(e) => {
-                error!("Failed to create OpenAI stream: {:?}", e);
-                // Try to extract more details about the error
-                let error_details = format!("{:?}", e);
-                error!("Full error details: {}", error_details);
-                return Err(Box::new(e));
-            }
+        let request = self.http.post(format!("{}{}", self.api_base, path));
+        let request = match self.provider {
+            Provider::OpenAi | Provider::OpenAiResponses => request.bearer_auth(&self.api_key),
+            Provider::Anthropic => request
+                .header("x-api-key", &self.api_key)
+                .header("anthropic-version", ANTHROPIC_VERSION),
         };
-        
+        let mut stream = request.json(&body).eventsource()?;
+
         let (tx, rx) = mpsc::unbounded_channel();
+        let provider = self.provider;
+        let thinking = thinking.clone();
 
         tokio::spawn(async move {
-            let mut tool_calls: Vec<ChatCompletionMessageToolCall> = Vec::new();
-            let mut sent_tool_calls = false;
+            let mut parser = StreamParser::new(provider);
+            let mut failed = false;
 
-            while let Some(result) = stream.next().await {
+            'events: while let Some(event) = stream.next().await {
+                let result = match event {
+                    Ok(Event::Open) => continue,
+                    Ok(Event::Message(message)) => parser.parse(&message.event, &message.data),
+                    Err(e) => Err(stream_error(provider, e).await),
+                };
                 match result {
-                    Ok(response) => {
-                        if let Some(choice) = response.choices.first() {
-                            // Handle text content
-                            if let Some(delta) = &choice.delta.content {
-                                let chunk = StreamChunk::Text {
-                                    delta: delta.clone(),
-                                    done: false,
-                                };
-                                if tx.send(chunk).is_err() {
-                                    error!("Failed to send chunk to channel");
-                                    break;
-                                }
-                            }
-
-                            // Handle tool calls
-                            if let Some(delta_tool_calls) = &choice.delta.tool_calls {
-                                for delta_tool in delta_tool_calls {
-                                    // Find or create tool call entry
-                                    let index = delta_tool.index as usize;
-                                    while tool_calls.len() <= index {
-                                        tool_calls.push(ChatCompletionMessageToolCall {
-                                            id: String::new(),
-                                            r#type: ChatCompletionToolType::Function,
-                                            function: FunctionCall {
-                                                name: String::new(),
-                                                arguments: String::new(),
-                                            },
-                                        });
-                                    }
-
-                                    // Update tool call with delta
-                                    if let Some(id) = &delta_tool.id {
-                                        tool_calls[index].id = id.clone();
-                                    }
-                                    if let Some(function) = &delta_tool.function {
-                                        if let Some(name) = &function.name {
-                                            tool_calls[index].function.name = name.clone();
-                                        }
-                                        if let Some(args) = &function.arguments {
-                                            tool_calls[index].function.arguments.push_str(args);
-                                        }
-                                    }
-                                }
+                    Ok(parsed) => {
+                        for chunk in parsed.chunks {
+                            if let StreamChunk::ToolCall { id, name, arguments } = &chunk {
+                                info!("Sending tool call to client:");
+                                info!("  Tool ID: {}", id);
+                                info!("  Tool Name: {}", name);
+                                info!("  Arguments: {}", arguments);
                             }
-
-                            // Check if we have complete tool calls (finish_reason is "tool_calls")
-                            if let Some(finish_reason) = &choice.finish_reason {
-                                if finish_reason == &async_openai::types::FinishReason::ToolCalls {
-                                    info!("=== TOOL CALLS DETECTED ===");
-                                    info!("Number of tool calls: {}", tool_calls.len());
-
-                                    // Send tool calls to client for execution
-                                    for tool_call in &tool_calls {
-                                        info!("Sending tool call to client:");
-                                        info!("  Tool ID: {}", tool_call.id);
-                                        info!("  Tool Name: {}", tool_call.function.name);
-                                        info!("  Arguments: {}", tool_call.function.arguments);
-
-                                        let chunk = StreamChunk::ToolCall {
-                                            id: tool_call.id.clone(),
-                                            name: tool_call.function.name.clone(),
-                                            arguments: tool_call.function.arguments.clone(),
-                                        };
-
-                                        let chunk_json = serde_json::to_string_pretty(&chunk).unwrap_or_else(|_| "Failed to serialize".to_string());
-                                        info!("Tool call chunk JSON:\n{}", chunk_json);
-
-                                        if tx.send(chunk).is_err() {
-                                            error!("Failed to send tool call chunk");
-                                            break;
-                                        }
-                                        sent_tool_calls = true;
-                                    }
-
-                                    // Clear tool calls for next iteration
-                                    // Note: The client will execute the tools and send results back
-                                    tool_calls.clear();
-                                }
+                            if tx.send(chunk).is_err() {
+                                error!("Failed to send chunk to channel");
+                                break 'events;
                             }
                         }
+                        if parsed.done {
+                            break;
+                        }
                     }
-                    
-                    Err(e) => {
-                        error!("OpenAI stream error: {:?}", e);
-                        let error_msg = match &e {
-                            async_openai::error::OpenAIError::ApiError(api_err) => {
-                                format!("OpenAI API Error: {} (Code: {:?}, Type: {:?})", 
-                                    api_err.message, api_err.code, api_err.r#type)
-                            },
-                            _ => format!("OpenAI Error: {:?}", e)
-                        };
-                        error!("Detailed error: {}", error_msg);
-                        let error_chunk = StreamChunk::Text {
-                            delta: error_msg,
-                            done: true,
-                        };
-                        let _ = tx.send(error_chunk);
+
+                    Err(message) => {
+                        error!("Detailed error: {}", message);
+                        let _ = tx.send(StreamChunk::Error { message });
+                        failed = true;
                         break;
                     }
                 }
             }
 
+            stream.close();
+
+            // Stored before the turn ends, so it's there when the tool
+            // results come back
+            if let Some((tool_call_id, blocks)) = parser.take_thinking() {
+                thinking.0.lock().unwrap().insert(tool_call_id, blocks);
+            }
+
+            // The error chunk already ended the response
+            if failed {
+                return;
+            }
             let done_chunk = StreamChunk::Text {
                 delta: String::new(),
                 done: true,
@@ -203,11 +256,228 @@ impl LlmService {
         Ok(rx)
     }
 
-    fn system_prompt(&self) -> String {
-        format!(
-            "You are a coding assistant. Your goal is to complete the coding task given to you by USER.\n\
-            You can and should use provided tools to complete the task."
-        )
+    /// A Responses API request. Reasoning summaries have to be asked for;
+    /// without them the API streams no reasoning at all.
+    fn responses_request(
+        &self,
+        messages: Vec<ChatMessage>,
+        system_prompt: String,
+        model: &str,
+        reasoning_effort: Option<ReasoningEffort>,
+    ) -> Value {
+        let mut input = Vec::new();
+        for msg in messages {
+            match msg.role {
+                MessageRole::System | MessageRole::User => {
+                    input.push(json!({ "role": msg.role, "content": msg.content }));
+                }
+                MessageRole::Assistant => {
+                    if !msg.content.is_empty() {
+                        input.push(json!({ "role": "assistant", "content": msg.content }));
+                    }
+                    for tool_call in msg.tool_calls.into_iter().flatten() {
+                        input.push(json!({
+                            "type": "function_call",
+                            "call_id": tool_call.id,
+                            "name": tool_call.name,
+                            "arguments": tool_call.arguments,
+                        }));
+                    }
+                }
+                MessageRole::Tool => input.push(json!({
+                    "type": "function_call_output",
+                    "call_id": msg.tool_call_id.unwrap_or_default(),
+                    "output": msg.content,
+                })),
+            }
+        }
+
+        let tools: Vec<Value> = self
+            .tool_definitions()
+            .into_iter()
+            .map(|tool| {
+                json!({
+                    "type": "function",
+                    "name": tool.function.name,
+                    "description": tool.function.description,
+                    "parameters": tool.function.parameters,
+                    "strict": false,
+                })
+            })
+            .collect();
+
+        let mut body = json!({
+            "model": model,
+            "instructions": system_prompt,
+            "input": input,
+            "tools": tools,
+            "stream": true,
+        });
+        if openai_reasons(model) {
+            body["reasoning"] = json!({ "summary": "auto" });
+            if let Some(effort) = reasoning_effort {
+                body["reasoning"]["effort"] = effort.as_str().into();
+            }
+        }
+        body
+    }
+
+    /// A Chat Completions request
+    fn chat_request(
+        &self,
+        messages: Vec<ChatMessage>,
+        system_prompt: String,
+        model: &str,
+        reasoning_effort: Option<ReasoningEffort>,
+    ) -> Result<Value, Box<dyn Error + Send + Sync>> {
+        let user_messages = messages
+            .into_iter()
+            .map(|msg| self.convert_to_openai_message(msg))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let system_message = ChatCompletionRequestMessage::System(
+            ChatCompletionRequestSystemMessageArgs::default()
+                .content(system_prompt)
+                .build()?
+        );
+
+        let mut messages = Vec::with_capacity(1 + user_messages.len());
+        messages.push(system_message);
+        messages.extend(user_messages);
+
+        let request = CreateChatCompletionRequestArgs::default()
+            .model(model)
+            .messages(messages)
+            .tools(self.tool_definitions())
+            .stream(true)
+            .stream_options(ChatCompletionStreamOptions { include_usage: true })
+            .build()?;
+
+        // async-openai's request has no reasoning_effort yet. Other models
+        // reject it.
+        let mut body = serde_json::to_value(&request)?;
+        if let Some(effort) = reasoning_effort.filter(|_| openai_reasons(model)) {
+            body["reasoning_effort"] = effort.as_str().into();
+        }
+        Ok(body)
+    }
+
+    /// A Messages API request. Thinking is off unless an effort is set, as
+    /// it is by default for Claude models.
+    fn anthropic_request(
+        &self,
+        messages: Vec<ChatMessage>,
+        mut system_prompt: String,
+        model: &str,
+        reasoning_effort: Option<ReasoningEffort>,
+        thinking: &ThinkingBlocks,
+    ) -> Value {
+        let mut thinking = thinking.0.lock().unwrap();
+        // Blocks for tool calls no longer in the conversation, after the
+        // client cleared or loaded another one, won't be needed again
+        thinking.retain(|id, _| {
+            messages
+                .iter()
+                .flat_map(|msg| msg.tool_calls.iter().flatten())
+                .any(|tool_call| tool_call.id == *id)
+        });
+        let mut turns: Vec<(&'static str, Vec<Value>)> = Vec::new();
+        for msg in messages {
+            let (role, blocks) = match msg.role {
+                MessageRole::System => {
+                    system_prompt.push_str("\n\n");
+                    system_prompt.push_str(&msg.content);
+                    continue;
+                }
+                MessageRole::User => ("user", vec![json!({ "type": "text", "text": msg.content })]),
+                MessageRole::Assistant => {
+                    let tool_calls = msg.tool_calls.unwrap_or_default();
+                    let mut blocks = tool_calls
+                        .first()
+                        .and_then(|tool_call| thinking.get(&tool_call.id))
+                        .cloned()
+                        .unwrap_or_default();
+                    if !msg.content.is_empty() {
+                        blocks.push(json!({ "type": "text", "text": msg.content }));
+                    }
+                    for tool_call in tool_calls {
+                        let input: Value =
+                            serde_json::from_str(&tool_call.arguments).unwrap_or_else(|_| json!({}));
+                        blocks.push(json!({
+                            "type": "tool_use",
+                            "id": tool_call.id,
+                            "name": tool_call.name,
+                            "input": input,
+                        }));
+                    }
+                    ("assistant", blocks)
+                }
+                MessageRole::Tool => (
+                    "user",
+                    vec![json!({
+                        "type": "tool_result",
+                        "tool_use_id": msg.tool_call_id.unwrap_or_default(),
+                        "content": msg.content,
+                    })],
+                ),
+            };
+            // Tool results and the text after them go in one user turn
+            match turns.last_mut() {
+                Some((last, last_blocks)) if *last == role => last_blocks.extend(blocks),
+                _ => turns.push((role, blocks)),
+            }
+        }
+        drop(thinking);
+
+        let messages: Vec<Value> = turns
+            .into_iter()
+            .map(|(role, content)| json!({ "role": role, "content": content }))
+            .collect();
+        let tools: Vec<Value> = self
+            .tool_definitions()
+            .into_iter()
+            .map(|tool| {
+                json!({
+                    "name": tool.function.name,
+                    "description": tool.function.description,
+                    "input_schema": tool.function.parameters,
+                })
+            })
+            .collect();
+
+        let budget = reasoning_effort.map(thinking_budget);
+        let mut body = json!({
+            "model": model,
+            "max_tokens": budget.unwrap_or(0) + ANTHROPIC_ANSWER_TOKENS,
+            "system": system_prompt,
+            "messages": messages,
+            "tools": tools,
+            "stream": true,
+        });
+        if let Some(budget) = budget {
+            body["thinking"] = json!({ "type": "enabled", "budget_tokens": budget });
+        }
+        body
+    }
+
+    fn system_prompt(&self, context: Option<&SessionContext>) -> String {
+        let mut prompt = "You are a coding assistant. Your goal is to complete the coding task given to you by USER.\n\
+        You can and should use provided tools to complete the task."
+            .to_string();
+
+        if let Some(context) = context {
+            prompt.push_str(&format!("\n\nWorking directory: {}", context.cwd));
+            if let Some(git) = &context.git {
+                prompt.push_str(&format!("\nGit branch: {}", git.branch));
+                match git.dirty {
+                    Some(true) => prompt.push_str(" (uncommitted changes)"),
+                    Some(false) => prompt.push_str(" (clean)"),
+                    None => {}
+                }
+            }
+        }
+
+        prompt
     }
 
     fn tool_definitions(&self) -> Vec<ChatCompletionTool> {
@@ -231,6 +501,63 @@ impl LlmService {
             },
         };
 
+        let write_file = ChatCompletionTool {
+            r#type: ChatCompletionToolType::Function,
+            function: FunctionObject {
+                name: "write_file".to_string(),
+                description: Some("Create a file or overwrite it with the given contents. The previous contents are snapshotted so the user can undo the change".to_string()),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "filename": {
+                            "type": "string",
+                            "description": "Workspace-relative path of the file to write"
+                        },
+                        "content": {
+                            "type": "string",
+                            "description": "Complete new contents of the file"
+                        }
+                    },
+                    "required": ["filename", "content"],
+                    "additionalProperties": false
+                }).into(),
+                strict: None
+            },
+        };
+
+        let edit_file = ChatCompletionTool {
+            r#type: ChatCompletionToolType::Function,
+            function: FunctionObject {
+                name: "edit_file".to_string(),
+                description: Some("Replace an exact string in an existing file. `old_string` must match uniquely unless `replace_all` is set. The previous contents are snapshotted so the user can undo the change".to_string()),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "filename": {
+                            "type": "string",
+                            "description": "Workspace-relative path of the file to edit"
+                        },
+                        "old_string": {
+                            "type": "string",
+                            "description": "Exact text to replace, including whitespace and indentation"
+                        },
+                        "new_string": {
+                            "type": "string",
+                            "description": "Replacement text"
+                        },
+                        "replace_all": {
+                            "type": "boolean",
+                            "description": "Replace every occurrence instead of requiring a unique match",
+                            "default": false
+                        }
+                    },
+                    "required": ["filename", "old_string", "new_string"],
+                    "additionalProperties": false
+                }).into(),
+                strict: None
+            },
+        };
+
         let grep = ChatCompletionTool {
             r#type: ChatCompletionToolType::Function,
             function: FunctionObject {
@@ -260,7 +587,182 @@ impl LlmService {
             },
         };
 
-        vec![read_file, grep]
+        let find_files = ChatCompletionTool {
+            r#type: ChatCompletionToolType::Function,
+            function: FunctionObject {
+                name: "find_files".to_string(),
+                description: Some("List files whose path matches a glob pattern, most recently modified first. Respects .gitignore".to_string()),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "pattern": {
+                            "type": "string",
+                            "description": "Glob matched against workspace-relative paths (e.g., '**/*.rs', 'src/**/mod.rs')"
+                        },
+                        "max_results": {
+                            "type": "integer",
+                            "description": "Maximum number of paths to return",
+                            "default": 200
+                        }
+                    },
+                    "required": ["pattern"],
+                    "additionalProperties": false
+                }).into(),
+                strict: None
+            },
+        };
+
+        let list_directory = ChatCompletionTool {
+            r#type: ChatCompletionToolType::Function,
+            function: FunctionObject {
+                name: "list_directory".to_string(),
+                description: Some("Show a directory tree with file sizes, down to a given depth. Respects .gitignore".to_string()),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "Directory to list, relative to the workspace root",
+                            "default": "."
+                        },
+                        "depth": {
+                            "type": "integer",
+                            "description": "How many levels to list; 1 lists only the directory's own entries",
+                            "minimum": 1,
+                            "default": 2
+                        }
+                    },
+                    "additionalProperties": false
+                }).into(),
+                strict: None
+            },
+        };
+
+        let find_symbol = ChatCompletionTool {
+            r#type: ChatCompletionToolType::Function,
+            function: FunctionObject {
+                name: "find_symbol".to_string(),
+                description: Some("Look up code definitions (functions, methods, structs, enums, traits, impls, classes) parsed with tree-sitter. \
+                    Give `name` to get a symbol's definition with its source, or only `file` to get an outline of that file. \
+                    Supports Rust, Python, JavaScript, TypeScript and Go".to_string()),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "name": {
+                            "type": "string",
+                            "description": "Symbol name to look up (exact match, falls back to substring)"
+                        },
+                        "file": {
+                            "type": "string",
+                            "description": "Workspace-relative file path; restricts the lookup, or outlines the file when `name` is omitted"
+                        },
+                        "kind": {
+                            "type": "string",
+                            "enum": ["function", "method", "struct", "enum", "trait", "impl", "interface", "class", "type", "const", "module", "macro"],
+                            "description": "Only return symbols of this kind"
+                        }
+                    },
+                    "additionalProperties": false
+                }).into(),
+                strict: None
+            },
+        };
+
+        let git_status = ChatCompletionTool {
+            r#type: ChatCompletionToolType::Function,
+            function: FunctionObject {
+                name: "git_status".to_string(),
+                description: Some("Show the current branch and changed, staged and untracked files (git status --short --branch)".to_string()),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {},
+                    "additionalProperties": false
+                }).into(),
+                strict: None
+            },
+        };
+
+        let git_diff = ChatCompletionTool {
+            r#type: ChatCompletionToolType::Function,
+            function: FunctionObject {
+                name: "git_diff".to_string(),
+                description: Some("Show a unified diff. By default unstaged worktree changes; set `staged` for the index, or `against` to compare the worktree with a ref".to_string()),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "staged": {
+                            "type": "boolean",
+                            "description": "Show staged changes (index vs HEAD)",
+                            "default": false
+                        },
+                        "against": {
+                            "type": "string",
+                            "description": "Commit, branch or tag to diff the worktree against (e.g., 'HEAD~3', 'main')"
+                        },
+                        "path": {
+                            "type": "string",
+                            "description": "Limit the diff to this file or directory"
+                        }
+                    },
+                    "additionalProperties": false
+                }).into(),
+                strict: None
+            },
+        };
+
+        let git_log = ChatCompletionTool {
+            r#type: ChatCompletionToolType::Function,
+            function: FunctionObject {
+                name: "git_log".to_string(),
+                description: Some("Show recent commits (hash, date, author, subject), optionally only those touching a path".to_string()),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "Only show commits touching this file or directory"
+                        },
+                        "max_count": {
+                            "type": "integer",
+                            "description": "Maximum number of commits to show",
+                            "default": 20
+                        }
+                    },
+                    "additionalProperties": false
+                }).into(),
+                strict: None
+            },
+        };
+
+        let git_blame = ChatCompletionTool {
+            r#type: ChatCompletionToolType::Function,
+            function: FunctionObject {
+                name: "git_blame".to_string(),
+                description: Some("Show who last changed each line in a line range of a file".to_string()),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "Workspace-relative path of the file"
+                        },
+                        "start_line": {
+                            "type": "integer",
+                            "description": "First line of the range (1-based)"
+                        },
+                        "end_line": {
+                            "type": "integer",
+                            "description": "Last line of the range (inclusive)"
+                        }
+                    },
+                    "required": ["path", "start_line", "end_line"],
+                    "additionalProperties": false
+                }).into(),
+                strict: None
+            },
+        };
+
+        vec![read_file, write_file, edit_file, grep, find_files, list_directory, find_symbol, git_status, git_diff, git_log, git_blame]
     }
 
     fn convert_to_openai_message(
@@ -319,4 +821,209 @@ impl LlmService {
         };
         Ok(message)
     }
-}
\ No newline at end of file
+}
+
+/// Describe a failed stream, with the API's own message when it sent one
+async fn stream_error(provider: Provider, error: reqwest_eventsource::Error) -> String {
+    error!("{} stream error: {:?}", provider.name(), error);
+    match error {
+        reqwest_eventsource::Error::InvalidStatusCode(status, response) => {
+            let body = response.text().await.unwrap_or_default();
+            match serde_json::from_str::<ErrorResponse>(&body) {
+                Ok(ErrorResponse { error: api_err }) => format!(
+                    "{} API Error: {} (Code: {:?}, Type: {:?})",
+                    provider.name(), api_err.message, api_err.code, api_err.r#type
+                ),
+                Err(_) => format!("{} Error: {} {}", provider.name(), status, body),
+            }
+        }
+        e => format!("{} Error: {}", provider.name(), e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jean_shared::ToolCall;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    fn message(role: MessageRole, content: &str) -> ChatMessage {
+        ChatMessage { role, content: content.to_string(), tool_call_id: None, tool_calls: None }
+    }
+
+    /// A turn that read a file: the prompt, the call and its result
+    fn tool_turn() -> Vec<ChatMessage> {
+        vec![
+            message(MessageRole::User, "What's in the manifest?"),
+            ChatMessage {
+                tool_calls: Some(vec![ToolCall {
+                    id: "toolu_01".to_string(),
+                    name: "read_file".to_string(),
+                    arguments: r#"{"filename":"Cargo.toml"}"#.to_string(),
+                }]),
+                ..message(MessageRole::Assistant, "")
+            },
+            ChatMessage { tool_call_id: Some("toolu_01".to_string()), ..message(MessageRole::Tool, "[package]") },
+            message(MessageRole::User, "Only the name, please"),
+        ]
+    }
+
+    fn service(provider: Provider) -> LlmService {
+        LlmService::new(provider, "sk-test".to_string(), "model".to_string(), None)
+    }
+
+    #[test]
+    fn the_responses_api_is_opted_into() {
+        assert_eq!("openai".parse(), Ok(Provider::OpenAi));
+        assert_eq!("openai-responses".parse(), Ok(Provider::OpenAiResponses));
+        assert!(matches!(StreamParser::new(Provider::OpenAi), StreamParser::Chat(_)));
+    }
+
+    #[test]
+    fn responses_requests_ask_reasoning_models_for_summaries() {
+        let llm = service(Provider::OpenAiResponses);
+
+        let body = llm.responses_request(tool_turn(), String::new(), "gpt-5-mini", Some(ReasoningEffort::Low));
+        assert_eq!(body["reasoning"], json!({ "summary": "auto", "effort": "low" }));
+        assert_eq!(body["input"][1]["type"], "function_call");
+        assert_eq!(body["input"][1]["call_id"], "toolu_01");
+        assert_eq!(body["input"][2], json!({ "type": "function_call_output", "call_id": "toolu_01", "output": "[package]" }));
+
+        let body = llm.responses_request(tool_turn(), String::new(), "gpt-4.1", Some(ReasoningEffort::Low));
+        assert!(body.get("reasoning").is_none());
+    }
+
+    #[test]
+    fn chat_requests_set_the_effort_only_for_reasoning_models() {
+        let llm = service(Provider::OpenAi);
+
+        let body = llm.chat_request(tool_turn(), String::new(), "gpt-5-mini", Some(ReasoningEffort::High)).unwrap();
+        assert_eq!(body["reasoning_effort"], "high");
+
+        let body = llm.chat_request(tool_turn(), String::new(), "gpt-4.1", Some(ReasoningEffort::High)).unwrap();
+        assert!(body.get("reasoning_effort").is_none());
+    }
+
+    #[test]
+    fn anthropic_requests_think_within_a_budget_only_when_asked() {
+        let llm = service(Provider::Anthropic);
+        let thinking = ThinkingBlocks::default();
+
+        let body = llm.anthropic_request(tool_turn(), String::new(), "claude", Some(ReasoningEffort::Medium), &thinking);
+        assert_eq!(body["thinking"], json!({ "type": "enabled", "budget_tokens": 8192 }));
+        assert_eq!(body["max_tokens"], 8192 + ANTHROPIC_ANSWER_TOKENS);
+
+        let body = llm.anthropic_request(tool_turn(), String::new(), "claude", None, &thinking);
+        assert!(body.get("thinking").is_none());
+        assert_eq!(body["max_tokens"], ANTHROPIC_ANSWER_TOKENS);
+    }
+
+    #[test]
+    fn anthropic_requests_send_thinking_back_ahead_of_its_tool_calls() {
+        let llm = service(Provider::Anthropic);
+        let block = json!({ "type": "thinking", "thinking": "Read it first.", "signature": "c2ln" });
+        let thinking = ThinkingBlocks::default();
+        thinking.0.lock().unwrap().insert("toolu_01".to_string(), vec![block.clone()]);
+        thinking.0.lock().unwrap().insert("toolu_00".to_string(), vec![block.clone()]);
+
+        let body = llm.anthropic_request(tool_turn(), String::new(), "claude", Some(ReasoningEffort::Low), &thinking);
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1]["role"], "assistant");
+        assert_eq!(messages[1]["content"][0], block);
+        assert_eq!(
+            messages[1]["content"][1],
+            json!({ "type": "tool_use", "id": "toolu_01", "name": "read_file", "input": { "filename": "Cargo.toml" } })
+        );
+        // The result and the prompt after it share one user turn
+        assert_eq!(messages[2]["role"], "user");
+        assert_eq!(messages[2]["content"][0]["type"], "tool_result");
+        assert_eq!(messages[2]["content"][1], json!({ "type": "text", "text": "Only the name, please" }));
+        // Dropped once its tool call is gone from the conversation
+        assert!(!thinking.0.lock().unwrap().contains_key("toolu_00"));
+    }
+
+    /// Answer one request on a local port with the given status and body,
+    /// standing in for the provider's API. Returns the base URL.
+    async fn serve_once(status: &'static str, content_type: &'static str, body: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut reader = BufReader::new(socket);
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).await.unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':')
+                    && name.eq_ignore_ascii_case("content-length")
+                {
+                    content_length = value.trim().parse().unwrap();
+                }
+            }
+            let mut request = vec![0; content_length];
+            reader.read_exact(&mut request).await.unwrap();
+            let response = format!(
+                "HTTP/1.1 {}\r\ncontent-type: {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                status,
+                content_type,
+                body.len(),
+                body
+            );
+            reader.get_mut().write_all(response.as_bytes()).await.unwrap();
+        });
+        format!("http://{}", addr)
+    }
+
+    /// Everything the client would be sent for one request
+    async fn chunks(llm: &LlmService) -> Vec<StreamChunk> {
+        let mut rx = llm
+            .stream_chat(vec![message(MessageRole::User, "Hi")], None, None, None, &ThinkingBlocks::default())
+            .await
+            .unwrap();
+        let mut chunks = Vec::new();
+        while let Some(chunk) = rx.recv().await {
+            chunks.push(chunk);
+        }
+        chunks
+    }
+
+    fn only_error(chunks: &[StreamChunk]) -> &str {
+        match chunks {
+            [StreamChunk::Error { message }] => message,
+            _ => panic!("expected a single error chunk, got {:?}", chunks),
+        }
+    }
+
+    #[tokio::test]
+    async fn errors_in_the_stream_reach_the_client_as_error_chunks() {
+        let base = serve_once(
+            "200 OK",
+            "text/event-stream",
+            "event: error\ndata: {\"type\":\"error\",\"error\":{\"type\":\"overloaded_error\",\"message\":\"Overloaded\"}}\n\n",
+        )
+        .await;
+        let llm = LlmService::new(Provider::Anthropic, "sk-test".to_string(), "claude".to_string(), Some(base));
+
+        assert_eq!(only_error(&chunks(&llm).await), "Anthropic API Error: Overloaded");
+    }
+
+    #[tokio::test]
+    async fn failed_requests_reach_the_client_as_error_chunks() {
+        let base = serve_once(
+            "429 Too Many Requests",
+            "application/json",
+            r#"{"error":{"message":"Rate limit reached","type":"requests","param":null,"code":"rate_limit_exceeded"}}"#,
+        )
+        .await;
+        let llm = LlmService::new(Provider::OpenAi, "sk-test".to_string(), "gpt-4.1".to_string(), Some(base));
+
+        let chunks = chunks(&llm).await;
+        let message = only_error(&chunks);
+        assert!(message.starts_with("OpenAI API Error: Rate limit reached"), "{}", message);
+    }
+}
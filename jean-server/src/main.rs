@@ -1,4 +1,5 @@
 mod llm;
+mod stream;
 
 use axum::{
     extract::ws::{Message, WebSocket, WebSocketUpgrade},
@@ -7,38 +8,50 @@ use axum::{
     routing::{get, post},
     Json, Router,
 };
-use jean_shared::{ClientChatRequest, ClientMessage, ChatMessage, MessageRole, ChatResponse, StreamChunk, ToolCall};
+use jean_shared::{ClientChatRequest, ClientMessage, ChatMessage, MessageRole, ChatResponse, ReasoningEffort, SessionContext, StreamChunk, ToolCall};
+use std::collections::VecDeque;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tower_http::cors::CorsLayer;
+use tokio::sync::mpsc;
 use tracing::{info, error};
-use llm::LlmService;
+use llm::{LlmService, Provider, ThinkingBlocks};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt::init();
     dotenv::dotenv().ok();
 
-    let api_key = match std::env::var("OPENAI_API_KEY") {
+    let provider = match std::env::var("LLM_PROVIDER") {
+        Ok(provider) => provider.parse().unwrap_or_else(|e| {
+            error!("{}. Please check your .env file", e);
+            panic!("Invalid LLM_PROVIDER");
+        }),
+        Err(_) => Provider::OpenAi,
+    };
+    let key_var = provider.key_var();
+
+    let api_key = match std::env::var(key_var) {
         Ok(key) if key.starts_with("sk-") => {
-            info!("OpenAI API key loaded successfully");
+            info!("{} loaded successfully", key_var);
             key
         }
         Ok(_) => {
-            error!("OPENAI_API_KEY found but doesn't start with 'sk-'. Please check your .env file");
-            panic!("Invalid OpenAI API key format");
+            error!("{} found but doesn't start with 'sk-'. Please check your .env file", key_var);
+            panic!("Invalid API key format");
         }
         Err(_) => {
-            error!("OPENAI_API_KEY not found. Please set it in your .env file");
-            panic!("OPENAI_API_KEY must be set in .env file");
+            error!("{} not found. Please set it in your .env file", key_var);
+            panic!("{} must be set in .env file", key_var);
         }
     };
-    
-    let model = std::env::var("OPENAI_MODEL")
-        .expect("OPENAI_MODEL must be set in .env file");
-    
-    info!("Using OpenAI model: {}", model);
-    let llm_service = Arc::new(LlmService::new(api_key, model.clone()));
+
+    let model = std::env::var(provider.model_var())
+        .unwrap_or_else(|_| panic!("{} must be set in .env file", provider.model_var()));
+    let api_base = std::env::var(provider.base_url_var()).ok();
+
+    info!("Using {:?} model: {}", provider, model);
+    let llm_service = Arc::new(LlmService::new(provider, api_key, model.clone(), api_base));
 
     let app = Router::new()
         .route("/health", get(health))
@@ -70,7 +83,13 @@ async fn chat(
     llm_service: Arc<LlmService>,
 ) -> Result<Json<ChatResponse>, StatusCode> {
     let mut rx = llm_service
-        .stream_chat(request.messages.clone())
+        .stream_chat(
+            request.messages.clone(),
+            request.context.as_ref(),
+            request.model.as_deref(),
+            request.reasoning_effort,
+            &ThinkingBlocks::default(),
+        )
         .await
         .map_err(|e| {
             error!("Failed to stream chat: {}", e);
@@ -86,6 +105,10 @@ async fn chat(
                     break;
                 }
             }
+            StreamChunk::Error { message } => {
+                error!("Chat failed: {}", message);
+                return Err(StatusCode::BAD_GATEWAY);
+            }
             _ => {}
         }
     }
@@ -108,10 +131,27 @@ async fn handle_socket(mut socket: WebSocket, llm_service: Arc<LlmService>) {
 
     // Store conversation history for this connection
     let mut conversation_history: Vec<ChatMessage> = Vec::new();
+    // Latest workspace context reported by the client, reused for tool follow-ups
+    let mut session_context: Option<SessionContext> = None;
+    // Model chosen by the client with /model, if any
+    let mut model: Option<String> = None;
+    // Reasoning effort chosen by the client with /effort, if any
+    let mut reasoning_effort: Option<ReasoningEffort> = None;
     // Track pending tool calls from the assistant (for future use)
     let mut _pending_tool_calls: Vec<ToolCall> = Vec::new();
+    // Messages that arrived while a response was streaming, handled next
+    let mut deferred: VecDeque<String> = VecDeque::new();
+    // Anthropic thinking to send back with the tool calls it led to
+    let thinking = ThinkingBlocks::default();
 
-    while let Some(msg) = socket.recv().await {
+    loop {
+        let msg = match deferred.pop_front() {
+            Some(text) => Ok(Message::Text(text)),
+            None => match socket.recv().await {
+                Some(msg) => msg,
+                None => break,
+            },
+        };
         if let Ok(Message::Text(text)) = msg {
             info!("=== MESSAGE RECEIVED FROM CLIENT ===");
             info!("Raw message:\n{}", text);
@@ -126,14 +166,20 @@ async fn handle_socket(mut socket: WebSocket, llm_service: Arc<LlmService>) {
 
                     // Update conversation history with new messages
                     conversation_history = request.messages.clone();
+                    session_context = request.context;
+                    model = request.model;
+                    reasoning_effort = request.reasoning_effort;
 
-                    match llm_service.stream_chat(request.messages).await {
+                    match llm_service
+                        .stream_chat(request.messages, session_context.as_ref(), model.as_deref(), reasoning_effort, &thinking)
+                        .await
+                    {
                         Ok(mut rx) => {
                             let mut assistant_response = String::new();
                             let mut current_tool_calls = Vec::new();
 
-                            while let Some(chunk) = rx.recv().await {
-                                let is_done = matches!(&chunk, StreamChunk::Text { done: true, .. });
+                            while let Some(chunk) = next_chunk(&mut rx, &mut socket, &mut deferred).await {
+                                let is_done = ends_response(&chunk);
 
                                 // Log different types of chunks
                                 match &chunk {
@@ -157,6 +203,13 @@ async fn handle_socket(mut socket: WebSocket, llm_service: Arc<LlmService>) {
                                     StreamChunk::ToolResult { id, content } => {
                                         info!("Sending tool result: {} - {}", id, content);
                                     }
+                                    StreamChunk::Usage { model, prompt_tokens, completion_tokens } => {
+                                        info!("Usage for {}: {} prompt + {} completion tokens", model, prompt_tokens, completion_tokens);
+                                    }
+                                    StreamChunk::Error { message } => {
+                                        error!("Sending error to client: {}", message);
+                                    }
+                                    StreamChunk::Reasoning { .. } | StreamChunk::Cancelled => {}
                                 }
 
                                 if let Ok(response) = serde_json::to_string(&chunk) {
@@ -194,17 +247,11 @@ async fn handle_socket(mut socket: WebSocket, llm_service: Arc<LlmService>) {
                         }
                         Err(e) => {
                             error!("Failed to stream chat: {:?}", e);
-                            let error_chunk = StreamChunk::Text {
-                                delta: format!("Error: {}", e),
-                                done: true,
-                            };
-                            if let Ok(response) = serde_json::to_string(&error_chunk) {
-                                let _ = socket.send(Message::Text(response)).await;
-                            }
+                            send_error(&mut socket, format!("Error: {}", e)).await;
                         }
                     }
                 }
-                Ok(ClientMessage::ToolResult { id, content }) => {
+                Ok(ClientMessage::ToolResult { id, content, steer }) => {
                     info!("=== TOOL RESULT RECEIVED FROM CLIENT ===");
                     info!("Tool ID: {}", id);
                     info!("Result content length: {} chars", content.len());
@@ -222,6 +269,15 @@ async fn handle_socket(mut socket: WebSocket, llm_service: Arc<LlmService>) {
                         tool_call_id: Some(id.clone()),
                         tool_calls: None,
                     });
+                    if let Some(steer) = steer {
+                        info!("Adding steering message from the user ({} chars)", steer.len());
+                        conversation_history.push(ChatMessage {
+                            role: MessageRole::User,
+                            content: steer,
+                            tool_call_id: None,
+                            tool_calls: None,
+                        });
+                    }
 
                     // Continue the conversation with the LLM
                     info!("Continuing conversation with tool result");
@@ -240,13 +296,22 @@ async fn handle_socket(mut socket: WebSocket, llm_service: Arc<LlmService>) {
                         }
                     }
 
-                    match llm_service.stream_chat(conversation_history.clone()).await {
+                    match llm_service
+                        .stream_chat(
+                            conversation_history.clone(),
+                            session_context.as_ref(),
+                            model.as_deref(),
+                            reasoning_effort,
+                            &thinking,
+                        )
+                        .await
+                    {
                         Ok(mut rx) => {
                             let mut assistant_response = String::new();
                             let mut current_tool_calls = Vec::new();
 
-                            while let Some(chunk) = rx.recv().await {
-                                let is_done = matches!(&chunk, StreamChunk::Text { done: true, .. });
+                            while let Some(chunk) = next_chunk(&mut rx, &mut socket, &mut deferred).await {
+                                let is_done = ends_response(&chunk);
 
                                 match &chunk {
                                     StreamChunk::Text { delta, done } => {
@@ -305,27 +370,75 @@ async fn handle_socket(mut socket: WebSocket, llm_service: Arc<LlmService>) {
                         }
                         Err(e) => {
                             error!("Failed to continue chat after tool result: {:?}", e);
-                            let error_chunk = StreamChunk::Text {
-                                delta: format!("Error continuing conversation: {}", e),
-                                done: true,
-                            };
-                            if let Ok(response) = serde_json::to_string(&error_chunk) {
-                                let _ = socket.send(Message::Text(response)).await;
-                            }
+                            send_error(&mut socket, format!("Error continuing conversation: {}", e)).await;
                         }
                     }
                 }
+                Ok(ClientMessage::Cancel) => {
+                    // The response already finished before the cancel arrived
+                    info!("Cancel received with nothing streaming");
+                    send_cancelled(&mut socket).await;
+                }
                 Err(e) => {
                     error!("Failed to parse request: {}", e);
-                    let error_chunk = StreamChunk::Text {
-                        delta: format!("Invalid request format: {}", e),
-                        done: true,
-                    };
-                    if let Ok(response) = serde_json::to_string(&error_chunk) {
-                        let _ = socket.send(Message::Text(response)).await;
-                    }
+                    send_error(&mut socket, format!("Invalid request format: {}", e)).await;
                 }
             }
         }
     }
-}
\ No newline at end of file
+}
+
+/// The next chunk of the response being streamed, while watching the socket
+/// for the client cancelling it. Other messages, such as tool results sent
+/// before the response finished, are kept in `deferred` for afterwards.
+/// Returns `None` when the response is over: finished, cancelled or the
+/// connection closed.
+async fn next_chunk(
+    rx: &mut mpsc::UnboundedReceiver<StreamChunk>,
+    socket: &mut WebSocket,
+    deferred: &mut VecDeque<String>,
+) -> Option<StreamChunk> {
+    loop {
+        tokio::select! {
+            chunk = rx.recv() => return chunk,
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str::<ClientMessage>(&text) {
+                    Ok(ClientMessage::Cancel) => {
+                        info!("=== TURN CANCELLED BY CLIENT ===");
+                        // Tool results still waiting belong to the cancelled turn
+                        deferred.clear();
+                        send_cancelled(socket).await;
+                        return None;
+                    }
+                    _ => {
+                        info!("Deferring message received while streaming");
+                        deferred.push_back(text);
+                    }
+                },
+                Some(Ok(_)) => {}
+                None | Some(Err(_)) => return None,
+            },
+        }
+    }
+}
+
+/// A response is over once its `done` chunk or an error has been sent
+fn ends_response(chunk: &StreamChunk) -> bool {
+    matches!(chunk, StreamChunk::Text { done: true, .. } | StreamChunk::Error { .. })
+}
+
+async fn send_error(socket: &mut WebSocket, message: String) {
+    if let Ok(response) = serde_json::to_string(&StreamChunk::Error { message })
+        && let Err(e) = socket.send(Message::Text(response)).await
+    {
+        error!("Failed to send error: {}", e);
+    }
+}
+
+async fn send_cancelled(socket: &mut WebSocket) {
+    if let Ok(response) = serde_json::to_string(&StreamChunk::Cancelled)
+        && let Err(e) = socket.send(Message::Text(response)).await
+    {
+        error!("Failed to send cancel acknowledgement: {}", e);
+    }
+}
@@ -0,0 +1,610 @@
+//! Turning each provider's server-sent events into `StreamChunk`s. The
+//! parsers only see event names and data, so recorded streams can be fed
+//! through them in tests.
+
+use async_openai::types::{ChatCompletionMessageToolCallChunk, CompletionUsage, FinishReason};
+use jean_shared::StreamChunk;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::llm::Provider;
+
+/// What one event amounted to
+#[derive(Debug, Default)]
+pub struct Parsed {
+    pub chunks: Vec<StreamChunk>,
+    /// The response is complete and the stream can be closed
+    pub done: bool,
+}
+
+impl Parsed {
+    fn chunks(chunks: Vec<StreamChunk>) -> Self {
+        Self { chunks, done: false }
+    }
+}
+
+pub enum StreamParser {
+    Chat(ChatParser),
+    Responses(ResponsesParser),
+    Anthropic(AnthropicParser),
+}
+
+impl StreamParser {
+    pub fn new(provider: Provider) -> Self {
+        match provider {
+            Provider::OpenAi => Self::Chat(ChatParser::default()),
+            Provider::OpenAiResponses => Self::Responses(ResponsesParser::default()),
+            Provider::Anthropic => Self::Anthropic(AnthropicParser::default()),
+        }
+    }
+
+    /// Parse one event. An error is a message to show the user in place of
+    /// the rest of the answer.
+    pub fn parse(&mut self, event: &str, data: &str) -> Result<Parsed, String> {
+        match self {
+            Self::Chat(parser) => parser.parse(data),
+            Self::Responses(parser) => parser.parse(data),
+            Self::Anthropic(parser) => parser.parse(event, data),
+        }
+    }
+
+    /// Thinking blocks that have to be sent back with the tool calls they
+    /// led to, keyed by the first tool call's id
+    pub fn take_thinking(&mut self) -> Option<(String, Vec<Value>)> {
+        match self {
+            Self::Anthropic(parser) => parser.take_thinking(),
+            _ => None,
+        }
+    }
+}
+
+fn unexpected(e: serde_json::Error) -> String {
+    format!("Error: unexpected response from the model API: {}", e)
+}
+
+/// One streamed chunk of a chat completion. Read with our own types rather
+/// than async-openai's, which drop the reasoning fields providers add.
+#[derive(Debug, Deserialize)]
+struct ChatStreamResponse {
+    model: String,
+    #[serde(default)]
+    choices: Vec<ChatStreamChoice>,
+    usage: Option<CompletionUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatStreamChoice {
+    delta: ChatStreamDelta,
+    finish_reason: Option<FinishReason>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatStreamDelta {
+    content: Option<String>,
+    /// DeepSeek, vLLM and other OpenAI-compatible servers
+    reasoning_content: Option<String>,
+    /// OpenRouter
+    reasoning: Option<String>,
+    tool_calls: Option<Vec<ChatCompletionMessageToolCallChunk>>,
+}
+
+impl ChatStreamDelta {
+    fn reasoning(&self) -> Option<&str> {
+        self.reasoning_content
+            .as_deref()
+            .or(self.reasoning.as_deref())
+            .filter(|reasoning| !reasoning.is_empty())
+    }
+}
+
+/// A tool call whose arguments are still streaming in
+#[derive(Debug, Default)]
+struct PartialToolCall {
+    id: String,
+    name: String,
+    arguments: String,
+}
+
+impl PartialToolCall {
+    fn into_chunk(self) -> StreamChunk {
+        StreamChunk::ToolCall {
+            id: self.id,
+            name: self.name,
+            // A call without parameters may stream no arguments at all
+            arguments: if self.arguments.is_empty() { "{}".to_string() } else { self.arguments },
+        }
+    }
+}
+
+/// Chat Completions, as served by OpenAI-compatible servers
+#[derive(Default)]
+pub struct ChatParser {
+    tool_calls: Vec<PartialToolCall>,
+}
+
+impl ChatParser {
+    fn parse(&mut self, data: &str) -> Result<Parsed, String> {
+        if data == "[DONE]" {
+            return Ok(Parsed { chunks: Vec::new(), done: true });
+        }
+        let response: ChatStreamResponse = serde_json::from_str(data).map_err(unexpected)?;
+        let mut chunks = Vec::new();
+
+        // Only the final chunk, which has no choices, carries usage
+        if let Some(usage) = &response.usage {
+            chunks.push(StreamChunk::Usage {
+                model: response.model.clone(),
+                prompt_tokens: usage.prompt_tokens,
+                completion_tokens: usage.completion_tokens,
+            });
+        }
+
+        let Some(choice) = response.choices.first() else {
+            return Ok(Parsed::chunks(chunks));
+        };
+        if let Some(reasoning) = choice.delta.reasoning() {
+            chunks.push(StreamChunk::Reasoning { delta: reasoning.to_string() });
+        }
+        if let Some(delta) = &choice.delta.content {
+            chunks.push(StreamChunk::Text { delta: delta.clone(), done: false });
+        }
+        for delta_tool in choice.delta.tool_calls.iter().flatten() {
+            let index = delta_tool.index as usize;
+            if self.tool_calls.len() <= index {
+                self.tool_calls.resize_with(index + 1, PartialToolCall::default);
+            }
+            let tool_call = &mut self.tool_calls[index];
+            if let Some(id) = &delta_tool.id {
+                tool_call.id = id.clone();
+            }
+            if let Some(function) = &delta_tool.function {
+                if let Some(name) = &function.name {
+                    tool_call.name = name.clone();
+                }
+                if let Some(arguments) = &function.arguments {
+                    tool_call.arguments.push_str(arguments);
+                }
+            }
+        }
+        // The calls are complete once the model says it stopped for them
+        if let Some(FinishReason::ToolCalls) = &choice.finish_reason {
+            chunks.extend(self.tool_calls.drain(..).map(PartialToolCall::into_chunk));
+        }
+        Ok(Parsed::chunks(chunks))
+    }
+}
+
+/// An event from OpenAI's Responses API. Events that don't change what the
+/// user sees, like the `.done` events repeating the deltas, are skipped.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+enum ResponsesEvent {
+    #[serde(rename = "response.output_text.delta")]
+    OutputTextDelta { delta: String },
+    #[serde(rename = "response.reasoning_summary_part.added")]
+    ReasoningSummaryPartAdded,
+    #[serde(rename = "response.reasoning_summary_text.delta")]
+    ReasoningSummaryTextDelta { delta: String },
+    #[serde(rename = "response.output_item.done")]
+    OutputItemDone { item: ResponsesItem },
+    #[serde(rename = "response.completed", alias = "response.incomplete")]
+    Completed { response: ResponsesResponse },
+    #[serde(rename = "response.failed")]
+    Failed { response: ResponsesResponse },
+    #[serde(rename = "error")]
+    Error { message: String },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+enum ResponsesItem {
+    #[serde(rename = "function_call")]
+    FunctionCall { call_id: String, name: String, arguments: String },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponsesResponse {
+    model: String,
+    usage: Option<ResponsesUsage>,
+    error: Option<ResponsesError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponsesUsage {
+    input_tokens: u32,
+    output_tokens: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponsesError {
+    message: String,
+}
+
+/// OpenAI's Responses API, the one OpenAI API that streams reasoning (as
+/// summaries)
+#[derive(Default)]
+pub struct ResponsesParser {
+    reasoned: bool,
+}
+
+impl ResponsesParser {
+    fn parse(&mut self, data: &str) -> Result<Parsed, String> {
+        let chunks = match serde_json::from_str(data).map_err(unexpected)? {
+            ResponsesEvent::OutputTextDelta { delta } => vec![StreamChunk::Text { delta, done: false }],
+            // Each summary part is a paragraph of its own
+            ResponsesEvent::ReasoningSummaryPartAdded if self.reasoned => {
+                vec![StreamChunk::Reasoning { delta: "\n\n".to_string() }]
+            }
+            ResponsesEvent::ReasoningSummaryTextDelta { delta } => {
+                self.reasoned = true;
+                vec![StreamChunk::Reasoning { delta }]
+            }
+            ResponsesEvent::OutputItemDone {
+                item: ResponsesItem::FunctionCall { call_id, name, arguments },
+            } => vec![PartialToolCall { id: call_id, name, arguments }.into_chunk()],
+            ResponsesEvent::Completed { response } => {
+                let chunks = response
+                    .usage
+                    .map(|usage| StreamChunk::Usage {
+                        model: response.model,
+                        prompt_tokens: usage.input_tokens,
+                        completion_tokens: usage.output_tokens,
+                    })
+                    .into_iter()
+                    .collect();
+                return Ok(Parsed { chunks, done: true });
+            }
+            ResponsesEvent::Failed { response } => {
+                let message = response.error.map_or_else(|| "the response failed".to_string(), |e| e.message);
+                return Err(format!("OpenAI API Error: {}", message));
+            }
+            ResponsesEvent::Error { message } => return Err(format!("OpenAI API Error: {}", message)),
+            _ => Vec::new(),
+        };
+        Ok(Parsed::chunks(chunks))
+    }
+}
+
+/// An event from Anthropic's Messages API
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AnthropicEvent {
+    MessageStart { message: AnthropicMessage },
+    ContentBlockStart { index: usize, content_block: Value },
+    ContentBlockDelta { index: usize, delta: AnthropicDelta },
+    ContentBlockStop { index: usize },
+    MessageDelta { usage: AnthropicUsage },
+    MessageStop,
+    Error { error: AnthropicError },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicMessage {
+    model: String,
+    usage: AnthropicUsage,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct AnthropicUsage {
+    input_tokens: u32,
+    cache_creation_input_tokens: u32,
+    cache_read_input_tokens: u32,
+    output_tokens: u32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AnthropicDelta {
+    TextDelta { text: String },
+    ThinkingDelta { thinking: String },
+    SignatureDelta { signature: String },
+    InputJsonDelta { partial_json: String },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicError {
+    message: String,
+}
+
+/// A content block that is still streaming in
+enum AnthropicBlock {
+    Text,
+    /// `thinking` and `redacted_thinking` blocks, kept whole to be sent back
+    Thinking(Value),
+    ToolUse(PartialToolCall),
+}
+
+/// Anthropic's Messages API, whose extended thinking streams as `thinking`
+/// content blocks
+#[derive(Default)]
+pub struct AnthropicParser {
+    model: String,
+    usage: AnthropicUsage,
+    blocks: Vec<(usize, AnthropicBlock)>,
+    thinking: Vec<Value>,
+    tool_ids: Vec<String>,
+}
+
+impl AnthropicParser {
+    fn parse(&mut self, event: &str, data: &str) -> Result<Parsed, String> {
+        if event == "ping" {
+            return Ok(Parsed::default());
+        }
+        let chunks = match serde_json::from_str(data).map_err(unexpected)? {
+            AnthropicEvent::MessageStart { message } => {
+                self.model = message.model;
+                self.usage = message.usage;
+                Vec::new()
+            }
+            AnthropicEvent::ContentBlockStart { index, content_block } => {
+                let block = match content_block["type"].as_str() {
+                    Some("thinking" | "redacted_thinking") => AnthropicBlock::Thinking(content_block),
+                    Some("tool_use") => AnthropicBlock::ToolUse(PartialToolCall {
+                        id: content_block["id"].as_str().unwrap_or_default().to_string(),
+                        name: content_block["name"].as_str().unwrap_or_default().to_string(),
+                        arguments: String::new(),
+                    }),
+                    _ => AnthropicBlock::Text,
+                };
+                // Thinking blocks after the first are paragraphs of their own
+                let chunks = match block {
+                    AnthropicBlock::Thinking(_) if !self.thinking.is_empty() => {
+                        vec![StreamChunk::Reasoning { delta: "\n\n".to_string() }]
+                    }
+                    _ => Vec::new(),
+                };
+                self.blocks.push((index, block));
+                chunks
+            }
+            AnthropicEvent::ContentBlockDelta { index, delta } => match (self.block(index), delta) {
+                (_, AnthropicDelta::TextDelta { text }) => vec![StreamChunk::Text { delta: text, done: false }],
+                (Some(AnthropicBlock::Thinking(block)), AnthropicDelta::ThinkingDelta { thinking }) => {
+                    append(&mut block["thinking"], &thinking);
+                    vec![StreamChunk::Reasoning { delta: thinking }]
+                }
+                (Some(AnthropicBlock::Thinking(block)), AnthropicDelta::SignatureDelta { signature }) => {
+                    append(&mut block["signature"], &signature);
+                    Vec::new()
+                }
+                (Some(AnthropicBlock::ToolUse(tool_call)), AnthropicDelta::InputJsonDelta { partial_json }) => {
+                    tool_call.arguments.push_str(&partial_json);
+                    Vec::new()
+                }
+                _ => Vec::new(),
+            },
+            AnthropicEvent::ContentBlockStop { index } => {
+                let Some(position) = self.blocks.iter().position(|(i, _)| *i == index) else {
+                    return Ok(Parsed::default());
+                };
+                match self.blocks.remove(position).1 {
+                    AnthropicBlock::Text => Vec::new(),
+                    AnthropicBlock::Thinking(block) => {
+                        self.thinking.push(block);
+                        Vec::new()
+                    }
+                    AnthropicBlock::ToolUse(tool_call) => {
+                        self.tool_ids.push(tool_call.id.clone());
+                        vec![tool_call.into_chunk()]
+                    }
+                }
+            }
+            AnthropicEvent::MessageDelta { usage } => {
+                self.usage.output_tokens = usage.output_tokens;
+                Vec::new()
+            }
+            AnthropicEvent::MessageStop => {
+                let usage = StreamChunk::Usage {
+                    model: self.model.clone(),
+                    prompt_tokens: self.usage.input_tokens
+                        + self.usage.cache_creation_input_tokens
+                        + self.usage.cache_read_input_tokens,
+                    completion_tokens: self.usage.output_tokens,
+                };
+                return Ok(Parsed { chunks: vec![usage], done: true });
+            }
+            AnthropicEvent::Error { error } => return Err(format!("Anthropic API Error: {}", error.message)),
+            AnthropicEvent::Other => Vec::new(),
+        };
+        Ok(Parsed::chunks(chunks))
+    }
+
+    fn block(&mut self, index: usize) -> Option<&mut AnthropicBlock> {
+        self.blocks.iter_mut().find(|(i, _)| *i == index).map(|(_, block)| block)
+    }
+
+    /// With thinking on, the API wants an answer's thinking blocks back
+    /// ahead of its tool calls when the tool results are sent
+    fn take_thinking(&mut self) -> Option<(String, Vec<Value>)> {
+        let id = self.tool_ids.first()?.clone();
+        (!self.thinking.is_empty()).then(|| (id, std::mem::take(&mut self.thinking)))
+    }
+}
+
+fn append(field: &mut Value, delta: &str) {
+    let mut text = field.as_str().unwrap_or_default().to_string();
+    text.push_str(delta);
+    *field = Value::String(text);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feed a recorded `text/event-stream` body through a parser, up to the
+    /// event that ends the response
+    fn replay(provider: Provider, sse: &str) -> (StreamParser, Vec<StreamChunk>) {
+        let mut parser = StreamParser::new(provider);
+        let mut chunks = Vec::new();
+        for event in sse.split("\n\n").filter(|event| !event.trim().is_empty()) {
+            let mut name = "message";
+            let mut data = Vec::new();
+            for line in event.lines() {
+                if let Some(value) = line.strip_prefix("event:") {
+                    name = value.trim();
+                } else if let Some(value) = line.strip_prefix("data:") {
+                    data.push(value.trim_start());
+                }
+            }
+            let parsed = parser.parse(name, &data.join("\n")).unwrap();
+            chunks.extend(parsed.chunks);
+            if parsed.done {
+                return (parser, chunks);
+            }
+        }
+        panic!("the stream ended before the response was complete");
+    }
+
+    fn reasoning(chunks: &[StreamChunk]) -> String {
+        chunks
+            .iter()
+            .filter_map(|chunk| match chunk {
+                StreamChunk::Reasoning { delta } => Some(delta.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn text(chunks: &[StreamChunk]) -> String {
+        chunks
+            .iter()
+            .filter_map(|chunk| match chunk {
+                StreamChunk::Text { delta, .. } => Some(delta.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn tool_calls(chunks: &[StreamChunk]) -> Vec<(&str, &str, &str)> {
+        chunks
+            .iter()
+            .filter_map(|chunk| match chunk {
+                StreamChunk::ToolCall { id, name, arguments } => Some((id.as_str(), name.as_str(), arguments.as_str())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn usage(chunks: &[StreamChunk]) -> Option<(&str, u32, u32)> {
+        chunks.iter().find_map(|chunk| match chunk {
+            StreamChunk::Usage { model, prompt_tokens, completion_tokens } => {
+                Some((model.as_str(), *prompt_tokens, *completion_tokens))
+            }
+            _ => None,
+        })
+    }
+
+    /// The position of the first chunk of a kind, to check the order
+    fn first(chunks: &[StreamChunk], kind: fn(&StreamChunk) -> bool) -> usize {
+        chunks.iter().position(kind).unwrap()
+    }
+
+    #[test]
+    fn responses_reasoning_summaries_stream_before_the_answer() {
+        let (_, chunks) = replay(Provider::OpenAiResponses, include_str!("../tests/fixtures/responses_reasoning.sse"));
+
+        assert_eq!(
+            reasoning(&chunks),
+            "**Adding the numbers**\n\nThe user asks for 2 + 2, which is 4.\n\n**Answering briefly**\n\nA one-word answer will do."
+        );
+        assert_eq!(text(&chunks), "2 + 2 = 4.");
+        assert!(
+            first(&chunks, |c| matches!(c, StreamChunk::Reasoning { .. }))
+                < first(&chunks, |c| matches!(c, StreamChunk::Text { .. }))
+        );
+        assert_eq!(usage(&chunks), Some(("gpt-5-mini-2025-08-07", 31, 92)));
+    }
+
+    #[test]
+    fn responses_function_calls_use_the_call_id() {
+        let (_, chunks) = replay(Provider::OpenAiResponses, include_str!("../tests/fixtures/responses_tool_call.sse"));
+
+        assert_eq!(reasoning(&chunks), "**Reading the manifest**\n\nI should look at Cargo.toml first.");
+        assert_eq!(
+            tool_calls(&chunks),
+            [("call_9f2kQ0bX1cVd7Lh3", "read_file", r#"{"filename":"Cargo.toml"}"#)]
+        );
+        assert_eq!(usage(&chunks), Some(("gpt-5-mini-2025-08-07", 1320, 211)));
+    }
+
+    #[test]
+    fn responses_failures_are_reported() {
+        let mut parser = StreamParser::new(Provider::OpenAiResponses);
+        let failed = r#"{"type":"response.failed","response":{"model":"gpt-5-mini","usage":null,"error":{"code":"server_error","message":"The server had an error processing your request."}}}"#;
+
+        assert_eq!(
+            parser.parse("response.failed", failed).unwrap_err(),
+            "OpenAI API Error: The server had an error processing your request."
+        );
+    }
+
+    #[test]
+    fn anthropic_thinking_streams_before_the_answer() {
+        let (mut parser, chunks) =
+            replay(Provider::Anthropic, include_str!("../tests/fixtures/anthropic_thinking.sse"));
+
+        assert_eq!(reasoning(&chunks), "The user wants 27 * 453. 27 * 453 = 12231.");
+        assert_eq!(text(&chunks), "27 × 453 = **12,231**");
+        assert!(
+            first(&chunks, |c| matches!(c, StreamChunk::Reasoning { .. }))
+                < first(&chunks, |c| matches!(c, StreamChunk::Text { .. }))
+        );
+        assert_eq!(usage(&chunks), Some(("claude-sonnet-4-20250514", 46, 98)));
+        // Nothing to send back without tool calls
+        assert!(parser.take_thinking().is_none());
+    }
+
+    #[test]
+    fn anthropic_tool_use_keeps_the_signed_thinking_block() {
+        let (mut parser, chunks) =
+            replay(Provider::Anthropic, include_str!("../tests/fixtures/anthropic_tool_use.sse"));
+
+        assert_eq!(reasoning(&chunks), "I need to see the manifest before answering.");
+        assert_eq!(text(&chunks), "Let me check the manifest.");
+        assert_eq!(
+            tool_calls(&chunks),
+            [
+                ("toolu_01A09q90qw90lq917835lq9", "read_file", r#"{"filename": "Cargo.toml"}"#),
+                ("toolu_01B7s2kPz3XnVbQmRt4wYc8", "git_status", "{}"),
+            ]
+        );
+        assert_eq!(usage(&chunks), Some(("claude-sonnet-4-20250514", 1914 + 1200, 139)));
+
+        let (id, blocks) = parser.take_thinking().unwrap();
+        assert_eq!(id, "toolu_01A09q90qw90lq917835lq9");
+        assert_eq!(
+            blocks,
+            [serde_json::json!({
+                "type": "thinking",
+                "thinking": "I need to see the manifest before answering.",
+                "signature": "EqQBCgIYAhIM1gbcDa9GJwZA2b3hGgxBdjrkzLoky3dl1pkiMOYds"
+            })]
+        );
+    }
+
+    #[test]
+    fn anthropic_errors_are_reported() {
+        let mut parser = StreamParser::new(Provider::Anthropic);
+        let error = r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#;
+
+        assert_eq!(parser.parse("error", error).unwrap_err(), "Anthropic API Error: Overloaded");
+    }
+
+    #[test]
+    fn chat_completions_reasoning_content_streams_before_the_answer() {
+        let (_, chunks) = replay(Provider::OpenAi, include_str!("../tests/fixtures/chat_reasoning.sse"));
+
+        assert_eq!(reasoning(&chunks), "Two plus two is four.");
+        assert_eq!(text(&chunks), "4");
+        assert_eq!(tool_calls(&chunks), [("call_0", "git_status", "{}")]);
+        assert_eq!(usage(&chunks), Some(("deepseek-reasoner", 18, 25)));
+    }
+}
@@ -1,4 +1,6 @@
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatMessage {
@@ -38,16 +40,93 @@ pub enum ClientMessage {
     ToolResult {
         id: String,
         content: String,
+        /// A message the user typed while the tool ran, added to the
+        /// conversation after the result
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        steer: Option<String>,
     },
+    /// Stop the turn in progress; the server answers with `StreamChunk::Cancelled`
+    #[serde(rename = "cancel")]
+    Cancel,
 }
 
 /// Request from client to server
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClientChatRequest {
     pub messages: Vec<ChatMessage>,
+    /// Details about the client's environment to put in front of the model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<SessionContext>,
+    /// Model to use instead of the server's default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// How hard a reasoning model should think; the model's default when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<ReasoningEffort>,
     // Future: tool_ids, context_window, etc.
 }
 
+/// How much a reasoning model thinks before answering, named as the
+/// OpenAI API names it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReasoningEffort {
+    Minimal,
+    Low,
+    Medium,
+    High,
+}
+
+impl ReasoningEffort {
+    pub const ALL: [ReasoningEffort; 4] = [Self::Minimal, Self::Low, Self::Medium, Self::High];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Minimal => "minimal",
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+}
+
+impl fmt::Display for ReasoningEffort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ReasoningEffort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|effort| effort.as_str() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|effort| effort.as_str()).collect();
+                format!("Unknown reasoning effort '{}' (one of {})", s, names.join(", "))
+            })
+    }
+}
+
+/// Workspace state gathered by the client when a turn starts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionContext {
+    pub cwd: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git: Option<GitContext>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitContext {
+    pub branch: String,
+    /// Whether there are uncommitted changes; `None` until the client has
+    /// checked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dirty: Option<bool>,
+}
+
 /// Internal request from server to LLM - kept for future use when
 /// server needs to make direct LLM calls with model selection
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -71,6 +150,12 @@ pub enum StreamChunk {
         delta: String,
         done: bool,
     },
+    /// The model's reasoning or thinking, streamed before its answer. Not
+    /// part of the conversation sent back to the model.
+    #[serde(rename = "reasoning")]
+    Reasoning {
+        delta: String,
+    },
     #[serde(rename = "tool_call")]
     ToolCall {
         id: String,
@@ -82,4 +167,20 @@ pub enum StreamChunk {
         id: String,
         content: String,
     },
+    /// Token counts for one completed model request
+    #[serde(rename = "usage")]
+    Usage {
+        model: String,
+        prompt_tokens: u32,
+        completion_tokens: u32,
+    },
+    /// Answers `ClientMessage::Cancel`; nothing more is sent for the
+    /// cancelled turn
+    #[serde(rename = "cancelled")]
+    Cancelled,
+    /// The request failed; sent in place of the final `done` text chunk
+    #[serde(rename = "error")]
+    Error {
+        message: String,
+    },
 }
\ No newline at end of file